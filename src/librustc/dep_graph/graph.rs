@@ -47,6 +47,20 @@ impl DepNodeIndex {
     const INVALID: DepNodeIndex = DepNodeIndex::MAX;
 }
 
+/// Why `try_mark_green` failed for a node. Only recorded with
+/// `-Z incremental-explain`.
+#[derive(Copy, Clone, Debug)]
+enum NotGreen {
+    /// The node did not exist in the previous session.
+    New,
+    /// The node is an input that does not exist anymore.
+    DeletedInput,
+    /// This dependency was red, i.e. it was recomputed and its result changed.
+    RedDependency(DepNode),
+    /// This dependency does not exist anymore or could not be forced.
+    LostDependency(DepNode),
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum DepNodeColor {
     Red,
@@ -85,6 +99,10 @@ struct DepGraphData {
 
     // Used for testing, only populated when -Zquery-dep-graph is specified.
     loaded_from_cache: Lock<FxHashMap<DepNodeIndex, bool>>,
+
+    // The first reason why each node could not be marked green, only
+    // populated when -Zincremental-explain is specified.
+    not_green: Lock<FxHashMap<DepNode, NotGreen>>,
 }
 
 impl DepGraph {
@@ -106,6 +124,7 @@ impl DepGraph {
                 previous: prev_graph,
                 colors: Lock::new(DepNodeColorMap::new(prev_graph_node_count)),
                 loaded_from_cache: Lock::new(Default::default()),
+                not_green: Lock::new(Default::default()),
             })),
             fingerprints: Lrc::new(Lock::new(fingerprints)),
         }
@@ -570,6 +589,7 @@ impl DepGraph {
             // executed.
             debug_assert!(dep_node.extract_def_id(tcx).is_none());
             debug!("try_mark_green({:?}) - END - DepNode is deleted input", dep_node);
+            self.record_not_green(tcx, dep_node, NotGreen::DeletedInput);
             return None;
         }
 
@@ -586,6 +606,7 @@ impl DepGraph {
                 // so we cannot mark it as green.
                 debug!("try_mark_green({:?}) - END - DepNode does not exist in \
                         current compilation session anymore", dep_node);
+                self.record_not_green(tcx, dep_node, NotGreen::New);
                 return None
             }
         };
@@ -617,6 +638,8 @@ impl DepGraph {
                             immediately red",
                             dep_node,
                             data.previous.index_to_node(dep_dep_node_index));
+                    let dep_dep_node = data.previous.index_to_node(dep_dep_node_index);
+                    self.record_not_green(tcx, dep_node, NotGreen::RedDependency(dep_dep_node));
                     return None
                 }
                 None => {
@@ -643,6 +666,9 @@ impl DepGraph {
                                 if dep_node.extract_def_id(tcx).is_none() {
                                     // If the node does not exist anymore, we
                                     // just fail to mark green.
+                                    self.record_not_green(tcx,
+                                                          dep_node,
+                                                          NotGreen::LostDependency(*dep_dep_node));
                                     return None
                                 } else {
                                     // If the node does exist, it should have
@@ -677,6 +703,9 @@ impl DepGraph {
                                         dependency {:?} was red after forcing",
                                        dep_node,
                                        dep_dep_node);
+                                self.record_not_green(tcx,
+                                                      dep_node,
+                                                      NotGreen::RedDependency(*dep_dep_node));
                                 return None
                             }
                             None => {
@@ -695,6 +724,9 @@ impl DepGraph {
                         // The DepNode could not be forced.
                         debug!("try_mark_green({:?}) - END - dependency {:?} \
                                 could not be forced", dep_node, dep_dep_node);
+                        self.record_not_green(tcx,
+                                              dep_node,
+                                              NotGreen::LostDependency(*dep_dep_node));
                         return None
                     }
                 }
@@ -778,6 +810,64 @@ impl DepGraph {
         Some(dep_node_index)
     }
 
+    fn record_not_green(&self, tcx: TyCtxt<'_, '_, '_>, dep_node: &DepNode, reason: NotGreen) {
+        if tcx.sess.opts.debugging_opts.incremental_explain {
+            let data = self.data.as_ref().unwrap();
+            data.not_green.borrow_mut().entry(*dep_node).or_insert(reason);
+        }
+    }
+
+    /// Explains why `dep_node` could not be marked green, one line per link
+    /// of the chain of red dependencies that led to it, ending with the node
+    /// that was new or deleted or the input that changed. This is only known
+    /// with `-Z incremental-explain`.
+    pub fn explain_not_green(&self, tcx: TyCtxt<'_, '_, '_>, dep_node: &DepNode) -> Vec<String> {
+        let data = self.data.as_ref().unwrap();
+        let not_green = data.not_green.borrow();
+
+        let mut explanation = Vec::new();
+        let mut node = *dep_node;
+        loop {
+            let reason = match not_green.get(&node) {
+                Some(&reason) => reason,
+                None if node == *dep_node => {
+                    explanation.push(format!("`{:?}` was not checked", node));
+                    break
+                }
+                None if node.kind.is_input() => {
+                    explanation.push(format!("`{:?}` changed{}", node, def_location(tcx, &node)));
+                    break
+                }
+                None => {
+                    explanation.push(format!("`{:?}` was recomputed and changed", node));
+                    break
+                }
+            };
+            match reason {
+                NotGreen::New => {
+                    explanation.push(format!("`{:?}` is new{}", node, def_location(tcx, &node)));
+                    break
+                }
+                NotGreen::DeletedInput => {
+                    explanation.push(format!("`{:?}` does not exist anymore", node));
+                    break
+                }
+                NotGreen::RedDependency(dependency) => {
+                    explanation.push(format!("`{:?}` depends on `{:?}`, which changed",
+                                             node, dependency));
+                    node = dependency;
+                }
+                NotGreen::LostDependency(dependency) => {
+                    explanation.push(format!("`{:?}` depends on `{:?}`, which does not \
+                                              exist anymore",
+                                             node, dependency));
+                    break
+                }
+            }
+        }
+        explanation
+    }
+
     // Returns true if the given node has been marked as green during the
     // current compilation session. Used in various assertions
     pub fn is_green(&self, dep_node: &DepNode) -> bool {
@@ -842,6 +932,17 @@ impl DepGraph {
     }
 }
 
+/// Where the item a node is about is defined, if it is a local item.
+fn def_location(tcx: TyCtxt<'_, '_, '_>, dep_node: &DepNode) -> String {
+    let span = dep_node.extract_def_id(tcx)
+                       .and_then(|def_id| tcx.hir.as_local_node_id(def_id))
+                       .map(|node_id| tcx.dep_graph.with_ignore(|| tcx.hir.span(node_id)));
+    match span {
+        Some(span) => format!(" (defined at {})", tcx.sess.source_map().span_to_string(span)),
+        None => String::new(),
+    }
+}
+
 /// A "work product" is an intermediate result that we save into the
 /// incremental directory for later re-use. The primary example are
 /// the object files that we save for each partition at code
@@ -885,6 +986,7 @@ pub enum WorkProductFileKind {
     Object,
     Bytecode,
     BytecodeCompressed,
+    DwarfObject,
 }
 
 pub(super) struct CurrentDepGraph {
@@ -354,7 +354,7 @@ for mir::ProjectionElem<'gcx, V, T>
     }
 }
 
-impl_stable_hash_for!(struct mir::SourceScopeData { span, parent_scope });
+impl_stable_hash_for!(struct mir::SourceScopeData { span, parent_scope, inlined });
 impl_stable_hash_for!(struct mir::SourceScopeLocalData {
     lint_root, safety
 });
@@ -2072,6 +2072,10 @@ newtype_index! {
 pub struct SourceScopeData {
     pub span: Span,
     pub parent_scope: Option<SourceScope>,
+
+    /// If this scope is the root of a function body inlined by the MIR
+    /// inliner, the inlined function and the span of the call site.
+    pub inlined: Option<(DefId, Span)>,
 }
 
 #[derive(Clone, Debug, RustcEncodable, RustcDecodable)]
@@ -210,6 +210,10 @@ pub struct Stats {
     pub llvm_insns: FxHashMap<String, usize>,
     // (ident, llvm-instructions)
     pub fn_stats: Vec<(String, usize)>,
+    // Debuginfo of composite types, keyed by the path of their definition.
+    pub debuginfo_types: FxHashMap<String, DebuginfoTypeStats>,
+    // LLVM instructions per symbol, for `-Z mono-item-report`.
+    pub symbol_insns: FxHashMap<String, usize>,
 }
 
 impl_stable_hash_for!(struct self::Stats {
@@ -221,7 +225,9 @@ impl_stable_hash_for!(struct self::Stats {
     n_closures,
     n_llvm_insns,
     llvm_insns,
-    fn_stats
+    fn_stats,
+    debuginfo_types,
+    symbol_insns
 });
 
 impl Stats {
@@ -238,6 +244,66 @@ impl Stats {
             *self.llvm_insns.entry(k).or_insert(0) += v;
         }
         self.fn_stats.extend(stats.fn_stats);
+        for (k, v) in stats.debuginfo_types {
+            self.debuginfo_types.entry(k).or_insert_with(Default::default).extend(v);
+        }
+        for (k, v) in stats.symbol_insns {
+            *self.symbol_insns.entry(k).or_insert(0) += v;
+        }
+    }
+}
+
+/// The debuginfo emitted for all the instances of one type definition, as
+/// recorded for `-Z debuginfo-stats`.
+#[derive(Clone, Default)]
+pub struct DebuginfoTypeStats {
+    /// The crate the type is defined in.
+    pub krate: String,
+    /// The path of the module the type is defined in.
+    pub module: String,
+    /// Number of instances, e.g. one per set of generic arguments, counted
+    /// once for every codegen unit that emits their debuginfo, since each of
+    /// them gets its own copy.
+    pub emitted_instances: usize,
+    /// Number of member, field and variant entries across all instances.
+    pub members: usize,
+    /// Bytes taken up by the names of the instances and their members.
+    pub name_bytes: usize,
+}
+
+impl_stable_hash_for!(struct self::DebuginfoTypeStats {
+    krate,
+    module,
+    emitted_instances,
+    members,
+    name_bytes
+});
+
+impl DebuginfoTypeStats {
+    pub fn extend(&mut self, stats: DebuginfoTypeStats) {
+        if self.krate.is_empty() {
+            self.krate = stats.krate;
+            self.module = stats.module;
+        }
+        self.emitted_instances += stats.emitted_instances;
+        self.members += stats.members;
+        self.name_bytes += stats.name_bytes;
+    }
+
+    /// A rough estimate of the bytes emitted into the object files: one
+    /// `.debug_info` entry per instance and per member, with the attributes
+    /// LLVM usually gives them, plus the names and their terminators in
+    /// `.debug_str`. Equal names are only emitted once per codegen unit, so
+    /// the names are overestimated.
+    pub fn estimated_bytes(&self) -> usize {
+        // Abbreviation code, name, size, alignment, file and line.
+        const INSTANCE_ENTRY_BYTES: usize = 1 + 4 + 2 + 1 + 1 + 2;
+        // Abbreviation code, name, type, alignment and offset.
+        const MEMBER_ENTRY_BYTES: usize = 1 + 4 + 4 + 1 + 2;
+
+        self.emitted_instances * (INSTANCE_ENTRY_BYTES + 1) +
+            self.members * (MEMBER_ENTRY_BYTES + 1) +
+            self.name_bytes
     }
 }
 
@@ -347,12 +347,16 @@ macro_rules! make_mir_visitor {
                 let SourceScopeData {
                     ref $($mutability)* span,
                     ref $($mutability)* parent_scope,
+                    ref $($mutability)* inlined,
                 } = *scope_data;
 
                 self.visit_span(span);
                 if let Some(ref $($mutability)* parent_scope) = *parent_scope {
                     self.visit_source_scope(parent_scope);
                 }
+                if let Some((_, ref $($mutability)* call_span)) = *inlined {
+                    self.visit_span(call_span);
+                }
             }
 
             fn super_statement(&mut self,
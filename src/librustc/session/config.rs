@@ -127,6 +127,26 @@ pub enum DebugInfo {
     Full,
 }
 
+/// Where the DWARF debug info ends up, selected with `-C split-debuginfo`.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum SplitDebuginfo {
+    /// In the object files, and from there in the linked output.
+    Off,
+    /// In a `.dwo` file next to each object file, which only keeps a skeleton
+    /// unit pointing to it. The `.dwo` files are kept next to the output.
+    Unpacked,
+    /// Like `Unpacked`, but the `.dwo` files are packaged into a `.dwp` file
+    /// next to each linked output with `dwp` afterwards.
+    Packed,
+}
+
+/// The format of `-Z mono-item-report`.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum MonoItemReportFormat {
+    Text,
+    Json,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord, RustcEncodable, RustcDecodable)]
 pub enum OutputType {
     Bitcode,
@@ -214,6 +234,7 @@ pub enum ErrorOutputType {
     HumanReadable(ColorConfig),
     Json(bool),
     Short(ColorConfig),
+    Sarif,
 }
 
 impl Default for ErrorOutputType {
@@ -419,6 +440,10 @@ top_level_options!(
         remap_path_prefix: Vec<(PathBuf, PathBuf)> [UNTRACKED],
 
         edition: Edition [TRACKED],
+
+        // Apply the machine-applicable suggestions of the emitted diagnostics
+        // to the source files, see `--apply-suggestions`.
+        apply_suggestions: Option<ApplySuggestions> [UNTRACKED],
     }
 );
 
@@ -478,6 +503,23 @@ impl BorrowckMode {
     }
 }
 
+/// What `--apply-suggestions` does with the machine-applicable suggestions of
+/// the emitted diagnostics.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ApplySuggestions {
+    pub mode: ApplySuggestionsMode,
+    /// Recompile with the suggestions applied until no new ones come up.
+    pub fixpoint: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ApplySuggestionsMode {
+    /// Rewrite the source files.
+    InPlace,
+    /// Print a unified diff to stdout and leave the source files alone.
+    Diff,
+}
+
 pub enum Input {
     /// Load source from file
     File(PathBuf),
@@ -614,6 +656,7 @@ impl Default for Options {
             cli_forced_thinlto_off: false,
             remap_path_prefix: Vec::new(),
             edition: DEFAULT_EDITION,
+            apply_suggestions: None,
         }
     }
 }
@@ -657,6 +700,21 @@ impl Options {
             }
         }
     }
+
+    /// Whether instantiations that upstream crates list among their exported
+    /// symbols are linked against instead of being instantiated again. By
+    /// default this goes along with sharing our own instantiations. It can be
+    /// turned on for optimized builds separately: unlike sharing our own
+    /// instantiations it keeps nothing from being internalized, but upstream
+    /// instantiations can't be inlined, and may have been built with less
+    /// optimization.
+    #[inline]
+    pub fn reuse_upstream_generics(&self) -> bool {
+        match self.debugging_opts.reuse_upstream_generics {
+            Some(setting) => setting,
+            None => self.share_generics(),
+        }
+    }
 }
 
 // The type of entry function, so
@@ -805,6 +863,10 @@ macro_rules! options {
             Some("either `panic` or `abort`");
         pub const parse_relro_level: Option<&'static str> =
             Some("one of: `full`, `partial`, or `off`");
+        pub const parse_split_debuginfo: Option<&'static str> =
+            Some("one of: `off`, `unpacked`, or `packed`");
+        pub const parse_mono_item_report: Option<&'static str> =
+            Some("either `text` or `json`");
         pub const parse_sanitizer: Option<&'static str> =
             Some("one of: `address`, `leak`, `memory` or `thread`");
         pub const parse_linker_flavor: Option<&'static str> =
@@ -823,7 +885,8 @@ macro_rules! options {
 
     #[allow(dead_code)]
     mod $mod_set {
-        use super::{$struct_name, Passes, Sanitizer, LtoCli, CrossLangLto};
+        use super::{$struct_name, Passes, Sanitizer, LtoCli, CrossLangLto, SplitDebuginfo,
+                    MonoItemReportFormat};
         use rustc_target::spec::{LinkerFlavor, PanicStrategy, RelroLevel};
         use std::path::PathBuf;
 
@@ -971,6 +1034,26 @@ macro_rules! options {
             true
         }
 
+        fn parse_split_debuginfo(slot: &mut SplitDebuginfo, v: Option<&str>) -> bool {
+            match v {
+                Some("off") => *slot = SplitDebuginfo::Off,
+                Some("unpacked") => *slot = SplitDebuginfo::Unpacked,
+                Some("packed") => *slot = SplitDebuginfo::Packed,
+                _ => return false,
+            }
+            true
+        }
+
+        fn parse_mono_item_report(slot: &mut Option<MonoItemReportFormat>,
+                                  v: Option<&str>) -> bool {
+            match v {
+                Some("text") => *slot = Some(MonoItemReportFormat::Text),
+                Some("json") => *slot = Some(MonoItemReportFormat::Json),
+                _ => return false,
+            }
+            true
+        }
+
         fn parse_sanitizer(slote: &mut Option<Sanitizer>, v: Option<&str>) -> bool {
             match v {
                 Some("address") => *slote = Some(Sanitizer::Address),
@@ -1122,6 +1205,9 @@ options! {CodegenOptions, CodegenSetter, basic_codegen_options,
     debuginfo: Option<usize> = (None, parse_opt_uint, [TRACKED],
         "debug info emission level, 0 = no debug info, 1 = line tables only, \
          2 = full debug info with variable and type information"),
+    split_debuginfo: SplitDebuginfo = (SplitDebuginfo::Off, parse_split_debuginfo, [TRACKED],
+        "move the debug info out of the object files into `.dwo` files (`unpacked`), \
+         and package those into a `.dwp` file next to the output (`packed`)"),
     opt_level: Option<String> = (None, parse_opt_string, [TRACKED],
         "optimize with possible levels 0-3, s, or z"),
     force_frame_pointers: Option<bool> = (None, parse_opt_bool, [TRACKED],
@@ -1174,6 +1260,16 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "The output of `-Z codegen-stats` might not be accurate when incremental \
          compilation is enabled")],
         "gather codegen statistics"),
+    debuginfo_stats: bool = (false, parse_bool, [UNTRACKED_WITH_WARNING(true,
+        "The output of `-Z debuginfo-stats` only covers re-codegened modules when \
+         incremental compilation is enabled")],
+        "print an estimate of how much debuginfo each crate, module and type contributes"),
+    mono_item_report: Option<MonoItemReportFormat> = (None, parse_mono_item_report,
+        [UNTRACKED_WITH_WARNING(true,
+        "The output of `-Z mono-item-report` misses the LLVM instruction counts of \
+         re-used modules when incremental compilation is enabled")],
+        "print the object code size and LLVM instructions of every mono item, \
+         grouped by definition (`text` or `json`)"),
     asm_comments: bool = (false, parse_bool, [TRACKED],
         "generate comments into the assembly (may change behavior)"),
     verify_llvm_ir: bool = (false, parse_bool, [TRACKED],
@@ -1198,6 +1294,11 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "print the pre-expansion AST as JSON and halt"),
     ls: bool = (false, parse_bool, [UNTRACKED],
         "list the symbols defined by a library crate"),
+    ls_json: bool = (false, parse_bool, [UNTRACKED],
+        "with -Z ls, print the crate metadata as JSON"),
+    explain_crate_search: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "print every search path and candidate file considered when looking for the \
+         given crate, and why each was accepted or rejected"),
     save_analysis: bool = (false, parse_bool, [UNTRACKED],
         "write syntax and type analysis (in JSON format) information, in \
          addition to normal output"),
@@ -1232,10 +1333,24 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "enable incremental compilation support for queries (experimental)"),
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
         "print high-level information about incremental reuse (or the lack thereof)"),
+    incremental_explain: bool = (false, parse_bool, [UNTRACKED],
+        "print why codegen units could not be reused and queries had to be \
+         re-executed, down to the input that changed"),
     incremental_dump_hash: bool = (false, parse_bool, [UNTRACKED],
         "dump hash information in textual format to stdout"),
     incremental_verify_ich: bool = (false, parse_bool, [UNTRACKED],
         "verify incr. comp. hashes of green query instances"),
+    incremental_shared_cache: Option<String> = (None, parse_opt_string, [UNTRACKED],
+        "share finalized incremental sessions between incremental directories \
+         through this directory"),
+    incremental_shared_cache_size: usize = (4096, parse_uint, [UNTRACKED],
+        "the size the incremental shared cache is trimmed to, in MiB (default: 4096)"),
+    incremental_size_limit: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "delete the least recently used incremental session directories once the \
+         incremental directory is bigger than this, in MiB"),
+    incremental_dump: bool = (false, parse_bool, [UNTRACKED],
+        "print what the last incremental session directory of the crate contains, \
+         with sizes, and stop without compiling"),
     incremental_ignore_spans: bool = (false, parse_bool, [UNTRACKED],
         "ignore spans during ICH computation -- used for testing"),
     dump_dep_graph: bool = (false, parse_bool, [UNTRACKED],
@@ -1270,6 +1385,12 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "print the result of the monomorphization collection pass"),
     mir_opt_level: usize = (1, parse_uint, [TRACKED],
         "set the MIR optimization level (0-3, default: 1)"),
+    inline_mir: Option<bool> = (None, parse_opt_bool, [TRACKED],
+        "enable MIR inlining (default: yes with -Z mir-opt-level=2 or higher)"),
+    inline_mir_threshold: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "the maximum cost of a function to be inlined into MIR (default: 50)"),
+    inline_mir_hint_threshold: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "the maximum cost of an `#[inline]` function to be inlined into MIR (default: 100)"),
     mutable_noalias: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "emit noalias metadata for mutable references (default: yes on LLVM >= 6)"),
     arg_align_attributes: bool = (false, parse_bool, [TRACKED],
@@ -1282,6 +1403,8 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "in addition to `.mir` files, create graphviz `.dot` files"),
     dump_mir_exclude_pass_number: bool = (false, parse_bool, [UNTRACKED],
         "if set, exclude the pass number when dumping MIR (used in tests)"),
+    validate_mir: bool = (false, parse_bool, [UNTRACKED],
+        "check the invariants of the MIR after every MIR pass and ICE on violations"),
     mir_emit_retag: bool = (false, parse_bool, [TRACKED],
         "emit Retagging MIR statements, interpreted e.g. by miri; implies -Zmir-opt-level=0"),
     perf_stats: bool = (false, parse_bool, [UNTRACKED],
@@ -1367,6 +1490,10 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "tell the linker to strip debuginfo when building without debuginfo enabled."),
     share_generics: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "make the current crate share its generic instantiations"),
+    reuse_upstream_generics: Option<bool> = (None, parse_opt_bool, [TRACKED],
+        "link against the generic instantiations shared by upstream crates instead of \
+         instantiating them again, also in optimized builds (default: same as \
+         `-Z share-generics`)"),
     chalk: bool = (false, parse_bool, [TRACKED],
         "enable the experimental Chalk-based trait solving engine"),
     cross_lang_lto: CrossLangLto = (CrossLangLto::Disabled, parse_cross_lang_lto, [TRACKED],
@@ -1381,8 +1508,18 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "run the self profiler"),
     profile_json: bool = (false, parse_bool, [UNTRACKED],
         "output a json file with profiler results"),
+    profile_trace: bool = (false, parse_bool, [UNTRACKED],
+        "output a Chrome trace-event file with the timeline of all queries \
+         (with -Z self-profile)"),
+    query_stats: bool = (false, parse_bool, [UNTRACKED],
+        "print invocations, cache hits, time and memory of every query"),
     emit_stack_sizes: bool = (false, parse_bool, [UNTRACKED],
         "emits a section containing stack size metadata"),
+    compress_debug_sections: bool = (false, parse_bool, [TRACKED],
+        "compress the DWARF sections of object files with zlib"),
+    polymorphize: bool = (false, parse_bool, [TRACKED],
+        "share the instances of generic functions that don't depend on some of \
+         their type parameters"),
     plt: Option<bool> = (None, parse_opt_bool, [TRACKED],
           "whether to use the PLT when calling into shared libraries;
           only has effect for PIC code on systems with ELF binaries
@@ -1718,7 +1855,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "",
             "error-format",
             "How errors and other messages are produced",
-            "human|json|short",
+            "human|json|short|sarif",
         ),
         opt::opt_s(
             "",
@@ -1750,6 +1887,14 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "Remap source names in all output (compiler messages and output files)",
             "FROM=TO",
         ),
+        opt::opt(
+            "",
+            "apply-suggestions",
+            "Apply the machine-applicable suggestions of the emitted diagnostics;
+                  `in-place` rewrites the source files, `diff` prints a unified diff.
+                  With `,fixpoint` the crate is recompiled until no suggestions are left.",
+            "in-place|diff[,fixpoint]",
+        ),
     ]);
     opts
 }
@@ -1874,13 +2019,14 @@ pub fn build_session_options_and_crate_config(
             Some("json") => ErrorOutputType::Json(false),
             Some("pretty-json") => ErrorOutputType::Json(true),
             Some("short") => ErrorOutputType::Short(color),
+            Some("sarif") => ErrorOutputType::Sarif,
             None => ErrorOutputType::HumanReadable(color),
 
             Some(arg) => early_error(
                 ErrorOutputType::HumanReadable(color),
                 &format!(
-                    "argument for --error-format must be `human`, `json` or \
-                     `short` (instead was `{}`)",
+                    "argument for --error-format must be `human`, `json`, \
+                     `short` or `sarif` (instead was `{}`)",
                     arg
                 ),
             ),
@@ -1904,6 +2050,13 @@ pub fn build_session_options_and_crate_config(
         );
     }
 
+    if !debugging_opts.unstable_options && error_format == ErrorOutputType::Sarif {
+        early_error(
+            ErrorOutputType::HumanReadable(color),
+            "--error-format=sarif is unstable",
+        );
+    }
+
     if debugging_opts.pgo_gen.is_some() && !debugging_opts.pgo_use.is_empty() {
         early_error(
             error_format,
@@ -2206,6 +2359,14 @@ pub fn build_session_options_and_crate_config(
         );
     }
 
+    if cg.split_debuginfo != SplitDebuginfo::Off && !is_unstable_enabled {
+        early_error(
+            error_format,
+            "the `-Z unstable-options` flag must also be passed to \
+             enable `-C split-debuginfo`",
+        );
+    }
+
     let mut externs: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
     for arg in &matches.opt_strs("extern") {
         let mut parts = arg.splitn(2, '=');
@@ -2228,6 +2389,28 @@ pub fn build_session_options_and_crate_config(
 
     let crate_name = matches.opt_str("crate-name");
 
+    let apply_suggestions = if matches.opts_present(&["apply-suggestions".to_owned()]) {
+        matches.opt_str("apply-suggestions").map(|arg| {
+            let (mode, fixpoint) = match arg.split(',').collect::<Vec<_>>()[..] {
+                [mode] => (mode, false),
+                [mode, "fixpoint"] => (mode, true),
+                _ => ("", false),
+            };
+            let mode = match mode {
+                "in-place" => ApplySuggestionsMode::InPlace,
+                "diff" => ApplySuggestionsMode::Diff,
+                _ => early_error(
+                    error_format,
+                    &format!("argument for --apply-suggestions must be `in-place` or `diff`, \
+                              optionally followed by `,fixpoint` (instead was `{}`)", arg),
+                ),
+            };
+            ApplySuggestions { mode, fixpoint }
+        })
+    } else {
+        None
+    };
+
     let remap_path_prefix = matches
         .opt_strs("remap-path-prefix")
         .into_iter()
@@ -2275,6 +2458,7 @@ pub fn build_session_options_and_crate_config(
             cli_forced_thinlto_off: disable_thinlto,
             remap_path_prefix,
             edition,
+            apply_suggestions,
         },
         cfg,
     )
@@ -2403,7 +2587,7 @@ mod dep_tracking {
     use std::path::PathBuf;
     use std::collections::hash_map::DefaultHasher;
     use super::{CrateType, DebugInfo, ErrorOutputType, OptLevel, OutputTypes,
-                Passes, Sanitizer, LtoCli, CrossLangLto};
+                Passes, Sanitizer, LtoCli, CrossLangLto, SplitDebuginfo};
     use syntax::feature_gate::UnstableFeatures;
     use rustc_target::spec::{PanicStrategy, RelroLevel, TargetTriple};
     use syntax::edition::Edition;
@@ -2460,6 +2644,7 @@ mod dep_tracking {
     impl_dep_tracking_hash_via_hash!(OptLevel);
     impl_dep_tracking_hash_via_hash!(LtoCli);
     impl_dep_tracking_hash_via_hash!(DebugInfo);
+    impl_dep_tracking_hash_via_hash!(SplitDebuginfo);
     impl_dep_tracking_hash_via_hash!(UnstableFeatures);
     impl_dep_tracking_hash_via_hash!(OutputTypes);
     impl_dep_tracking_hash_via_hash!(cstore::NativeLibraryKind);
@@ -2537,7 +2722,7 @@ mod tests {
     use std::collections::{BTreeMap, BTreeSet};
     use std::iter::FromIterator;
     use std::path::PathBuf;
-    use super::{Externs, OutputType, OutputTypes};
+    use super::{Externs, MonoItemReportFormat, OutputType, OutputTypes};
     use rustc_target::spec::{PanicStrategy, RelroLevel};
     use syntax::symbol::Symbol;
     use syntax::edition::{Edition, DEFAULT_EDITION};
@@ -3084,6 +3269,10 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.codegen_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.debuginfo_stats = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.mono_item_report = Some(MonoItemReportFormat::Text);
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.borrowck_stats = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.meta_stats = true;
@@ -3136,12 +3325,26 @@ mod tests {
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
         opts.debugging_opts.dump_mir_graphviz = true;
         assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
+        opts.debugging_opts.validate_mir = true;
+        assert_eq!(reference.dep_tracking_hash(), opts.dep_tracking_hash());
 
         // Make sure changing a [TRACKED] option changes the hash
         opts = reference.clone();
         opts.debugging_opts.asm_comments = true;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
 
+        opts = reference.clone();
+        opts.debugging_opts.compress_debug_sections = true;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.polymorphize = true;
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.reuse_upstream_generics = Some(true);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
         opts = reference.clone();
         opts.debugging_opts.verify_llvm_ir = true;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
@@ -3186,6 +3389,18 @@ mod tests {
         opts.debugging_opts.mir_opt_level = 3;
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
 
+        opts = reference.clone();
+        opts.debugging_opts.inline_mir = Some(true);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.inline_mir_threshold = Some(123);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
+        opts = reference.clone();
+        opts.debugging_opts.inline_mir_hint_threshold = Some(123);
+        assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
+
         opts = reference.clone();
         opts.debugging_opts.relro_level = Some(RelroLevel::Full);
         assert!(reference.dep_tracking_hash() != opts.dep_tracking_hash());
@@ -34,6 +34,7 @@ use syntax::ast::{self, NodeId};
 use syntax::edition::Edition;
 use syntax::feature_gate::{self, AttributeType};
 use syntax::json::JsonEmitter;
+use syntax::sarif::SarifEmitter;
 use syntax::source_map;
 use syntax::parse::{self, ParseSess};
 use syntax_pos::{MultiSpan, Span};
@@ -516,6 +517,12 @@ impl Session {
     pub fn codegen_stats(&self) -> bool {
         self.opts.debugging_opts.codegen_stats
     }
+    pub fn debuginfo_stats(&self) -> bool {
+        self.opts.debugging_opts.debuginfo_stats
+    }
+    pub fn mono_item_report(&self) -> bool {
+        self.opts.debugging_opts.mono_item_report.is_some()
+    }
     pub fn meta_stats(&self) -> bool {
         self.opts.debugging_opts.meta_stats
     }
@@ -545,6 +552,16 @@ impl Session {
         self.features.set(features);
     }
 
+    /// Where the debug info goes. Without debug info there is nothing to
+    /// split, so this is `Off` then, whatever `-C split-debuginfo` says.
+    pub fn split_debuginfo(&self) -> config::SplitDebuginfo {
+        if self.opts.debuginfo == config::DebugInfo::None {
+            config::SplitDebuginfo::Off
+        } else {
+            self.opts.cg.split_debuginfo
+        }
+    }
+
     /// Calculates the flavor of LTO to use for this compilation.
     pub fn lto(&self) -> config::Lto {
         // If our target has codegen requirements ignore the command line
@@ -840,6 +857,11 @@ impl Session {
         profiler.save_results(&self.opts);
     }
 
+    pub fn save_trace_events(&self) {
+        let profiler = self.self_profiling.borrow();
+        profiler.save_trace(&self.opts);
+    }
+
     pub fn print_perf_stats(&self) {
         println!(
             "Total time spent computing symbol hashes:      {}",
@@ -1075,6 +1097,12 @@ pub fn build_session_with_source_map(
             (config::ErrorOutputType::Short(_), Some(dst)) => {
                 Box::new(EmitterWriter::new(dst, Some(source_map.clone()), true, false))
             }
+            (config::ErrorOutputType::Sarif, None) => Box::new(
+                SarifEmitter::stderr(Some(registry), source_map.clone()),
+            ),
+            (config::ErrorOutputType::Sarif, Some(dst)) => Box::new(
+                SarifEmitter::new(dst, Some(registry), source_map.clone()),
+            ),
         };
 
     let diagnostic_handler = errors::Handler::with_emitter_and_flags(
@@ -1085,6 +1113,7 @@ pub fn build_session_with_source_map(
             report_delayed_bugs,
             dont_buffer_diagnostics,
             external_macro_backtrace,
+            collect_machine_applicable_suggestions: sopts.apply_suggestions.is_some(),
             ..Default::default()
         },
     );
@@ -1123,6 +1152,11 @@ pub fn build_session_(
     let print_fuel_crate = sopts.debugging_opts.print_fuel.clone();
     let print_fuel = LockCell::new(0);
 
+    let mut self_profiler = SelfProfiler::new();
+    if sopts.debugging_opts.self_profile && sopts.debugging_opts.profile_trace {
+        self_profiler.enable_trace_events();
+    }
+
     let working_dir = env::current_dir().unwrap_or_else(|e|
         p_s.span_diagnostic
             .fatal(&format!("Current directory is invalid: {}", e))
@@ -1166,7 +1200,7 @@ pub fn build_session_(
         imported_macro_spans: OneThread::new(RefCell::new(FxHashMap::default())),
         incr_comp_session: OneThread::new(RefCell::new(IncrCompSession::NotInitialized)),
         cgu_reuse_tracker,
-        self_profiling: Lock::new(SelfProfiler::new()),
+        self_profiling: Lock::new(self_profiler),
         profile_channel: Lock::new(None),
         perf_stats: PerfStats {
             symbol_hash_time: Lock::new(Duration::from_secs(0)),
@@ -1234,6 +1268,17 @@ fn validate_commandline_args_with_session_available(sess: &Session) {
         }
     }
 
+    if sess.opts.cg.split_debuginfo != config::SplitDebuginfo::Off {
+        if sess.target.target.target_os != "linux" {
+            sess.err("`-C split-debuginfo` is only supported on Linux");
+        }
+        // Fat LTO merges the codegen units, but their compile units keep
+        // pointing to the `.dwo` files they would have been written to.
+        if sess.lto() == Lto::Fat {
+            sess.err("`-C split-debuginfo` can't be combined with fat LTO");
+        }
+    }
+
     // Since we don't know if code in an rlib will be linked to statically or
     // dynamically downstream, rustc generates `__imp_` symbols that help the
     // MSVC linker deal with this lack of knowledge (#27438). Unfortunately,
@@ -1308,6 +1353,7 @@ pub fn early_error(output: config::ErrorOutputType, msg: &str) -> ! {
         config::ErrorOutputType::Short(color_config) => {
             Box::new(EmitterWriter::stderr(color_config, None, true, false))
         }
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::basic()),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Fatal);
@@ -1323,6 +1369,7 @@ pub fn early_warn(output: config::ErrorOutputType, msg: &str) {
         config::ErrorOutputType::Short(color_config) => {
             Box::new(EmitterWriter::stderr(color_config, None, true, false))
         }
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::basic()),
     };
     let handler = errors::Handler::with_emitter(true, false, emitter);
     handler.emit(&MultiSpan::new(), msg, errors::Level::Warning);
@@ -11,6 +11,7 @@
 use hir::Unsafety;
 use hir::def_id::DefId;
 use ty::{self, Ty, PolyFnSig, TypeFoldable, Substs, TyCtxt};
+use ty::subst::UnpackedKind;
 use traits;
 use rustc_target::spec::abi::Abi;
 use util::ppaux;
@@ -332,6 +333,34 @@ impl<'a, 'b, 'tcx> Instance<'tcx> {
         }
     }
 
+    /// With `-Z polymorphize`, replaces the type parameters that the MIR of
+    /// the item doesn't depend on with `()`, so that all instances differing
+    /// only in those parameters are collapsed into one. Both the collector and
+    /// codegen go through this, which keeps the instances they see in sync.
+    pub fn polymorphize(self, tcx: TyCtxt<'a, 'tcx, 'tcx>) -> Instance<'tcx> {
+        if !tcx.sess.opts.debugging_opts.polymorphize {
+            return self;
+        }
+        let def_id = match self.def {
+            InstanceDef::Item(def_id) => def_id,
+            _ => return self,
+        };
+        let unused = tcx.unused_generic_params(def_id);
+        if unused == 0 {
+            return self;
+        }
+
+        let substs = tcx.mk_substs(self.substs.iter().enumerate().map(|(index, &kind)| {
+            match kind.unpack() {
+                UnpackedKind::Type(_) if index < 64 && unused & (1 << index) != 0 => {
+                    tcx.types.unit.into()
+                }
+                _ => kind,
+            }
+        }));
+        Instance { def: self.def, substs }
+    }
+
     pub fn is_vtable_shim(&self) -> bool {
         if let InstanceDef::VtableShim(..) = self.def {
             true
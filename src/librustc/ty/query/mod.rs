@@ -92,6 +92,9 @@ use self::config::{QueryAccessors, QueryDescription};
 mod on_disk_cache;
 pub use self::on_disk_cache::OnDiskCache;
 
+mod stats;
+use self::stats::QueryStatsEntry;
+
 // Each of these quries corresponds to a function pointer field in the
 // `Providers` struct for requesting a value of that type, and a method
 // on `tcx: TyCtxt` (and `tcx.at(span)`) for doing that request in a way
@@ -552,6 +555,7 @@ define_queries! { <'tcx>
             collect_and_partition_mono_items_node(CrateNum)
             -> (Arc<DefIdSet>, Arc<Vec<Arc<CodegenUnit<'tcx>>>>),
         [] fn is_codegened_item: IsCodegenedItem(DefId) -> bool,
+        [] fn unused_generic_params: UnusedGenericParams(DefId) -> u64,
         [] fn codegen_unit: CodegenUnit(InternedString) -> Arc<CodegenUnit<'tcx>>,
     },
 
@@ -164,6 +164,16 @@ impl<'sess> OnDiskCache<'sess> {
         }
     }
 
+    /// The dep-nodes of the previous session whose results are in the cache.
+    pub fn cached_results(&self) -> Vec<SerializedDepNodeIndex> {
+        self.query_result_index.keys().cloned().collect()
+    }
+
+    /// The dep-nodes of the previous session that have diagnostics in the cache.
+    pub fn cached_diagnostics(&self) -> Vec<SerializedDepNodeIndex> {
+        self.prev_diagnostics_index.keys().cloned().collect()
+    }
+
     pub fn serialize<'a, 'tcx, E>(&self,
                                   tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                   encoder: &mut E)
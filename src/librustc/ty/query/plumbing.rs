@@ -22,12 +22,15 @@ use ty::{TyCtxt};
 use ty::query::Query;
 use ty::query::config::{QueryConfig, QueryDescription};
 use ty::query::job::{QueryJob, QueryResult, QueryInfo};
+use ty::query::stats::{self, QueryStats};
 use ty::item_path;
 
 use util::common::{profq_msg, ProfileQueriesMsg, QueryMsg};
 
 use rustc_data_structures::fx::{FxHashMap};
 use rustc_data_structures::sync::{Lrc, Lock};
+use session::Session;
+use std::fmt::Debug;
 use std::mem;
 use std::ptr;
 use std::collections::hash_map::Entry;
@@ -37,6 +40,8 @@ use syntax::source_map::DUMMY_SP;
 pub struct QueryCache<'tcx, D: QueryConfig<'tcx> + ?Sized> {
     pub(super) results: FxHashMap<D::Key, QueryValue<D::Value>>,
     pub(super) active: FxHashMap<D::Key, QueryResult<'tcx>>,
+    /// Only kept up to date with `-Z query-stats`.
+    pub(super) stats: QueryStats,
 }
 
 pub(super) struct QueryValue<T> {
@@ -60,6 +65,7 @@ impl<'tcx, M: QueryConfig<'tcx>> Default for QueryCache<'tcx, M> {
         QueryCache {
             results: FxHashMap::default(),
             active: FxHashMap::default(),
+            stats: QueryStats::default(),
         }
     }
 }
@@ -91,6 +97,16 @@ macro_rules! profq_query_msg {
     }}
 }
 
+/// The key of a query as shown in the `-Z profile-trace` timeline, if that is
+/// enabled. Formatting keys is too expensive to do for every query otherwise.
+fn trace_key<K: Debug>(sess: &Session, key: &K) -> Option<String> {
+    if sess.opts.debugging_opts.self_profile && sess.opts.debugging_opts.profile_trace {
+        Some(format!("{:?}", key))
+    } else {
+        None
+    }
+}
+
 /// A type representing the responsibility to execute the job in the `job` field.
 /// This will poison the relevant query if dropped.
 pub(super) struct JobOwner<'a, 'tcx: 'a, Q: QueryDescription<'tcx> + 'a> {
@@ -194,7 +210,7 @@ impl<'a, 'tcx, Q: QueryDescription<'tcx>> JobOwner<'a, 'tcx, Q> {
         // The TyCtxt stored in TLS has the same global interner lifetime
         // as `tcx`, so we use `with_related_context` to relate the 'gcx lifetimes
         // when accessing the ImplicitCtxt
-        let r = tls::with_related_context(tcx, move |current_icx| {
+        let run = move || tls::with_related_context(tcx, move |current_icx| {
             // Update the ImplicitCtxt to point to our new query job
             let new_icx = tls::ImplicitCtxt {
                 tcx,
@@ -209,6 +225,16 @@ impl<'a, 'tcx, Q: QueryDescription<'tcx>> JobOwner<'a, 'tcx, Q> {
             })
         });
 
+        let r = if tcx.sess.opts.debugging_opts.query_stats {
+            let (r, total, self_time) = stats::timed(run);
+            let mut lock = self.cache.borrow_mut();
+            lock.stats.computed += 1;
+            lock.stats.record_time(total, self_time);
+            r
+        } else {
+            run()
+        };
+
         // Extract the diagnostic from the job
         let diagnostics = mem::replace(&mut *self.job.diagnostics.lock(), Vec::new());
 
@@ -364,12 +390,18 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
         );
 
         self.sess.profiler(|p| p.record_query(Q::CATEGORY));
+        if self.sess.opts.debugging_opts.query_stats {
+            Q::query_cache(self).borrow_mut().stats.invocations += 1;
+        }
 
         let job = match JobOwner::try_get(self, span, &key) {
             TryGetJob::NotYetStarted(job) => job,
             TryGetJob::JobCompleted(result) => {
                 return result.map(|(v, index)| {
                     self.sess.profiler(|p| p.record_query_hit(Q::CATEGORY));
+                    if self.sess.opts.debugging_opts.query_stats {
+                        Q::query_cache(self).borrow_mut().stats.hits += 1;
+                    }
                     self.dep_graph.read_index(index);
                     v
                 })
@@ -387,7 +419,11 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
 
         if dep_node.kind.is_anon() {
             profq_msg!(self, ProfileQueriesMsg::ProviderBegin);
-            self.sess.profiler(|p| p.start_activity(Q::CATEGORY));
+            let trace_key = trace_key(self.sess, &key);
+            self.sess.profiler(|p| {
+                p.start_activity(Q::CATEGORY);
+                p.start_query(Q::NAME, Q::CATEGORY, trace_key);
+            });
 
             let res = job.start(self, |tcx| {
                 tcx.dep_graph.with_anon_task(dep_node.kind, || {
@@ -395,7 +431,10 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
                 })
             });
 
-            self.sess.profiler(|p| p.end_activity(Q::CATEGORY));
+            self.sess.profiler(|p| {
+                p.end_query(Q::NAME, Q::CATEGORY);
+                p.end_activity(Q::CATEGORY);
+            });
             profq_msg!(self, ProfileQueriesMsg::ProviderEnd);
             let ((result, dep_node_index), diagnostics) = res;
 
@@ -448,8 +487,18 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
                         self.sess.opts.debugging_opts.incremental_queries {
             let prev_dep_node_index =
                 self.dep_graph.prev_dep_node_index_of(dep_node);
-            let result = Q::try_load_from_disk(self.global_tcx(),
-                                               prev_dep_node_index);
+            let load = || Q::try_load_from_disk(self.global_tcx(), prev_dep_node_index);
+            let result = if self.sess.opts.debugging_opts.query_stats {
+                let (result, total, self_time) = stats::timed(load);
+                let mut lock = job.cache.borrow_mut();
+                if result.is_some() {
+                    lock.stats.loaded_from_disk += 1;
+                }
+                lock.stats.record_time(total, self_time);
+                result
+            } else {
+                load()
+            };
 
             // We always expect to find a cached result for things that
             // can be forced from DepNode.
@@ -472,6 +521,8 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             // The diagnostics for this query have already been
             // promoted to the current session during
             // try_mark_green(), so we can ignore them here.
+            let trace_key = trace_key(self.sess, &key);
+            self.sess.profiler(|p| p.start_query(Q::NAME, Q::CATEGORY, trace_key));
             let (result, _) = job.start(self, |tcx| {
                 // The dep-graph for this computation is already in
                 // place
@@ -479,6 +530,7 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
                     Q::compute(tcx, key)
                 })
             });
+            self.sess.profiler(|p| p.end_query(Q::NAME, Q::CATEGORY));
             result
         };
 
@@ -534,10 +586,16 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
                  - dep-node: {:?}",
                 key, dep_node);
 
+        if self.sess.opts.debugging_opts.incremental_explain {
+            self.explain_re_execution(Q::NAME, &dep_node);
+        }
+
         profq_msg!(self, ProfileQueriesMsg::ProviderBegin);
+        let trace_key = trace_key(self.sess, &key);
         self.sess.profiler(|p| {
             p.start_activity(Q::CATEGORY);
             p.record_query(Q::CATEGORY);
+            p.start_query(Q::NAME, Q::CATEGORY, trace_key);
         });
 
         let res = job.start(self, |tcx| {
@@ -554,7 +612,10 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
             }
         });
 
-        self.sess.profiler(|p| p.end_activity(Q::CATEGORY));
+        self.sess.profiler(|p| {
+            p.end_query(Q::NAME, Q::CATEGORY);
+            p.end_activity(Q::CATEGORY);
+        });
         profq_msg!(self, ProfileQueriesMsg::ProviderEnd);
 
         let ((result, dep_node_index), diagnostics) = res;
@@ -573,6 +634,21 @@ impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
         Ok((result, dep_node_index))
     }
 
+    /// Prints why a query that was run in the previous session has to run
+    /// again, for `-Z incremental-explain`. Queries that are new or always
+    /// re-executed are not worth mentioning.
+    fn explain_re_execution(self, query_name: &str, dep_node: &DepNode) {
+        if !self.dep_graph.is_fully_enabled() ||
+           dep_node.kind.is_eval_always() ||
+           self.dep_graph.prev_fingerprint_of(dep_node).is_none() {
+            return;
+        }
+        println!("[incremental] query `{}` re-executed:", query_name);
+        for line in self.dep_graph.explain_not_green(self.global_tcx(), dep_node) {
+            println!("[incremental]     {}", line);
+        }
+    }
+
     /// Ensure that either this query has all green inputs or been executed.
     /// Executing query::ensure(D) is considered a read of the dep-node D.
     ///
@@ -723,6 +799,20 @@ macro_rules! define_queries_inner {
 
                 jobs
             }
+
+            fn query_stats(&self) -> Vec<QueryStatsEntry> {
+                let mut stats = Vec::new();
+                $({
+                    let cache = self.$name.lock();
+                    stats.push(QueryStatsEntry {
+                        name: stringify!($name),
+                        stats: cache.stats.clone(),
+                        entries: cache.results.len(),
+                        entry_size: mem::size_of::<($K, QueryValue<$V>)>(),
+                    });
+                })*
+                stats
+            }
         }
 
         #[allow(nonstandard_style)]
@@ -1259,6 +1349,7 @@ pub fn force_from_dep_node<'a, 'gcx, 'lcx>(tcx: TyCtxt<'a, 'gcx, 'lcx>,
             force!(collect_and_partition_mono_items, LOCAL_CRATE);
         }
         DepKind::IsCodegenedItem => { force!(is_codegened_item, def_id!()); }
+        DepKind::UnusedGenericParams => { force!(unused_generic_params, def_id!()); }
         DepKind::OutputFilenames => { force!(output_filenames, LOCAL_CRATE); }
 
         DepKind::TargetFeaturesWhitelist => { force!(target_features_whitelist, LOCAL_CRATE); }
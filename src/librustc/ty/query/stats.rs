@@ -0,0 +1,122 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The per-query statistics printed by `-Z query-stats`.
+
+use ty::TyCtxt;
+
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+
+/// What happened to the instances of one query over the whole session.
+#[derive(Clone, Default)]
+pub(super) struct QueryStats {
+    /// Calls of `tcx.$query(key)`, including the ones answered from the cache.
+    pub(super) invocations: u64,
+    /// Calls answered from the in-memory cache.
+    pub(super) hits: u64,
+    /// Times the provider was run.
+    pub(super) computed: u64,
+    /// Results decoded from the on-disk cache of the previous session.
+    pub(super) loaded_from_disk: u64,
+    /// Time spent computing or loading results, nested queries included.
+    pub(super) total_time: Duration,
+    /// Time spent computing or loading results, nested queries excluded.
+    pub(super) self_time: Duration,
+}
+
+impl QueryStats {
+    pub(super) fn record_time(&mut self, total: Duration, self_time: Duration) {
+        self.total_time += total;
+        self.self_time += self_time;
+    }
+}
+
+/// The statistics of one query, along with what its cache holds at the end.
+pub(super) struct QueryStatsEntry {
+    pub(super) name: &'static str,
+    pub(super) stats: QueryStats,
+    pub(super) entries: usize,
+    /// The size of a key and value pair in the cache. This does not follow
+    /// pointers, so results living in arenas are only counted as references.
+    pub(super) entry_size: usize,
+}
+
+thread_local!(static NESTED_TIME: Cell<Duration> = Cell::new(Duration::new(0, 0)));
+
+/// Runs `f` and returns its result, the time it took and the time it took
+/// without the nested calls of `timed`, i.e. without nested queries.
+pub(super) fn timed<R, F: FnOnce() -> R>(f: F) -> (R, Duration, Duration) {
+    let outer = NESTED_TIME.with(|nested| nested.replace(Duration::new(0, 0)));
+    let start = Instant::now();
+    let r = f();
+    let total = start.elapsed();
+    let nested = NESTED_TIME.with(|nested| nested.replace(outer + total));
+    (r, total, total - nested)
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs() as f64 * 1000.0 + d.subsec_nanos() as f64 / 1_000_000.0
+}
+
+impl<'a, 'gcx, 'tcx> TyCtxt<'a, 'gcx, 'tcx> {
+    /// Prints the `-Z query-stats` report, sorted by self time.
+    pub fn print_query_stats(self) {
+        let incremental = self.sess.opts.incremental.is_some();
+
+        let mut entries = self.queries.query_stats();
+        entries.retain(|e| e.stats.invocations > 0 || e.entries > 0);
+        entries.sort_by_key(|e| Reverse(e.stats.self_time));
+
+        let width = entries.iter().map(|e| e.name.len()).max().unwrap_or(0).max(5);
+        let disk_header = if incremental { "  from disk" } else { "" };
+        println!("{:<width$} {:>10} {:>10} {:>6} {:>10}{} {:>12} {:>12} {:>12}",
+                 "query", "calls", "hits", "hit %", "computed", disk_header,
+                 "total ms", "self ms", "memory",
+                 width = width);
+
+        let mut total = QueryStats::default();
+        let mut total_memory = 0;
+        for e in &entries {
+            let s = &e.stats;
+            let hit_rate = if s.invocations > 0 {
+                s.hits as f64 / s.invocations as f64 * 100.0
+            } else {
+                0.0
+            };
+            let memory = e.entries * e.entry_size;
+            let from_disk = if incremental {
+                format!(" {:>10}", s.loaded_from_disk)
+            } else {
+                String::new()
+            };
+            println!("{:<width$} {:>10} {:>10} {:>5.1}% {:>10}{} {:>12.3} {:>12.3} {:>12}",
+                     e.name, s.invocations, s.hits, hit_rate, s.computed, from_disk,
+                     millis(s.total_time), millis(s.self_time), memory,
+                     width = width);
+
+            total.invocations += s.invocations;
+            total.hits += s.hits;
+            total.computed += s.computed;
+            total.loaded_from_disk += s.loaded_from_disk;
+            total.self_time += s.self_time;
+            total_memory += memory;
+        }
+
+        println!("{} queries: {} calls, {} cache hits, {} computed, {:.3} ms, {} bytes of results",
+                 entries.len(), total.invocations, total.hits, total.computed,
+                 millis(total.self_time), total_memory);
+        if incremental {
+            println!("incremental: {} results loaded from the on-disk cache, {} recomputed",
+                     total.loaded_from_disk, total.computed);
+        }
+    }
+}
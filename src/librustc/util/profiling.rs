@@ -11,8 +11,12 @@
 use session::config::Options;
 
 use std::fs;
-use std::io::{self, StdoutLock, Write};
-use std::time::Instant;
+use std::io::{self, BufWriter, StdoutLock, Write};
+use std::process;
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+use std::time::{Duration, Instant};
+
+use serialize::json::as_json;
 
 macro_rules! define_categories {
     ($($name:ident,)*) => {
@@ -121,10 +125,32 @@ define_categories! {
     Other,
 }
 
+/// The start or the end of a query, for the `-Z profile-trace` timeline.
+struct TraceEvent {
+    query_name: &'static str,
+    category: ProfileCategory,
+    is_start: bool,
+    /// The query key, for start events.
+    key: Option<String>,
+    thread: usize,
+    /// The time since the profiler was created.
+    timestamp: Duration,
+}
+
+/// A small number identifying the current thread in the trace, since
+/// `ThreadId`s can't be turned into numbers.
+fn current_thread_index() -> usize {
+    static NEXT_THREAD_INDEX: AtomicUsize = ATOMIC_USIZE_INIT;
+    thread_local!(static THREAD_INDEX: usize = NEXT_THREAD_INDEX.fetch_add(1, Ordering::SeqCst));
+    THREAD_INDEX.with(|&index| index)
+}
+
 pub struct SelfProfiler {
     timer_stack: Vec<ProfileCategory>,
     data: CategoryData,
     current_timer: Instant,
+    start_time: Instant,
+    trace_events: Option<Vec<TraceEvent>>,
 }
 
 impl SelfProfiler {
@@ -133,6 +159,8 @@ impl SelfProfiler {
             timer_stack: Vec::new(),
             data: CategoryData::new(),
             current_timer: Instant::now(),
+            start_time: Instant::now(),
+            trace_events: None,
         };
 
         profiler.start_activity(ProfileCategory::Other);
@@ -140,6 +168,43 @@ impl SelfProfiler {
         profiler
     }
 
+    /// Starts recording the start and end of every query, see `save_trace`.
+    pub fn enable_trace_events(&mut self) {
+        self.trace_events = Some(Vec::new());
+    }
+
+    /// Records the start of a query for the trace. `key` is `None` unless
+    /// trace events are enabled, since formatting keys is not cheap.
+    pub fn start_query(&mut self,
+                       query_name: &'static str,
+                       category: ProfileCategory,
+                       key: Option<String>) {
+        self.record_trace_event(query_name, category, true, key);
+    }
+
+    /// Records the end of a query for the trace.
+    pub fn end_query(&mut self, query_name: &'static str, category: ProfileCategory) {
+        self.record_trace_event(query_name, category, false, None);
+    }
+
+    fn record_trace_event(&mut self,
+                          query_name: &'static str,
+                          category: ProfileCategory,
+                          is_start: bool,
+                          key: Option<String>) {
+        let timestamp = self.start_time.elapsed();
+        if let Some(ref mut events) = self.trace_events {
+            events.push(TraceEvent {
+                query_name,
+                category,
+                is_start,
+                key,
+                thread: current_thread_index(),
+                timestamp,
+            });
+        }
+    }
+
     pub fn start_activity(&mut self, category: ProfileCategory) {
         match self.timer_stack.last().cloned() {
             None => {
@@ -245,4 +310,57 @@ impl SelfProfiler {
 
         fs::write("self_profiler_results.json", json).unwrap();
     }
+
+    /// Writes the recorded query starts and ends in the Chrome trace-event
+    /// format, which chrome://tracing and Perfetto can show as a timeline.
+    pub fn save_trace(&self, opts: &Options) {
+        let events = match self.trace_events {
+            Some(ref events) => events,
+            None => return,
+        };
+
+        let file_name = match opts.crate_name {
+            Some(ref name) => format!("{}-self_profiler_trace.json", name),
+            None => "self_profiler_trace.json".to_string(),
+        };
+        let mut out = BufWriter::new(fs::File::create(file_name).unwrap());
+        let pid = process::id();
+
+        write!(out, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[").unwrap();
+
+        let mut threads = events.iter().map(|event| event.thread).collect::<Vec<_>>();
+        threads.sort();
+        threads.dedup();
+        for (i, thread) in threads.iter().enumerate() {
+            if i > 0 {
+                write!(out, ",").unwrap();
+            }
+            write!(out,
+                   "\n{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{},\"tid\":{},\
+                    \"args\":{{\"name\":\"rustc thread {}\"}}}}",
+                   pid, thread, thread).unwrap();
+        }
+
+        for event in events {
+            let ts = event.timestamp.as_secs() * 1_000_000 +
+                (event.timestamp.subsec_nanos() / 1_000) as u64;
+            let ts = format!("{}.{:03}", ts, event.timestamp.subsec_nanos() % 1_000);
+            let category = format!("{:?}", event.category);
+            if event.is_start {
+                write!(out,
+                       ",\n{{\"name\":{},\"cat\":{},\"ph\":\"B\",\"ts\":{},\
+                        \"pid\":{},\"tid\":{},\"args\":{{\"key\":{}}}}}",
+                       as_json(&event.query_name), as_json(&category), ts,
+                       pid, event.thread, as_json(&event.key)).unwrap();
+            } else {
+                write!(out,
+                       ",\n{{\"name\":{},\"cat\":{},\"ph\":\"E\",\"ts\":{},\
+                        \"pid\":{},\"tid\":{}}}",
+                       as_json(&event.query_name), as_json(&category), ts,
+                       pid, event.thread).unwrap();
+            }
+        }
+
+        writeln!(out, "\n]}}").unwrap();
+    }
 }
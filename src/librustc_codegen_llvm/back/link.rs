@@ -16,7 +16,7 @@ use super::rpath::RPathConfig;
 use super::rpath;
 use metadata::METADATA_FILENAME;
 use rustc::session::config::{self, DebugInfo, OutputFilenames, OutputType, PrintRequest};
-use rustc::session::config::{RUST_CGU_EXT, Lto};
+use rustc::session::config::{RUST_CGU_EXT, Lto, SplitDebuginfo};
 use rustc::session::filesearch;
 use rustc::session::search_paths::PathKind;
 use rustc::session::Session;
@@ -163,6 +163,11 @@ pub(crate) fn link_binary(sess: &Session,
                 remove(sess, bc);
             }
         }
+        if !preserve_dwarf_objects(sess) {
+            for dwo in codegen_results.modules.iter().filter_map(|m| m.dwarf_object.as_ref()) {
+                remove(sess, dwo);
+            }
+        }
     }
 
     out_filenames
@@ -214,6 +219,23 @@ fn preserve_objects_for_their_debuginfo(sess: &Session) -> bool {
     false
 }
 
+/// Returns whether the `.dwo` files of `-C split-debuginfo` have to stay
+/// around once the outputs are linked.
+fn preserve_dwarf_objects(sess: &Session) -> bool {
+    match sess.split_debuginfo() {
+        SplitDebuginfo::Off => false,
+        SplitDebuginfo::Unpacked => true,
+        // The `.dwp` files are written for the linked outputs only. Archives
+        // still point to the `.dwo` files, and so will whatever they end up
+        // being linked into.
+        SplitDebuginfo::Packed => {
+            sess.crate_types.borrow()
+                .iter()
+                .any(|&x| x == config::CrateType::Rlib || x == config::CrateType::Staticlib)
+        }
+    }
+}
+
 pub(crate) fn each_linked_rlib(sess: &Session,
                                info: &CrateInfo,
                                f: &mut dyn FnMut(CrateNum, &Path)) -> Result<(), String> {
@@ -866,6 +888,26 @@ fn link_natively(sess: &Session,
         }
     }
 
+    // With packed split debug info, gather the `.dwo` files the skeleton units
+    // of the output point to, those of upstream crates included, into a `.dwp`
+    // file next to it, where debuggers look for it.
+    if sess.split_debuginfo() == SplitDebuginfo::Packed {
+        let mut dwp = out_filename.as_os_str().to_owned();
+        dwp.push(".dwp");
+        let mut cmd = Command::new("dwp");
+        cmd.arg("-e").arg(out_filename).arg("-o").arg(&dwp);
+        match cmd.output() {
+            Ok(ref output) if !output.status.success() => {
+                sess.struct_err("packing the split debug info with `dwp` failed")
+                    .note(&format!("{:?}", &cmd))
+                    .note(&String::from_utf8_lossy(&output.stderr))
+                    .emit();
+            }
+            Ok(_) => {}
+            Err(e) => sess.fatal(&format!("failed to run dwp: {}", e)),
+        }
+    }
+
     if sess.opts.target_triple.triple() == "wasm32-unknown-unknown" {
         wasm::rewrite_imports(&out_filename, &codegen_results.crate_info.wasm_imports);
     }
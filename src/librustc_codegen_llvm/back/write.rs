@@ -26,6 +26,7 @@ use rustc::util::nodemap::FxHashMap;
 use time_graph::{self, TimeGraph, Timeline};
 use llvm::{self, DiagnosticInfo, PassManager, SMDiagnostic};
 use llvm_util;
+use mono_item_report::MonoItemReport;
 use {CodegenResults, ModuleCodegen, CompiledModule, ModuleKind, // ModuleLlvm,
      CachedModuleCodegen};
 use CrateInfo;
@@ -56,6 +57,7 @@ use std::fs;
 use std::io::{self, Write};
 use std::mem;
 use std::path::{Path, PathBuf};
+use std::ptr;
 use std::str;
 use std::sync::Arc;
 use std::sync::mpsc::{channel, Sender, Receiver};
@@ -90,6 +92,10 @@ pub const TLS_MODEL_ARGS : [(&str, llvm::ThreadLocalMode); 4] = [
 
 const PRE_THIN_LTO_BC_EXT: &str = "pre-thin-lto.bc";
 
+/// The extension of the files `-C split-debuginfo` moves the debug info of
+/// each object file to.
+pub const DWARF_OBJECT_EXTENSION: &str = "dwo";
+
 pub fn llvm_err(handler: &errors::Handler, msg: &str) -> FatalError {
     match llvm::last_error() {
         Some(err) => handler.fatal(&format!("{}: {}", msg, err)),
@@ -103,10 +109,14 @@ pub fn write_output_file(
         pm: &llvm::PassManager<'ll>,
         m: &'ll llvm::Module,
         output: &Path,
+        dwo_output: Option<&Path>,
         file_type: llvm::FileType) -> Result<(), FatalError> {
     unsafe {
         let output_c = path2cstr(output);
-        let result = llvm::LLVMRustWriteOutputFile(target, pm, m, output_c.as_ptr(), file_type);
+        let dwo_output_c = dwo_output.map(path2cstr);
+        let dwo_output_ptr = dwo_output_c.as_ref().map_or(ptr::null(), |c| c.as_ptr());
+        let result = llvm::LLVMRustWriteOutputFile(target, pm, m, output_c.as_ptr(),
+                                                   dwo_output_ptr, file_type);
         if result.into_result().is_err() {
             let msg = format!("could not write output to {}", output.display());
             Err(llvm_err(handler, &msg))
@@ -196,6 +206,7 @@ pub fn target_machine_factory(sess: &Session, find_features: bool)
     let is_pie_binary = !find_features && is_pie_binary(sess);
     let trap_unreachable = sess.target.target.options.trap_unreachable;
     let emit_stack_size_section = sess.opts.debugging_opts.emit_stack_sizes;
+    let compress_debug_sections = sess.opts.debugging_opts.compress_debug_sections;
 
     let asm_comments = sess.asm_comments();
 
@@ -214,12 +225,18 @@ pub fn target_machine_factory(sess: &Session, find_features: bool)
                 singlethread,
                 asm_comments,
                 emit_stack_size_section,
+                compress_debug_sections,
             )
         };
 
         tm.ok_or_else(|| {
-            format!("Could not create LLVM TargetMachine for triple: {}",
-                    triple.to_str().unwrap())
+            let mut msg = format!("Could not create LLVM TargetMachine for triple: {}",
+                                  triple.to_str().unwrap());
+            if let Some(err) = llvm::last_error() {
+                msg.push_str(": ");
+                msg.push_str(&err);
+            }
+            msg
         })
     })
 }
@@ -370,6 +387,7 @@ pub struct CodegenContext {
     pub msvc_imps_needed: bool,
     pub target_pointer_width: String,
     debuginfo: config::DebugInfo,
+    split_debuginfo: config::SplitDebuginfo,
 
     // Number of cgus excluding the allocator/metadata modules
     pub total_cgus: usize,
@@ -681,7 +699,7 @@ unsafe fn codegen(cgcx: &CodegenContext,
     -> Result<CompiledModule, FatalError>
 {
     timeline.record("codegen");
-    {
+    let emit_dwarf_object = {
         let llmod = module.module_llvm.llmod();
         let llcx = &*module.module_llvm.llcx;
         let tm = &*module.module_llvm.tm;
@@ -728,6 +746,13 @@ unsafe fn codegen(cgcx: &CodegenContext,
 
         let bc_out = cgcx.output_filenames.temp_path(OutputType::Bitcode, module_name);
         let obj_out = cgcx.output_filenames.temp_path(OutputType::Object, module_name);
+        // Only the regular modules have debug info to split off.
+        let dwo_out = if cgcx.split_debuginfo != config::SplitDebuginfo::Off &&
+                         module.kind == ModuleKind::Regular && write_obj {
+            Some(cgcx.output_filenames.temp_path_ext(DWARF_OBJECT_EXTENSION, module_name))
+        } else {
+            None
+        };
 
 
         if write_bc || config.emit_bc_compressed || config.embed_bitcode {
@@ -815,7 +840,7 @@ unsafe fn codegen(cgcx: &CodegenContext,
                     llmod
                 };
                 with_codegen(tm, llmod, config.no_builtins, |cpm| {
-                    write_output_file(diag_handler, tm, cpm, llmod, &path,
+                    write_output_file(diag_handler, tm, cpm, llmod, &path, None,
                                       llvm::FileType::AssemblyFile)
                 })?;
                 timeline.record("asm");
@@ -824,6 +849,7 @@ unsafe fn codegen(cgcx: &CodegenContext,
             if write_obj {
                 with_codegen(tm, llmod, config.no_builtins, |cpm| {
                     write_output_file(diag_handler, tm, cpm, llmod, &obj_out,
+                                      dwo_out.as_ref().map(|p| &**p),
                                       llvm::FileType::ObjectFile)
                 })?;
                 timeline.record("obj");
@@ -855,10 +881,12 @@ unsafe fn codegen(cgcx: &CodegenContext,
         }
 
         drop(handlers);
-    }
+        dwo_out.is_some()
+    };
     Ok(module.into_compiled_module(config.emit_obj,
                                    config.emit_bc,
                                    config.emit_bc_compressed,
+                                   emit_dwarf_object,
                                    &cgcx.output_filenames))
 }
 
@@ -1087,6 +1115,7 @@ pub fn start_async_codegen(tcx: TyCtxt,
         shared_emitter_main,
         future: coordinator_thread,
         output_filenames: tcx.output_filenames(LOCAL_CRATE),
+        mono_item_report: None,
     }
 }
 
@@ -1112,6 +1141,9 @@ fn copy_all_cgu_workproducts_to_incr_comp_cache_dir(
         if let Some(ref path) = module.bytecode_compressed {
             files.push((WorkProductFileKind::BytecodeCompressed, path.clone()));
         }
+        if let Some(ref path) = module.dwarf_object {
+            files.push((WorkProductFileKind::DwarfObject, path.clone()));
+        }
 
         if let Some((id, product)) =
             copy_cgu_workproducts_to_incr_comp_cache_dir(sess, &module.name, &files)
@@ -1412,6 +1444,7 @@ fn execute_copy_from_cache_work_item(cgcx: &CodegenContext,
     let mut object = None;
     let mut bytecode = None;
     let mut bytecode_compressed = None;
+    let mut dwarf_object = None;
     for (kind, saved_file) in &module.source.saved_files {
         let obj_out = match kind {
             WorkProductFileKind::Object => {
@@ -1433,6 +1466,12 @@ fn execute_copy_from_cache_work_item(cgcx: &CodegenContext,
                 bytecode_compressed = Some(path.clone());
                 path
             }
+            WorkProductFileKind::DwarfObject => {
+                let path = cgcx.output_filenames.temp_path_ext(DWARF_OBJECT_EXTENSION,
+                                                               Some(&module.name));
+                dwarf_object = Some(path.clone());
+                path
+            }
         };
         let source_file = in_incr_comp_dir(&incr_comp_session_dir,
                                            &saved_file);
@@ -1459,6 +1498,7 @@ fn execute_copy_from_cache_work_item(cgcx: &CodegenContext,
         object,
         bytecode,
         bytecode_compressed,
+        dwarf_object,
     }))
 }
 
@@ -1613,6 +1653,7 @@ fn start_executing_work(tcx: TyCtxt,
         msvc_imps_needed: msvc_imps_needed(tcx),
         target_pointer_width: tcx.sess.target.target.target_pointer_width.clone(),
         debuginfo: tcx.sess.opts.debuginfo,
+        split_debuginfo: tcx.sess.split_debuginfo(),
         assembler_cmd,
     };
 
@@ -2389,6 +2430,7 @@ pub struct OngoingCodegen {
     shared_emitter_main: SharedEmitterMain,
     future: thread::JoinHandle<Result<CompiledModules, ()>>,
     output_filenames: Arc<OutputFilenames>,
+    pub(crate) mono_item_report: Option<MonoItemReport>,
 }
 
 impl OngoingCodegen {
@@ -2419,6 +2461,12 @@ impl OngoingCodegen {
         let work_products =
             copy_all_cgu_workproducts_to_incr_comp_cache_dir(sess,
                                                              &compiled_modules);
+
+        // The report reads the object files, which may be removed below.
+        if let Some(report) = self.mono_item_report {
+            report.print(sess, &compiled_modules.modules);
+        }
+
         produce_final_output_artifacts(sess,
                                        &compiled_modules,
                                        &self.output_filenames);
@@ -36,7 +36,7 @@ use rustc::dep_graph::cgu_reuse_tracker::CguReuse;
 use rustc::hir::def_id::{CrateNum, DefId, LOCAL_CRATE};
 use rustc::middle::lang_items::StartFnLangItem;
 use rustc::middle::weak_lang_items;
-use rustc::mir::mono::{Linkage, Visibility, Stats, CodegenUnitNameBuilder};
+use rustc::mir::mono::{Linkage, Visibility, Stats, DebuginfoTypeStats, CodegenUnitNameBuilder};
 use rustc::middle::cstore::{EncodedMetadata};
 use rustc::ty::{self, Ty, TyCtxt};
 use rustc::ty::layout::{self, Align, TyLayout, LayoutOf, VariantIdx};
@@ -67,6 +67,7 @@ use monomorphize::partitioning::{CodegenUnit, CodegenUnitExt};
 use rustc_codegen_utils::symbol_names_test;
 use time_graph;
 use mono_item::{MonoItem, MonoItemExt};
+use mono_item_report::MonoItemReport;
 use type_::Type;
 use type_of::LayoutLlvmExt;
 use rustc::util::nodemap::FxHashMap;
@@ -95,15 +96,17 @@ use rustc_codegen_utils::check_for_rustc_errors_attr;
 pub struct StatRecorder<'a, 'll: 'a, 'tcx: 'll> {
     cx: &'a CodegenCx<'ll, 'tcx>,
     name: Option<String>,
+    symbol_name: Option<String>,
     istart: usize,
 }
 
 impl StatRecorder<'a, 'll, 'tcx> {
-    pub fn new(cx: &'a CodegenCx<'ll, 'tcx>, name: String) -> Self {
+    pub fn new(cx: &'a CodegenCx<'ll, 'tcx>, name: String, symbol_name: String) -> Self {
         let istart = cx.stats.borrow().n_llvm_insns;
         StatRecorder {
             cx,
             name: Some(name),
+            symbol_name: Some(symbol_name),
             istart,
         }
     }
@@ -111,11 +114,19 @@ impl StatRecorder<'a, 'll, 'tcx> {
 
 impl Drop for StatRecorder<'a, 'll, 'tcx> {
     fn drop(&mut self) {
-        if self.cx.sess().codegen_stats() {
+        let sess = self.cx.sess();
+        if sess.codegen_stats() || sess.mono_item_report() {
             let mut stats = self.cx.stats.borrow_mut();
             let iend = stats.n_llvm_insns;
-            stats.fn_stats.push((self.name.take().unwrap(), iend - self.istart));
-            stats.n_fns += 1;
+            if sess.codegen_stats() {
+                stats.fn_stats.push((self.name.take().unwrap(), iend - self.istart));
+                stats.n_fns += 1;
+            }
+            if sess.mono_item_report() {
+                *stats.symbol_insns
+                      .entry(self.symbol_name.take().unwrap())
+                      .or_insert(0) += iend - self.istart;
+            }
             // Reset LLVM insn count to avoid compound costs.
             stats.n_llvm_insns = self.istart;
         }
@@ -474,11 +485,12 @@ pub fn call_memset(
 }
 
 pub fn codegen_instance<'a, 'tcx>(cx: &CodegenCx<'a, 'tcx>, instance: Instance<'tcx>) {
-    let _s = if cx.sess().codegen_stats() {
+    let _s = if cx.sess().codegen_stats() || cx.sess().mono_item_report() {
         let mut instance_name = String::new();
         DefPathBasedNames::new(cx.tcx, true, true)
             .push_def_path(instance.def_id(), &mut instance_name);
-        Some(StatRecorder::new(cx, instance_name))
+        let symbol_name = cx.tcx.symbol_name(instance).as_str().to_string();
+        Some(StatRecorder::new(cx, instance_name, symbol_name))
     } else {
         None
     };
@@ -703,6 +715,10 @@ fn determine_cgu_reuse<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     if tcx.dep_graph.previous_work_product(work_product_id).is_none() {
         // We don't have anything cached for this CGU. This can happen
         // if the CGU did not exist in the previous session.
+        if tcx.sess.opts.debugging_opts.incremental_explain {
+            println!("[incremental] CGU `{}` not reused: nothing was cached for it",
+                     cgu.name());
+        }
         return CguReuse::No
     }
 
@@ -725,6 +741,12 @@ fn determine_cgu_reuse<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
             CguReuse::PostLto
         }
     } else {
+        if tcx.sess.opts.debugging_opts.incremental_explain {
+            println!("[incremental] CGU `{}` not reused:", cgu.name());
+            for line in tcx.dep_graph.explain_not_green(tcx, &dep_node) {
+                println!("[incremental]     {}", line);
+            }
+        }
         CguReuse::No
     }
 }
@@ -804,7 +826,7 @@ pub fn codegen_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         metadata,
         rx,
         codegen_units.len());
-    let ongoing_codegen = AbortCodegenOnDrop(Some(ongoing_codegen));
+    let mut ongoing_codegen = AbortCodegenOnDrop(Some(ongoing_codegen));
 
     // Codegen an allocator shim, if necessary.
     //
@@ -860,6 +882,12 @@ pub fn codegen_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let mut total_codegen_time = Duration::new(0, 0);
     let mut all_stats = Stats::default();
 
+    let mono_item_report = if tcx.sess.mono_item_report() {
+        Some(MonoItemReport::new(tcx, &codegen_units))
+    } else {
+        None
+    };
+
     for cgu in codegen_units.into_iter() {
         ongoing_codegen.wait_for_signal_to_codegen_item();
         ongoing_codegen.check_for_errors(tcx.sess);
@@ -931,6 +959,16 @@ pub fn codegen_crate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         }
     }
 
+    if tcx.sess.debuginfo_stats() {
+        print_debuginfo_stats(&all_stats.debuginfo_types);
+    }
+
+    // The report is completed and printed once LLVM has written the objects.
+    if let Some(mut report) = mono_item_report {
+        report.record_llvm_insns(&all_stats.symbol_insns);
+        ongoing_codegen.mono_item_report = Some(report);
+    }
+
     ongoing_codegen.check_for_errors(tcx.sess);
 
     assert_and_save_dep_graph(tcx);
@@ -984,6 +1022,42 @@ impl Drop for AbortCodegenOnDrop {
     }
 }
 
+/// Prints the debuginfo recorded for `-Z debuginfo-stats`, summed up per
+/// crate, per module and per type, largest first.
+fn print_debuginfo_stats(types: &FxHashMap<String, DebuginfoTypeStats>) {
+    fn print_table(title: &str, totals: FxHashMap<&str, DebuginfoTypeStats>) {
+        let mut totals: Vec<_> = totals.into_iter().collect();
+        totals.sort_by(|&(a_name, ref a), &(b_name, ref b)| {
+            b.estimated_bytes().cmp(&a.estimated_bytes()).then(a_name.cmp(b_name))
+        });
+        println!("{}:", title);
+        println!("{:>12} {:>10} {:>10} {:>12}  {}",
+                 "est. bytes", "emitted", "members", "name bytes", "name");
+        for (name, stats) in totals {
+            println!("{:>12} {:>10} {:>10} {:>12}  {}",
+                     stats.estimated_bytes(), stats.emitted_instances, stats.members,
+                     stats.name_bytes, name);
+        }
+    }
+
+    let mut by_crate = FxHashMap::default();
+    let mut by_module = FxHashMap::default();
+    for stats in types.values() {
+        by_crate.entry(&stats.krate[..])
+            .or_insert_with(DebuginfoTypeStats::default)
+            .extend(stats.clone());
+        by_module.entry(&stats.module[..])
+            .or_insert_with(DebuginfoTypeStats::default)
+            .extend(stats.clone());
+    }
+    let by_type = types.iter().map(|(path, stats)| (&path[..], stats.clone())).collect();
+
+    println!("--- debuginfo stats ---");
+    print_table("by crate", by_crate);
+    print_table("by module", by_module);
+    print_table("by type", by_type);
+}
+
 fn assert_and_save_dep_graph<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>) {
     time(tcx.sess,
          "assert dep graph",
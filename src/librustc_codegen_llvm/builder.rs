@@ -105,7 +105,7 @@ impl Builder<'a, 'll, 'tcx> {
     }
 
     fn count_insn(&self, category: &str) {
-        if self.cx.sess().codegen_stats() {
+        if self.cx.sess().codegen_stats() || self.cx.sess().mono_item_report() {
             self.cx.stats.borrow_mut().n_llvm_insns += 1;
         }
         if self.cx.sess().count_llvm_insns() {
@@ -43,6 +43,10 @@ pub fn get_fn(
 
     debug!("get_fn(instance={:?})", instance);
 
+    // The collector only saw the polymorphized instance, see
+    // `Instance::polymorphize`.
+    let instance = instance.polymorphize(tcx);
+
     assert!(!instance.substs.needs_infer());
     assert!(!instance.substs.has_escaping_bound_vars());
     assert!(!instance.substs.has_param_types());
@@ -133,7 +137,16 @@ pub fn get_fn(
                 // This is a monomorphization. Its expected visibility depends
                 // on whether we are in share-generics mode.
 
-                if cx.tcx.sess.opts.share_generics() {
+                let is_upstream_instance = !instance_def_id.is_local() &&
+                    cx.tcx.sess.opts.reuse_upstream_generics() &&
+                    cx.tcx.upstream_monomorphizations_for(instance_def_id)
+                          .map(|set| set.contains_key(instance.substs))
+                          .unwrap_or(false);
+
+                if is_upstream_instance {
+                    // This is instantiated in another crate. It cannot be
+                    // `hidden`, whether or not we share generics ourselves.
+                } else if cx.tcx.sess.opts.share_generics() {
                     // We are in share_generics mode.
 
                     if instance_def_id.is_local() {
@@ -147,21 +160,12 @@ pub fn get_fn(
                             llvm::LLVMRustSetVisibility(llfn, llvm::Visibility::Hidden);
                         }
                     } else {
-                        // This is a monomorphization of a generic function
-                        // defined in an upstream crate.
-                        if cx.tcx.upstream_monomorphizations_for(instance_def_id)
-                                 .map(|set| set.contains_key(instance.substs))
-                                 .unwrap_or(false) {
-                            // This is instantiated in another crate. It cannot
-                            // be `hidden`.
-                        } else {
-                            // This is a local instantiation of an upstream definition.
-                            // If the current crate does not re-export it
-                            // (because it is a C library or an executable), it
-                            // will have been declared `hidden`.
-                            if !cx.tcx.local_crate_exports_generics() {
-                                llvm::LLVMRustSetVisibility(llfn, llvm::Visibility::Hidden);
-                            }
+                        // This is a local instantiation of an upstream definition.
+                        // If the current crate does not re-export it
+                        // (because it is a C library or an executable), it
+                        // will have been declared `hidden`.
+                        if !cx.tcx.local_crate_exports_generics() {
+                            llvm::LLVMRustSetVisibility(llfn, llvm::Visibility::Hidden);
                         }
                     }
                 } else {
@@ -8,14 +8,15 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use super::{FunctionDebugContext, FunctionDebugContextData};
-use super::metadata::file_metadata;
-use super::utils::{DIB, span_start};
+use super::{FunctionDebugContext, FunctionDebugContextData, create_inlined_subprogram};
+use super::metadata::{extend_scope_to_file, file_metadata, UNKNOWN_COLUMN_NUMBER};
+use super::utils::{DIB, debug_context, span_start};
 
 use llvm;
-use llvm::debuginfo::DIScope;
+use llvm::debuginfo::{DIScope, DILocation, DISubprogram};
 use common::CodegenCx;
 use rustc::mir::{Mir, SourceScope};
+use rustc::util::nodemap::DefIdMap;
 
 use libc::c_uint;
 
@@ -24,11 +25,14 @@ use syntax_pos::Pos;
 use rustc_data_structures::bit_set::BitSet;
 use rustc_data_structures::indexed_vec::{Idx, IndexVec};
 
-use syntax_pos::BytePos;
+use syntax_pos::{BytePos, Span};
 
 #[derive(Clone, Copy, Debug)]
 pub struct MirDebugScope<'ll> {
     pub scope_metadata: Option<&'ll DIScope>,
+    // The call site that the function body containing this scope was inlined
+    // at, if it was inlined by the MIR inliner.
+    pub inlined_at: Option<&'ll DILocation>,
     // Start and end offsets of the file to which this DIScope belongs.
     // These are used to quickly determine whether some span refers to the same file.
     pub file_start_pos: BytePos,
@@ -50,6 +54,7 @@ pub fn create_mir_scopes(
 ) -> IndexVec<SourceScope, MirDebugScope<'ll>> {
     let null_scope = MirDebugScope {
         scope_metadata: None,
+        inlined_at: None,
         file_start_pos: BytePos(0),
         file_end_pos: BytePos(0)
     };
@@ -71,9 +76,11 @@ pub fn create_mir_scopes(
     }
 
     // Instantiate all scopes.
+    let mut inlined_subprograms = DefIdMap();
     for idx in 0..mir.source_scopes.len() {
         let scope = SourceScope::new(idx);
-        make_mir_scope(cx, &mir, &has_variables, debug_context, scope, &mut scopes);
+        make_mir_scope(cx, &mir, &has_variables, debug_context, &mut inlined_subprograms,
+                       scope, &mut scopes);
     }
 
     scopes
@@ -83,6 +90,7 @@ fn make_mir_scope(cx: &CodegenCx<'ll, '_>,
                   mir: &Mir,
                   has_variables: &BitSet<SourceScope>,
                   debug_context: &FunctionDebugContextData<'ll>,
+                  inlined_subprograms: &mut DefIdMap<&'ll DISubprogram>,
                   scope: SourceScope,
                   scopes: &mut IndexVec<SourceScope, MirDebugScope<'ll>>) {
     if scopes[scope].is_valid() {
@@ -91,19 +99,38 @@ fn make_mir_scope(cx: &CodegenCx<'ll, '_>,
 
     let scope_data = &mir.source_scopes[scope];
     let parent_scope = if let Some(parent) = scope_data.parent_scope {
-        make_mir_scope(cx, mir, has_variables, debug_context, parent, scopes);
+        make_mir_scope(cx, mir, has_variables, debug_context, inlined_subprograms,
+                       parent, scopes);
         scopes[parent]
     } else {
         // The root is the function itself.
         let loc = span_start(cx, mir.span);
         scopes[scope] = MirDebugScope {
             scope_metadata: Some(debug_context.fn_metadata),
+            inlined_at: None,
             file_start_pos: loc.file.start_pos,
             file_end_pos: loc.file.end_pos,
         };
         return;
     };
 
+    if let Some((callee, call_span)) = scope_data.inlined {
+        // The body of an inlined function gets a subprogram of its own, so
+        // that debuggers and backtraces show a frame for it.
+        let subprogram = *inlined_subprograms.entry(callee).or_insert_with(|| {
+            create_inlined_subprogram(cx, callee, scope_data.span)
+        });
+        let inlined_at = make_inlined_at(cx, debug_context, parent_scope, call_span);
+        let loc = span_start(cx, scope_data.span);
+        scopes[scope] = MirDebugScope {
+            scope_metadata: Some(subprogram),
+            inlined_at: Some(inlined_at),
+            file_start_pos: loc.file.start_pos,
+            file_end_pos: loc.file.end_pos,
+        };
+        return;
+    }
+
     if !has_variables.contains(scope) {
         // Do not create a DIScope if there are no variables
         // defined in this MIR Scope, to avoid debuginfo bloat.
@@ -132,7 +159,40 @@ fn make_mir_scope(cx: &CodegenCx<'ll, '_>,
     };
     scopes[scope] = MirDebugScope {
         scope_metadata,
+        inlined_at: parent_scope.inlined_at,
         file_start_pos: loc.file.start_pos,
         file_end_pos: loc.file.end_pos,
     };
 }
+
+/// Creates the location of a call to an inlined function, which is part of
+/// the location of everything inlined there.
+fn make_inlined_at(cx: &CodegenCx<'ll, '_>,
+                   fn_debug_context: &FunctionDebugContextData<'ll>,
+                   caller_scope: MirDebugScope<'ll>,
+                   call_span: Span)
+                   -> &'ll DILocation {
+    let loc = span_start(cx, call_span);
+    let mut scope_metadata = caller_scope.scope_metadata.unwrap();
+    if loc.file.start_pos != caller_scope.file_start_pos {
+        scope_metadata = extend_scope_to_file(cx,
+                                              scope_metadata,
+                                              &loc.file,
+                                              fn_debug_context.defining_crate);
+    }
+
+    // Like in `set_debug_location`, leave out the column on MSVC.
+    let col = if cx.sess().target.target.options.is_like_msvc {
+        UNKNOWN_COLUMN_NUMBER
+    } else {
+        loc.col.to_usize() as c_uint
+    };
+
+    unsafe {
+        llvm::LLVMRustDIBuilderCreateInlinedAt(debug_context(cx).llcontext,
+                                               loc.line as c_uint,
+                                               col,
+                                               scope_metadata,
+                                               caller_scope.inlined_at)
+    }
+}
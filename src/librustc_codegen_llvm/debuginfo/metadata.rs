@@ -24,12 +24,15 @@ use llvm;
 use llvm::debuginfo::{DIType, DIFile, DIScope, DIDescriptor,
                       DICompositeType, DILexicalBlock, DIFlags};
 use llvm_util;
+use back::write::DWARF_OBJECT_EXTENSION;
 
 use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
 use rustc::hir::CodegenFnAttrFlags;
 use rustc::hir::def::CtorKind;
 use rustc::hir::def_id::{DefId, CrateNum, LOCAL_CRATE};
+use rustc::hir::map::DefPathData;
 use rustc::ich::NodeIdHashingMode;
+use rustc::mir::mono::DebuginfoTypeStats;
 use rustc_data_structures::fingerprint::Fingerprint;
 use rustc::ty::Instance;
 use common::{CodegenCx, C_u64};
@@ -270,6 +273,10 @@ impl RecursiveTypeDescription<'ll, 'tcx> {
                 let member_descriptions =
                     member_description_factory.create_member_descriptions(cx);
 
+                if cx.sess().debuginfo_stats() {
+                    record_debuginfo_type_stats(cx, unfinished_type, &member_descriptions);
+                }
+
                 // ... and attach them to the stub to complete it.
                 set_members_of_composite_type(cx,
                                               member_holding_stub,
@@ -280,6 +287,61 @@ impl RecursiveTypeDescription<'ll, 'tcx> {
     }
 }
 
+// Accounts the debuginfo of one composite type instance to the definition it
+// was instantiated from, for `-Z debuginfo-stats`. All instances of a generic
+// type end up under the same key, which is what makes generic bloat visible.
+fn record_debuginfo_type_stats(cx: &CodegenCx<'ll, 'tcx>,
+                               t: Ty<'tcx>,
+                               member_descriptions: &[MemberDescription<'ll>]) {
+    let def_id = match t.sty {
+        ty::Adt(def, _) => Some(def.did),
+        ty::Closure(def_id, _) |
+        ty::Generator(def_id, ..) => Some(def_id),
+        _ => None,
+    };
+    let (key, krate, module) = match def_id {
+        Some(def_id) => {
+            let (path, module) = ty::item_path::with_forced_absolute_paths(|| {
+                (cx.tcx.item_path_str(def_id),
+                 cx.tcx.item_path_str(enclosing_module(cx.tcx, def_id)))
+            });
+            (path, cx.tcx.crate_name(def_id.krate).to_string(), module)
+        }
+        None => {
+            let kind = match t.sty {
+                ty::Tuple(_) => "<tuple>",
+                ty::Dynamic(..) => "<trait object>",
+                _ => "<builtin>",
+            };
+            (kind.to_string(), kind.to_string(), kind.to_string())
+        }
+    };
+
+    let type_name = compute_debuginfo_type_name(cx, t, true);
+    let mut stats = cx.stats.borrow_mut();
+    let entry = stats.debuginfo_types.entry(key).or_insert_with(|| DebuginfoTypeStats {
+        krate,
+        module,
+        ..Default::default()
+    });
+    entry.emitted_instances += 1;
+    entry.members += member_descriptions.len();
+    entry.name_bytes += type_name.len() +
+        member_descriptions.iter().map(|m| m.name.len()).sum::<usize>();
+}
+
+// The module `def_id` is defined in. Types defined in a function body, and
+// the closures and generators of a function, are accounted to the module of
+// the function.
+fn enclosing_module(tcx: TyCtxt, mut def_id: DefId) -> DefId {
+    loop {
+        match tcx.def_key(def_id).disambiguated_data.data {
+            DefPathData::Module(..) | DefPathData::CrateRoot => return def_id,
+            _ => def_id = tcx.parent_def_id(def_id).unwrap(),
+        }
+    }
+}
+
 // Returns from the enclosing function if the type metadata with the given
 // unique id can be found in the type map
 macro_rules! return_if_metadata_created_in_meantime {
@@ -850,7 +912,17 @@ pub fn compile_unit_metadata(tcx: TyCtxt,
     let work_dir = SmallCStr::new(&tcx.sess.working_dir.0.to_string_lossy());
     let producer = CString::new(producer).unwrap();
     let flags = "\0";
-    let split_name = "\0";
+    // With split debug info, the skeleton unit left in the object file names
+    // the `.dwo` file the rest of the debug info went to.
+    let split_name = if tcx.sess.split_debuginfo() != config::SplitDebuginfo::Off {
+        tcx.output_filenames(LOCAL_CRATE)
+           .temp_path_ext(DWARF_OBJECT_EXTENSION, Some(codegen_unit_name))
+           .to_string_lossy()
+           .into_owned()
+    } else {
+        String::new()
+    };
+    let split_name = SmallCStr::new(&split_name);
 
     unsafe {
         let file_metadata = llvm::LLVMRustDIBuilderCreateFile(
@@ -864,7 +936,7 @@ pub fn compile_unit_metadata(tcx: TyCtxt,
             tcx.sess.opts.optimize != config::OptLevel::No,
             flags.as_ptr() as *const _,
             0,
-            split_name.as_ptr() as *const _);
+            split_name.as_ptr());
 
         if tcx.sess.opts.debugging_opts.profile {
             let cu_desc_metadata = llvm::LLVMRustMetadataAsValue(debug_context.llcontext,
@@ -21,7 +21,8 @@ use self::metadata::{type_metadata, file_metadata, TypeMap};
 use self::source_loc::InternalDebugLocation::{self, UnknownLocation};
 
 use llvm;
-use llvm::debuginfo::{DIFile, DIType, DIScope, DIBuilder, DISubprogram, DIArray, DIFlags};
+use llvm::debuginfo::{DIFile, DIType, DIScope, DIBuilder, DISubprogram, DIArray, DIFlags,
+                      DILocation};
 use rustc::hir::CodegenFnAttrFlags;
 use rustc::hir::def_id::{DefId, CrateNum};
 use rustc::ty::subst::{Substs, UnpackedKind};
@@ -295,7 +296,7 @@ pub fn create_function_debug_context(
             scope_line as c_uint,
             flags,
             cx.sess().opts.optimize != config::OptLevel::No,
-            llfn,
+            Some(llfn),
             template_parameters,
             None)
     };
@@ -482,12 +483,59 @@ pub fn create_function_debug_context(
     }
 }
 
+/// Creates the subprogram DIE standing in for a function whose MIR was
+/// inlined into the function being codegened. Unlike the subprogram of a
+/// regular function it isn't attached to an LLVM function; it only serves
+/// as the scope of the inlined statements.
+pub fn create_inlined_subprogram(
+    cx: &CodegenCx<'ll, '_>,
+    def_id: DefId,
+    span: Span,
+) -> &'ll DISubprogram {
+    let def_key = cx.tcx.def_key(def_id);
+    let containing_scope = namespace::item_namespace(cx, DefId {
+        krate: def_id.krate,
+        index: def_key.parent.expect("create_inlined_subprogram: missing parent?"),
+    });
+    let loc = span_start(cx, span);
+    let file_metadata = file_metadata(cx, &loc.file.name, def_id.krate);
+
+    let function_type_metadata = unsafe {
+        llvm::LLVMRustDIBuilderCreateSubroutineType(DIB(cx),
+                                                    file_metadata,
+                                                    create_DIArray(DIB(cx), &[]))
+    };
+
+    let function_name = CString::new(def_key.disambiguated_data.data.to_string()).unwrap();
+    let linkage_name = SmallCStr::new("");
+
+    unsafe {
+        llvm::LLVMRustDIBuilderCreateFunction(
+            DIB(cx),
+            containing_scope,
+            function_name.as_ptr(),
+            linkage_name.as_ptr(),
+            file_metadata,
+            loc.line as c_uint,
+            function_type_metadata,
+            is_node_local_to_unit(cx, def_id),
+            true,
+            loc.line as c_uint,
+            DIFlags::FlagPrototyped,
+            cx.sess().opts.optimize != config::OptLevel::No,
+            None,
+            create_DIArray(DIB(cx), &[]),
+            None)
+    }
+}
+
 pub fn declare_local(
     bx: &Builder<'a, 'll, 'tcx>,
     dbg_context: &FunctionDebugContext<'ll>,
     variable_name: ast::Name,
     variable_type: Ty<'tcx>,
     scope_metadata: &'ll DIScope,
+    inlined_at: Option<&'ll DILocation>,
     variable_access: VariableAccess<'_, 'll>,
     variable_kind: VariableKind,
     span: Span,
@@ -529,7 +577,10 @@ pub fn declare_local(
                 )
             };
             source_loc::set_debug_location(bx,
-                InternalDebugLocation::new(scope_metadata, loc.line, loc.col.to_usize()));
+                InternalDebugLocation::new(scope_metadata,
+                                           inlined_at,
+                                           loc.line,
+                                           loc.col.to_usize()));
             unsafe {
                 let debug_loc = llvm::LLVMGetCurrentDebugLocation(bx.llbuilder);
                 let instr = llvm::LLVMRustDIBuilderInsertDeclareAtEnd(
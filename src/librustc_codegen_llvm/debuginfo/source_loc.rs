@@ -15,7 +15,7 @@ use super::metadata::UNKNOWN_COLUMN_NUMBER;
 use super::FunctionDebugContext;
 
 use llvm;
-use llvm::debuginfo::DIScope;
+use llvm::debuginfo::{DIScope, DILocation};
 use builder::Builder;
 
 use libc::c_uint;
@@ -28,6 +28,7 @@ pub fn set_source_location(
     debug_context: &FunctionDebugContext<'ll>,
     bx: &Builder<'_, 'll, '_>,
     scope: Option<&'ll DIScope>,
+    inlined_at: Option<&'ll DILocation>,
     span: Span,
 ) {
     let function_debug_context = match *debug_context {
@@ -42,7 +43,7 @@ pub fn set_source_location(
     let dbg_loc = if function_debug_context.source_locations_enabled.get() {
         debug!("set_source_location: {}", bx.sess().source_map().span_to_string(span));
         let loc = span_start(bx.cx, span);
-        InternalDebugLocation::new(scope.unwrap(), loc.line, loc.col.to_usize())
+        InternalDebugLocation::new(scope.unwrap(), inlined_at, loc.line, loc.col.to_usize())
     } else {
         UnknownLocation
     };
@@ -64,14 +65,24 @@ pub fn start_emitting_source_locations(dbg_context: &FunctionDebugContext<'ll>)
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum InternalDebugLocation<'ll> {
-    KnownLocation { scope: &'ll DIScope, line: usize, col: usize },
+    KnownLocation {
+        scope: &'ll DIScope,
+        inlined_at: Option<&'ll DILocation>,
+        line: usize,
+        col: usize,
+    },
     UnknownLocation
 }
 
 impl InternalDebugLocation<'ll> {
-    pub fn new(scope: &'ll DIScope, line: usize, col: usize) -> Self {
+    pub fn new(scope: &'ll DIScope,
+               inlined_at: Option<&'ll DILocation>,
+               line: usize,
+               col: usize)
+               -> Self {
         KnownLocation {
             scope,
+            inlined_at,
             line,
             col,
         }
@@ -80,7 +91,7 @@ impl InternalDebugLocation<'ll> {
 
 pub fn set_debug_location(bx: &Builder<'_, 'll, '_>, debug_location: InternalDebugLocation<'ll>) {
     let metadata_node = match debug_location {
-        KnownLocation { scope, line, col } => {
+        KnownLocation { scope, inlined_at, line, col } => {
             // For MSVC, set the column number to zero.
             // Otherwise, emit it. This mimics clang behaviour.
             // See discussion in https://github.com/rust-lang/rust/issues/42921
@@ -97,7 +108,7 @@ pub fn set_debug_location(bx: &Builder<'_, 'll, '_>, debug_location: InternalDeb
                     line as c_uint,
                     col_used,
                     scope,
-                    None))
+                    inlined_at))
             }
         }
         UnknownLocation => {
@@ -68,6 +68,7 @@ extern crate tempfile;
 extern crate memmap;
 
 use back::bytecode::RLIB_BYTECODE_EXTENSION;
+use back::write::DWARF_OBJECT_EXTENSION;
 
 pub use llvm_util::target_features;
 use std::any::Any;
@@ -80,7 +81,7 @@ use rustc::middle::cstore::MetadataLoader;
 use rustc::middle::cstore::{NativeLibrary, CrateSource, LibSource};
 use rustc::middle::lang_items::LangItem;
 use rustc::session::{Session, CompileIncomplete};
-use rustc::session::config::{OutputFilenames, OutputType, PrintRequest};
+use rustc::session::config::{OutputFilenames, OutputType, PrintRequest, SplitDebuginfo};
 use rustc::ty::{self, TyCtxt};
 use rustc::util::time_graph;
 use rustc::util::nodemap::{FxHashSet, FxHashMap};
@@ -122,6 +123,7 @@ mod metadata;
 mod meth;
 mod mir;
 mod mono_item;
+mod mono_item_report;
 mod type_;
 mod type_of;
 mod value;
@@ -140,6 +142,12 @@ impl LlvmCodegenBackend {
 impl CodegenBackend for LlvmCodegenBackend {
     fn init(&self, sess: &Session) {
         llvm_util::init(sess); // Make sure llvm is inited
+
+        // Writing the `.dwo` files needs LLVM 7.
+        if sess.opts.cg.split_debuginfo != SplitDebuginfo::Off &&
+           llvm_util::get_major_version() < 7 {
+            sess.err("`-C split-debuginfo` requires LLVM 7 or later");
+        }
     }
 
     fn print(&self, req: PrintRequest, sess: &Session) {
@@ -282,6 +290,7 @@ impl ModuleCodegen {
                             emit_obj: bool,
                             emit_bc: bool,
                             emit_bc_compressed: bool,
+                            emit_dwarf_object: bool,
                             outputs: &OutputFilenames) -> CompiledModule {
         let object = if emit_obj {
             Some(outputs.temp_path(OutputType::Object, Some(&self.name)))
@@ -299,6 +308,11 @@ impl ModuleCodegen {
         } else {
             None
         };
+        let dwarf_object = if emit_dwarf_object {
+            Some(outputs.temp_path_ext(DWARF_OBJECT_EXTENSION, Some(&self.name)))
+        } else {
+            None
+        };
 
         CompiledModule {
             name: self.name.clone(),
@@ -306,6 +320,7 @@ impl ModuleCodegen {
             object,
             bytecode,
             bytecode_compressed,
+            dwarf_object,
         }
     }
 }
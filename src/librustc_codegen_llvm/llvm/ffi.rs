@@ -12,7 +12,7 @@ use super::debuginfo::{
     DIBuilder, DIDescriptor, DIFile, DILexicalBlock, DISubprogram, DIType,
     DIBasicType, DIDerivedType, DICompositeType, DIScope, DIVariable,
     DIGlobalVariableExpression, DIArray, DISubrange, DITemplateTypeParameter, DIEnumerator,
-    DINameSpace, DIFlags,
+    DINameSpace, DIFlags, DILocation,
 };
 
 use libc::{c_uint, c_int, size_t, c_char};
@@ -404,6 +404,8 @@ extern { pub type PassManagerBuilder; }
 extern { pub type ObjectFile; }
 #[repr(C)]
 pub struct SectionIterator<'a>(InvariantOpaque<'a>);
+#[repr(C)]
+pub struct SymbolIterator<'a>(InvariantOpaque<'a>);
 extern { pub type Pass; }
 extern { pub type TargetMachine; }
 extern { pub type Archive; }
@@ -447,6 +449,7 @@ pub mod debuginfo {
     pub type DISubrange = DIDescriptor;
     pub type DIEnumerator = DIDescriptor;
     pub type DITemplateTypeParameter = DIDescriptor;
+    pub type DILocation = Metadata;
 
     // These values **must** match with LLVMRustDIFlags!!
     bitflags! {
@@ -1198,6 +1201,19 @@ extern "C" {
     /// Returns the current section contents as a string buffer.
     pub fn LLVMGetSectionContents(SI: &SectionIterator) -> *const c_char;
 
+    /// Enumerates the symbols in an object file.
+    pub fn LLVMGetSymbols(ObjFile: &'a ObjectFile) -> &'a mut SymbolIterator<'a>;
+    /// Destroys a symbol iterator.
+    pub fn LLVMDisposeSymbolIterator(SI: &'a mut SymbolIterator<'a>);
+    /// Returns true if the symbol iterator is at the end of the symbol list.
+    pub fn LLVMIsSymbolIteratorAtEnd(ObjFile: &'a ObjectFile, SI: &SymbolIterator<'a>) -> Bool;
+    /// Moves the symbol iterator to point to the next symbol.
+    pub fn LLVMMoveToNextSymbol(SI: &SymbolIterator);
+    /// Returns the name of the current symbol.
+    pub fn LLVMGetSymbolName(SI: &SymbolIterator) -> *const c_char;
+    /// Returns the size of the current symbol, zero if the format doesn't record it.
+    pub fn LLVMGetSymbolSize(SI: &SymbolIterator) -> c_ulonglong;
+
     /// Reads the given file and returns it as a memory buffer. Use
     /// LLVMDisposeMemoryBuffer() to get rid of it.
     pub fn LLVMRustCreateMemoryBufferWithContentsOfFile(
@@ -1277,7 +1293,7 @@ extern "C" {
                                            ScopeLine: c_uint,
                                            Flags: DIFlags,
                                            isOptimized: bool,
-                                           Fn: &'a Value,
+                                           Fn: Option<&'a Value>,
                                            TParam: &'a DIArray,
                                            Decl: Option<&'a DIDescriptor>)
                                            -> &'a DISubprogram;
@@ -1471,8 +1487,14 @@ extern "C" {
                                                 Line: c_uint,
                                                 Column: c_uint,
                                                 Scope: &'a DIScope,
-                                                InlinedAt: Option<&'a Metadata>)
+                                                InlinedAt: Option<&'a DILocation>)
                                                 -> &'a Value;
+    pub fn LLVMRustDIBuilderCreateInlinedAt(Context: &'a Context,
+                                            Line: c_uint,
+                                            Column: c_uint,
+                                            Scope: &'a DIScope,
+                                            InlinedAt: Option<&'a DILocation>)
+                                            -> &'a DILocation;
     pub fn LLVMRustDIBuilderCreateOpDeref() -> i64;
     pub fn LLVMRustDIBuilderCreateOpPlusUconst() -> i64;
 }
@@ -1510,7 +1532,8 @@ extern "C" {
                                        TrapUnreachable: bool,
                                        Singlethread: bool,
                                        AsmComments: bool,
-                                       EmitStackSizeSection: bool)
+                                       EmitStackSizeSection: bool,
+                                       CompressDebugSections: bool)
                                        -> Option<&'static mut TargetMachine>;
     pub fn LLVMRustDisposeTargetMachine(T: &'static mut TargetMachine);
     pub fn LLVMRustAddAnalysisPasses(T: &'a TargetMachine, PM: &PassManager<'a>, M: &'a Module);
@@ -1533,6 +1556,7 @@ extern "C" {
                                    PM: &PassManager<'a>,
                                    M: &'a Module,
                                    Output: *const c_char,
+                                   DwoOutput: *const c_char,
                                    FileType: FileType)
                                    -> LLVMRustResult;
     pub fn LLVMRustPrintModule(PM: &PassManager<'a>,
@@ -120,7 +120,8 @@ impl FunctionCx<'a, 'll, 'tcx> {
 
     pub fn set_debug_loc(&mut self, bx: &Builder<'_, 'll, '_>, source_info: mir::SourceInfo) {
         let (scope, span) = self.debug_loc(source_info);
-        debuginfo::set_source_location(&self.debug_context, bx, scope, span);
+        let inlined_at = self.scopes[source_info.scope].inlined_at;
+        debuginfo::set_source_location(&self.debug_context, bx, scope, inlined_at, span);
     }
 
     pub fn debug_loc(&mut self, source_info: mir::SourceInfo) -> (Option<&'ll DIScope>, Span) {
@@ -294,6 +295,7 @@ pub fn codegen_mir(
                             scope: decl.visibility_scope,
                         });
                         declare_local(&bx, &fx.debug_context, name, layout.ty, scope.unwrap(),
+                            debug_scope.inlined_at,
                             VariableAccess::DirectVariable { alloca: place.llval },
                             VariableKind::LocalVariable, span);
                     }
@@ -492,7 +494,7 @@ fn arg_local_refs(
                     bx,
                     &fx.debug_context,
                     arg_decl.name.unwrap_or(keywords::Invalid.name()),
-                    arg_ty, scope,
+                    arg_ty, scope, None,
                     variable_access,
                     VariableKind::ArgumentVariable(arg_index + 1),
                     DUMMY_SP
@@ -584,6 +586,7 @@ fn arg_local_refs(
                     arg_decl.name.unwrap_or(keywords::Invalid.name()),
                     arg.layout.ty,
                     scope,
+                    None,
                     variable_access,
                     VariableKind::ArgumentVariable(arg_index + 1),
                     DUMMY_SP
@@ -657,6 +660,7 @@ fn arg_local_refs(
                     decl.debug_name,
                     ty,
                     scope,
+                    None,
                     variable_access,
                     VariableKind::LocalVariable,
                     DUMMY_SP
@@ -0,0 +1,216 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The report printed by `-Z mono-item-report`.
+//!
+//! Before codegen starts, every mono item of the crate is recorded under the
+//! name of the symbol defining it, together with the generic definition it is
+//! an instance of. Codegen adds the number of LLVM instructions emitted for
+//! each symbol, and once LLVM has written the object files the size of every
+//! symbol is read back from their symbol tables. The report then adds up all
+//! instances of a definition, so that e.g. every `Vec<T>::extend` ends up in
+//! one line.
+//!
+//! Items that LLVM inlined into all their callers have no symbol left and
+//! count as zero bytes. Object formats that don't record symbol sizes, like
+//! Mach-O, report zero bytes throughout; the instruction counts are still
+//! meaningful there.
+
+use llvm::{self, False, ObjectFile, mk_symbol_iter};
+use mono_item::{BaseMonoItemExt, MonoItem};
+use monomorphize::partitioning::CodegenUnit;
+use rustc::session::Session;
+use rustc::session::config::MonoItemReportFormat;
+use rustc::ty::{InstanceDef, TyCtxt};
+use rustc::util::nodemap::FxHashMap;
+use rustc_codegen_utils::CompiledModule;
+use rustc_fs_util::path2cstr;
+use serialize::json::{Json, ToJson};
+
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Bumped whenever a field of the JSON output is removed or changes meaning.
+const FORMAT_VERSION: u32 = 1;
+
+struct ItemCost {
+    /// The item itself, e.g. `<alloc::vec::Vec<T>>::push::<u8>`.
+    instance: String,
+    /// The definition it is an instance of, e.g. `<alloc::vec::Vec<T>>::push`.
+    definition: String,
+    /// Number of codegen units with a copy of the item.
+    copies: usize,
+    llvm_insns: usize,
+    bytes: u64,
+}
+
+pub struct MonoItemReport {
+    format: MonoItemReportFormat,
+    /// The mono items of the crate, keyed by their symbol name.
+    items: FxHashMap<String, ItemCost>,
+}
+
+impl MonoItemReport {
+    pub fn new<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                         codegen_units: &[Arc<CodegenUnit<'tcx>>])
+                         -> MonoItemReport {
+        let format = tcx.sess.opts.debugging_opts.mono_item_report
+            .expect("-Z mono-item-report is not enabled");
+
+        let mut items = FxHashMap::default();
+        for cgu in codegen_units {
+            for &mono_item in cgu.items().keys() {
+                let def_id = match mono_item {
+                    MonoItem::Fn(instance) => instance.def_id(),
+                    MonoItem::Static(def_id) => def_id,
+                    MonoItem::GlobalAsm(..) => continue,
+                };
+                let symbol_name = mono_item.symbol_name(tcx).as_str().to_string();
+                items.entry(symbol_name).or_insert_with(|| ItemCost {
+                    instance: instance_name(tcx, mono_item),
+                    definition: tcx.item_path_str(def_id),
+                    copies: 0,
+                    llvm_insns: 0,
+                    bytes: 0,
+                }).copies += 1;
+            }
+        }
+
+        MonoItemReport { format, items }
+    }
+
+    /// Adds the instruction counts gathered during codegen, keyed by symbol.
+    pub fn record_llvm_insns(&mut self, symbol_insns: &FxHashMap<String, usize>) {
+        for (symbol_name, &insns) in symbol_insns {
+            if let Some(item) = self.items.get_mut(symbol_name) {
+                item.llvm_insns += insns;
+            }
+        }
+    }
+
+    /// Reads the symbol sizes from the object files of `modules` and prints
+    /// the report.
+    pub fn print(mut self, sess: &Session, modules: &[CompiledModule]) {
+        for module in modules {
+            if let Some(ref object) = module.object {
+                if let Err(err) = self.record_symbol_sizes(object) {
+                    sess.warn(&format!("-Z mono-item-report: {}", err));
+                }
+            }
+        }
+
+        // Group the instances by their definition, largest first.
+        let mut definitions: FxHashMap<&str, Vec<(&str, &ItemCost)>> = FxHashMap::default();
+        for (symbol_name, item) in &self.items {
+            definitions.entry(&item.definition[..])
+                       .or_default()
+                       .push((&symbol_name[..], item));
+        }
+        let mut definitions: Vec<_> = definitions.into_iter().map(|(definition, mut items)| {
+            items.sort_by(|&(_, a), &(_, b)| {
+                (b.bytes, b.llvm_insns).cmp(&(a.bytes, a.llvm_insns))
+                    .then(a.instance.cmp(&b.instance))
+            });
+            let bytes = items.iter().map(|&(_, item)| item.bytes).sum::<u64>();
+            let llvm_insns = items.iter().map(|&(_, item)| item.llvm_insns).sum::<usize>();
+            (definition, bytes, llvm_insns, items)
+        }).collect();
+        definitions.sort_by(|a, b| (b.1, b.2).cmp(&(a.1, a.2)).then(a.0.cmp(b.0)));
+
+        match self.format {
+            MonoItemReportFormat::Text => {
+                println!("--- mono item report ---");
+                println!("{:>10} {:>10} {:>9}  {}",
+                         "bytes", "llvm insns", "instances", "definition");
+                for &(definition, bytes, llvm_insns, ref items) in &definitions {
+                    println!("{:>10} {:>10} {:>9}  {}", bytes, llvm_insns, items.len(), definition);
+                    for &(_, item) in items {
+                        println!("{:>10} {:>10} {:>9}    {}",
+                                 item.bytes, item.llvm_insns, "", item.instance);
+                    }
+                }
+            }
+            MonoItemReportFormat::Json => {
+                let definitions = definitions.iter().map(|entry| {
+                    let (definition, bytes, llvm_insns, ref items) = *entry;
+                    let items: Vec<_> = items.iter().map(|&(symbol_name, item)| {
+                        let mut obj = BTreeMap::new();
+                        obj.insert("instance".to_string(), item.instance.to_json());
+                        obj.insert("symbol".to_string(), symbol_name.to_json());
+                        obj.insert("copies".to_string(), item.copies.to_json());
+                        obj.insert("bytes".to_string(), item.bytes.to_json());
+                        obj.insert("llvm_insns".to_string(), item.llvm_insns.to_json());
+                        Json::Object(obj)
+                    }).collect();
+                    let mut obj = BTreeMap::new();
+                    obj.insert("definition".to_string(), definition.to_json());
+                    obj.insert("instances".to_string(), items.len().to_json());
+                    obj.insert("bytes".to_string(), bytes.to_json());
+                    obj.insert("llvm_insns".to_string(), llvm_insns.to_json());
+                    obj.insert("items".to_string(), Json::Array(items));
+                    Json::Object(obj)
+                }).collect();
+                let mut obj = BTreeMap::new();
+                obj.insert("format_version".to_string(), FORMAT_VERSION.to_json());
+                obj.insert("definitions".to_string(), Json::Array(definitions));
+                println!("{}", Json::Object(obj).pretty());
+            }
+        }
+    }
+
+    fn record_symbol_sizes(&mut self, path: &Path) -> Result<(), String> {
+        unsafe {
+            let buf = path2cstr(path);
+            let mb = llvm::LLVMRustCreateMemoryBufferWithContentsOfFile(buf.as_ptr())
+                .ok_or_else(|| format!("error reading object file: '{}'", path.display()))?;
+            let of = ObjectFile::new(mb)
+                .ok_or_else(|| format!("not an object file: '{}'", path.display()))?;
+            let si = mk_symbol_iter(of.llof);
+            while llvm::LLVMIsSymbolIteratorAtEnd(of.llof, si.llsi) == False {
+                let name = CStr::from_ptr(llvm::LLVMGetSymbolName(si.llsi)).to_string_lossy();
+                let size = llvm::LLVMGetSymbolSize(si.llsi) as u64;
+                // Mach-O prefixes every symbol name with an underscore.
+                let name = if !self.items.contains_key(&name[..]) && name.starts_with('_') {
+                    &name[1..]
+                } else {
+                    &name[..]
+                };
+                if let Some(item) = self.items.get_mut(name) {
+                    item.bytes += size;
+                }
+                llvm::LLVMMoveToNextSymbol(si.llsi);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Names an instance after the path of its definition and the types it is
+/// instantiated with, e.g. `generic::<u8>`.
+fn instance_name<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, mono_item: MonoItem<'tcx>) -> String {
+    let instance = match mono_item {
+        MonoItem::Fn(instance) => instance,
+        MonoItem::Static(def_id) => return tcx.item_path_str(def_id),
+        MonoItem::GlobalAsm(..) => bug!("instance_name: global_asm has no name"),
+    };
+    let mut name = tcx.item_path_str(instance.def_id());
+    let types: Vec<_> = instance.substs.types().map(|ty| ty.to_string()).collect();
+    if !types.is_empty() {
+        name.push_str(&format!("::<{}>", types.join(", ")));
+    }
+    match instance.def {
+        InstanceDef::Item(_) => {}
+        // Shims share the definition of the item they stand in for.
+        _ => name.push_str(" (shim)"),
+    }
+    name
+}
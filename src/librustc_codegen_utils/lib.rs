@@ -83,6 +83,8 @@ pub struct CompiledModule {
     pub object: Option<PathBuf>,
     pub bytecode: Option<PathBuf>,
     pub bytecode_compressed: Option<PathBuf>,
+    /// The `.dwo` file the debug info was split into, see `-C split-debuginfo`.
+    pub dwarf_object: Option<PathBuf>,
 }
 
 pub fn find_library(name: &str, search_paths: &[PathBuf], sess: &Session)
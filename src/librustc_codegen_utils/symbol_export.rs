@@ -316,6 +316,12 @@ fn upstream_monomorphizations_provider<'a, 'tcx>(
     };
 
     for &cnum in cnums.iter() {
+        // Crates only loaded for their macros are not linked, so nothing they
+        // export can be linked against.
+        if tcx.dep_kind(cnum).macros_only() {
+            continue
+        }
+
         for &(ref exported_symbol, _) in tcx.exported_symbols(cnum).iter() {
             if let &ExportedSymbol::Generic(def_id, substs) = exported_symbol {
                 let substs_map = instances.entry(def_id).or_default();
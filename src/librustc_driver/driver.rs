@@ -332,6 +332,10 @@ pub fn compile_input(
                     tcx.print_debug_stats();
                 }
 
+                if tcx.sess.opts.debugging_opts.query_stats {
+                    tcx.print_query_stats();
+                }
+
                 if tcx.sess.opts.output_types.contains_key(&OutputType::Mir) {
                     if let Err(e) = mir::transform::dump_mir::emit_mir(tcx, &outputs) {
                         sess.err(&format!("could not emit MIR: {}", e));
@@ -360,6 +364,10 @@ pub fn compile_input(
         if sess.opts.debugging_opts.profile_json {
             sess.save_json_results();
         }
+
+        if sess.opts.debugging_opts.profile_trace {
+            sess.save_trace_events();
+        }
     }
 
     controller_entry_point!(
@@ -843,6 +851,12 @@ where
 
     let disambiguator = compute_crate_disambiguator(sess);
     sess.crate_disambiguator.set(disambiguator);
+
+    if sess.opts.debugging_opts.incremental_dump {
+        rustc_incremental::dump_session_directory(sess, &crate_name, disambiguator);
+        return Err(CompileIncomplete::Stopped);
+    }
+
     rustc_incremental::prepare_session_directory(sess, &crate_name, disambiguator);
 
     if sess.opts.incremental.is_some() {
@@ -128,6 +128,7 @@ pub mod profile;
 pub mod driver;
 pub mod pretty;
 mod derive_registrar;
+mod suggestions;
 
 pub mod target_features {
     use syntax::ast;
@@ -470,6 +471,13 @@ pub fn run_compiler<'a>(args: &[String],
 
     let (sopts, cfg) = config::build_session_options_and_crate_config(&matches);
 
+    if let Some(apply) = sopts.apply_suggestions {
+        let file_loader = file_loader.unwrap_or(box RealFileLoader);
+        return suggestions::run_compiler(
+            apply, matches, sopts, cfg, callbacks, file_loader, emitter_dest,
+        );
+    }
+
     driver::spawn_thread_pool(sopts, |sopts| {
         run_compiler_with_pool(matches, sopts, cfg, callbacks, file_loader, emitter_dest)
     })
@@ -991,9 +999,10 @@ impl RustcDefaultCalls {
                 &Input::File(ref ifile) => {
                     let path = &(*ifile);
                     let mut v = Vec::new();
-                    locator::list_file_metadata(&sess.target.target,
+                    locator::list_file_metadata(sess,
                                                 path,
                                                 &*cstore.metadata_loader,
+                                                sess.opts.debugging_opts.ls_json,
                                                 &mut v)
                             .unwrap();
                     println!("{}", String::from_utf8(v).unwrap());
@@ -0,0 +1,535 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `--apply-suggestions`: applying the machine-applicable suggestions of the
+//! emitted diagnostics to the source files.
+//!
+//! The edited sources are not written out right away but kept in an overlay
+//! on top of the file loader. This lets `fixpoint` compile the crate again
+//! against the edited sources, and leaves the files alone in `diff` mode.
+//!
+//! When several suggestions touch the same code, the first one in source
+//! order wins and the others are dropped. With `fixpoint` the dropped ones
+//! get another chance in the next pass, if they still apply.
+
+use rustc::session::{CompileIncomplete, CompileResult, Session};
+use rustc::session::config::{self, ApplySuggestions, ApplySuggestionsMode};
+use rustc::util::common::ErrorReported;
+use rustc_data_structures::sync::{self, Lrc};
+use errors::{self, CodeSuggestion};
+use syntax;
+use syntax::ast;
+use syntax::source_map::{FileLoader, SourceMap};
+use syntax_pos::FileName;
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use driver;
+use getopts;
+use super::{run_compiler_with_pool, CompilerCalls, RustcDefaultCalls};
+
+/// The number of passes `fixpoint` makes before giving up.
+const MAX_PASSES: usize = 16;
+
+/// Compiles the crate and applies the machine-applicable suggestions.
+///
+/// With `fixpoint`, the crate is first compiled without codegen and with the
+/// diagnostics going nowhere until no new suggestions come up. These passes
+/// use the default callbacks, since `CompilerCalls` can only build a single
+/// controller. The last pass is a regular compilation of the edited sources.
+pub fn run_compiler<'a>(
+    apply: ApplySuggestions,
+    matches: getopts::Matches,
+    sopts: config::Options,
+    cfg: ast::CrateConfig,
+    callbacks: Box<dyn CompilerCalls<'a> + sync::Send + 'a>,
+    file_loader: Box<dyn FileLoader + Send + Sync + 'static>,
+    emitter_dest: Option<Box<dyn Write + Send>>,
+) -> (CompileResult, Option<Session>) {
+    let overlay = Overlay::new(file_loader);
+
+    let mut converged = true;
+    // Standard input can only be read once.
+    if apply.fixpoint && matches.free.iter().all(|input| input != "-") {
+        converged = false;
+        let mut pass_opts = sopts.clone();
+        pass_opts.debugging_opts.no_codegen = true;
+        for _ in 0..MAX_PASSES {
+            let applied = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                // Every pass gets its own interners, so the `--cfg`s, which
+                // are interned, have to be parsed again.
+                syntax::with_globals(|| {
+                    let cfg = config::parse_cfgspecs(matches.opt_strs("cfg"));
+                    driver::spawn_thread_pool(pass_opts.clone(), |sopts| {
+                        let (_, sess) = run_compiler_with_pool(
+                            matches.clone(),
+                            sopts,
+                            cfg,
+                            Box::new(RustcDefaultCalls),
+                            Some(Box::new(overlay.clone())),
+                            Some(Box::new(io::sink())),
+                        );
+                        sess.map_or(0, |sess| apply_suggestions(&sess, &overlay).applied)
+                    })
+                })
+            }));
+            match applied {
+                Ok(0) => {
+                    converged = true;
+                    break;
+                }
+                Ok(_) => {}
+                // The last pass reports the error.
+                Err(ref value) if value.is::<errors::FatalErrorMarker>() => {
+                    converged = true;
+                    break;
+                }
+                Err(value) => panic::resume_unwind(value),
+            }
+        }
+    }
+
+    let (mut result, sess) = driver::spawn_thread_pool(sopts, |sopts| {
+        run_compiler_with_pool(
+            matches,
+            sopts,
+            cfg,
+            callbacks,
+            Some(Box::new(overlay.clone())),
+            emitter_dest,
+        )
+    });
+    let sess = match sess {
+        Some(sess) => sess,
+        None => return (result, None),
+    };
+
+    let applied = apply_suggestions(&sess, &overlay);
+    if !converged {
+        sess.warn(&format!("suggestions kept coming up after {} passes", MAX_PASSES));
+    } else if applied.overlapping > 0 && !apply.fixpoint {
+        sess.note_without_error(&format!(
+            "{} suggestion(s) overlapped with others and were not applied, \
+             use `--apply-suggestions={},fixpoint` to apply them as well",
+            applied.overlapping,
+            match apply.mode {
+                ApplySuggestionsMode::InPlace => "in-place",
+                ApplySuggestionsMode::Diff => "diff",
+            }
+        ));
+    }
+
+    if let Err(e) = overlay.finish(apply.mode) {
+        sess.err(&format!("failed to apply suggestions: {}", e));
+        result = Err(CompileIncomplete::Errored(ErrorReported));
+    }
+
+    (result, Some(sess))
+}
+
+/// A file loader that serves the edited sources in place of the originals.
+#[derive(Clone)]
+struct Overlay {
+    loader: Arc<dyn FileLoader + Send + Sync>,
+    files: Arc<Mutex<BTreeMap<PathBuf, String>>>,
+}
+
+impl Overlay {
+    fn new(loader: Box<dyn FileLoader + Send + Sync>) -> Overlay {
+        Overlay {
+            loader: Arc::from(loader),
+            files: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Writes the edited sources back to their files, or prints a diff
+    /// against the originals.
+    fn finish(&self, mode: ApplySuggestionsMode) -> io::Result<()> {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        for (path, src) in self.files.lock().unwrap().iter() {
+            let with_path = |e: io::Error| {
+                io::Error::new(e.kind(), format!("{}: {}", path.display(), e))
+            };
+            let original = self.loader.read_file(path).map_err(with_path)?;
+            if original == *src {
+                continue;
+            }
+            match mode {
+                ApplySuggestionsMode::InPlace => fs::write(path, src).map_err(with_path)?,
+                ApplySuggestionsMode::Diff => {
+                    let name = path.display().to_string();
+                    stdout.write_all(unified_diff(&name, &original, src).as_bytes())?;
+                }
+            }
+        }
+        stdout.flush()
+    }
+}
+
+impl FileLoader for Overlay {
+    fn file_exists(&self, path: &Path) -> bool {
+        self.files.lock().unwrap().contains_key(path) || self.loader.file_exists(path)
+    }
+
+    fn abs_path(&self, path: &Path) -> Option<PathBuf> {
+        self.loader.abs_path(path)
+    }
+
+    fn read_file(&self, path: &Path) -> io::Result<String> {
+        match self.files.lock().unwrap().get(path) {
+            Some(src) => Ok(src.clone()),
+            None => self.loader.read_file(path),
+        }
+    }
+}
+
+/// A replacement of the bytes `lo..hi` of a source file, as the compiler saw
+/// it, i.e. without a byte order mark.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Edit {
+    path: PathBuf,
+    lo: usize,
+    hi: usize,
+    snippet: String,
+}
+
+impl Edit {
+    /// Two insertions at the same position conflict as well, since there is
+    /// no telling which one should go first.
+    fn overlaps(&self, other: &Edit) -> bool {
+        self.path == other.path &&
+            (self.lo == other.lo || (self.lo < other.hi && other.lo < self.hi))
+    }
+}
+
+struct Applied {
+    applied: usize,
+    overlapping: usize,
+}
+
+/// Applies the machine-applicable suggestions collected by the session to the
+/// sources in `overlay`.
+fn apply_suggestions(sess: &Session, overlay: &Overlay) -> Applied {
+    let mut sources = BTreeMap::new();
+    let mut candidates = sess.diagnostic()
+        .take_machine_applicable_suggestions()
+        .iter()
+        .filter_map(|suggestion| suggestion_edits(sess.source_map(), suggestion, &mut sources))
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+
+    let mut taken: Vec<Edit> = Vec::new();
+    let mut applied = 0;
+    let mut overlapping = 0;
+    for edits in candidates {
+        if edits.iter().any(|edit| taken.iter().any(|t| edit.overlaps(t))) {
+            overlapping += 1;
+        } else {
+            taken.extend(edits);
+            applied += 1;
+        }
+    }
+    taken.sort();
+
+    let mut files = overlay.files.lock().unwrap();
+    for (path, src) in sources {
+        let current = match files.get(&path) {
+            Some(current) => Ok(current.clone()),
+            None => overlay.loader.read_file(&path),
+        };
+        let current = match current {
+            Ok(current) => current,
+            Err(e) => {
+                sess.warn(&format!("not applying suggestions to {}: {}", path.display(), e));
+                continue;
+            }
+        };
+        let (bom, text) = if current.starts_with('\u{feff}') {
+            current.split_at(3)
+        } else {
+            ("", &current[..])
+        };
+        if text != &src[..] {
+            sess.warn(&format!(
+                "not applying suggestions to {}: the file changed during compilation",
+                path.display()
+            ));
+            continue;
+        }
+
+        let mut edited = String::from(bom);
+        let mut pos = 0;
+        for edit in taken.iter().filter(|edit| edit.path == path) {
+            edited.push_str(&text[pos..edit.lo]);
+            edited.push_str(&edit.snippet);
+            pos = edit.hi;
+        }
+        edited.push_str(&text[pos..]);
+        files.insert(path, edited);
+    }
+
+    Applied { applied, overlapping }
+}
+
+/// The edits making up `suggestion`, or `None` if it cannot be applied.
+fn suggestion_edits(source_map: &SourceMap,
+                    suggestion: &CodeSuggestion,
+                    sources: &mut BTreeMap<PathBuf, Lrc<String>>)
+                    -> Option<Vec<Edit>> {
+    // Suggestions with several alternatives need someone to pick one.
+    if suggestion.substitutions.len() != 1 {
+        return None;
+    }
+
+    let mut edits = Vec::new();
+    for part in &suggestion.substitutions[0].parts {
+        let file = source_map.lookup_char_pos(part.span.lo()).file;
+        let path = match file.unmapped_path {
+            Some(FileName::Real(ref path)) => path.clone(),
+            _ => return None,
+        };
+        let src = match file.src {
+            Some(ref src) if part.span.hi() <= file.end_pos => src.clone(),
+            _ => return None,
+        };
+        let lo = (part.span.lo().0 - file.start_pos.0) as usize;
+        let hi = (part.span.hi().0 - file.start_pos.0) as usize;
+        if src[lo..hi] == part.snippet[..] {
+            continue;
+        }
+        sources.entry(path.clone()).or_insert(src);
+        edits.push(Edit {
+            path,
+            lo,
+            hi,
+            snippet: part.snippet.clone(),
+        });
+    }
+
+    if edits.is_empty() {
+        return None;
+    }
+    edits.sort();
+    Some(edits)
+}
+
+/// The number of unchanged lines shown around the changes of a diff.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DiffLine {
+    Same(usize, usize),
+    Delete(usize, usize),
+    Insert(usize, usize),
+}
+
+impl DiffLine {
+    /// The position of the line in the old and the new file.
+    fn position(self) -> (usize, usize) {
+        match self {
+            DiffLine::Same(x, y) | DiffLine::Delete(x, y) | DiffLine::Insert(x, y) => (x, y),
+        }
+    }
+
+    fn is_same(self) -> bool {
+        match self {
+            DiffLine::Same(..) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A unified diff between `old` and `new`, both named `name`.
+fn unified_diff(name: &str, old: &str, new: &str) -> String {
+    let old = lines(old);
+    let new = lines(new);
+    let diff = diff_lines(&old, &new);
+
+    let mut out = format!("--- {}\n+++ {}\n", name, name);
+    let mut i = 0;
+    while i < diff.len() {
+        if diff[i].is_same() {
+            i += 1;
+            continue;
+        }
+
+        // A hunk goes on as long as the changes are less than two contexts
+        // apart.
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let mut last_change = i;
+        let mut end = i;
+        while end < diff.len() && end - last_change <= 2 * CONTEXT_LINES {
+            if !diff[end].is_same() {
+                last_change = end;
+            }
+            end += 1;
+        }
+        let end = cmp::min(last_change + CONTEXT_LINES + 1, diff.len());
+        let hunk = &diff[start..end];
+
+        let old_len = hunk.iter().filter(|l| !is_insert(l)).count();
+        let new_len = hunk.iter().filter(|l| !is_delete(l)).count();
+        let (old_start, new_start) = hunk[0].position();
+        // An empty range starts at the line before it.
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_len == 0 { old_start } else { old_start + 1 },
+            old_len,
+            if new_len == 0 { new_start } else { new_start + 1 },
+            new_len
+        ));
+        for line in hunk {
+            let (prefix, text) = match *line {
+                DiffLine::Same(x, _) => (' ', old[x]),
+                DiffLine::Delete(x, _) => ('-', old[x]),
+                DiffLine::Insert(_, y) => ('+', new[y]),
+            };
+            out.push(prefix);
+            out.push_str(text);
+            if !text.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+        i = end;
+    }
+    out
+}
+
+fn is_insert(line: &DiffLine) -> bool {
+    match *line {
+        DiffLine::Insert(..) => true,
+        _ => false,
+    }
+}
+
+fn is_delete(line: &DiffLine) -> bool {
+    match *line {
+        DiffLine::Delete(..) => true,
+        _ => false,
+    }
+}
+
+/// Splits `s` into lines, keeping the line terminators.
+fn lines(s: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (i, _) in s.match_indices('\n') {
+        lines.push(&s[start..i + 1]);
+        start = i + 1;
+    }
+    if start < s.len() {
+        lines.push(&s[start..]);
+    }
+    lines
+}
+
+/// A shortest edit script turning `old` into `new`, using Myers' algorithm.
+///
+/// The `(x, y)` of every line are the number of old and new lines before it.
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<DiffLine> {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let max = n + m;
+    let offset = max + 1;
+    let index = |k: isize| (k + offset) as usize;
+
+    // `v[index(k)]` is the furthest `x` reached on diagonal `k`, `trace[d]` is
+    // `v` as it was before looking for paths with `d` changes.
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut trace = Vec::new();
+    'search: for d in 0..max + 1 {
+        trace.push(v.clone());
+        let mut k = -d;
+        while k <= d {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+            k += 2;
+        }
+    }
+
+    let mut script = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            script.push(DiffLine::Same(x as usize, y as usize));
+        }
+        if d > 0 {
+            if x == prev_x {
+                script.push(DiffLine::Insert(x as usize, prev_y as usize));
+            } else {
+                script.push(DiffLine::Delete(prev_x as usize, y as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    script.reverse();
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::unified_diff;
+
+    #[test]
+    fn unified_diff_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn";
+        assert_eq!(unified_diff("f.rs", old, new), "\
+--- f.rs
++++ f.rs
+@@ -1,5 +1,5 @@
+ a
+-b
++B
+ c
+ d
+ e
+@@ -11,3 +11,4 @@
+ k
+ l
+ m
++n
+\\ No newline at end of file
+");
+        assert_eq!(unified_diff("f.rs", old, old), "--- f.rs\n+++ f.rs\n");
+    }
+}
@@ -42,7 +42,7 @@ use rustc_data_structures::stable_hasher::StableHasher;
 
 use std::borrow::Cow;
 use std::cell::Cell;
-use std::{error, fmt};
+use std::{error, fmt, mem};
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering::SeqCst;
 use std::panic;
@@ -296,6 +296,10 @@ pub struct Handler {
     // this handler. These hashes is used to avoid emitting the same error
     // twice.
     emitted_diagnostics: Lock<FxHashSet<u128>>,
+
+    /// The machine-applicable suggestions of all emitted diagnostics, if
+    /// `flags.collect_machine_applicable_suggestions` is set.
+    machine_applicable_suggestions: Lock<Vec<CodeSuggestion>>,
 }
 
 fn default_track_diagnostic(_: &Diagnostic) {}
@@ -320,6 +324,10 @@ pub struct HandlerFlags {
     /// show macro backtraces even for non-local macros.
     /// (rustc: see `-Z external-macro-backtrace`)
     pub external_macro_backtrace: bool,
+    /// If true, keep the machine-applicable suggestions of emitted diagnostics
+    /// around for `take_machine_applicable_suggestions`.
+    /// (rustc: see `--apply-suggestions`)
+    pub collect_machine_applicable_suggestions: bool,
 }
 
 impl Drop for Handler {
@@ -385,6 +393,7 @@ impl Handler {
             taught_diagnostics: Default::default(),
             emitted_diagnostic_codes: Default::default(),
             emitted_diagnostics: Default::default(),
+            machine_applicable_suggestions: Default::default(),
         }
     }
 
@@ -403,6 +412,11 @@ impl Handler {
         self.err_count.store(0, SeqCst);
     }
 
+    /// Returns the machine-applicable suggestions collected since the last call.
+    pub fn take_machine_applicable_suggestions(&self) -> Vec<CodeSuggestion> {
+        mem::replace(&mut *self.machine_applicable_suggestions.borrow_mut(), Vec::new())
+    }
+
     pub fn struct_dummy<'a>(&'a self) -> DiagnosticBuilder<'a> {
         DiagnosticBuilder::new(self, Level::Cancelled, "")
     }
@@ -720,6 +734,14 @@ impl Handler {
         // one:
         if self.emitted_diagnostics.borrow_mut().insert(diagnostic_hash) {
             self.emitter.borrow_mut().emit(db);
+            if self.flags.collect_machine_applicable_suggestions {
+                self.machine_applicable_suggestions.borrow_mut().extend(
+                    diagnostic.suggestions
+                              .iter()
+                              .filter(|s| s.applicability == Applicability::MachineApplicable)
+                              .cloned(),
+                );
+            }
             if db.is_error() {
                 self.bump_err_count();
             }
@@ -36,6 +36,7 @@ mod persist;
 
 pub use assert_dep_graph::assert_dep_graph;
 pub use persist::dep_graph_tcx_init;
+pub use persist::dump_session_directory;
 pub use persist::load_dep_graph;
 pub use persist::load_query_result_cache;
 pub use persist::LoadResult;
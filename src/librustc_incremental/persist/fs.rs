@@ -60,6 +60,9 @@
 //! compilation directory for private session directories that are not in use
 //! any more and will delete those. It will also delete any finalized session
 //! directories for a given crate except for the most recent one.
+//! With `-Z incremental-size-limit`, it then deletes the most recent session
+//! directories of the least recently compiled crates as well, until the
+//! incremental compilation directory fits into the given size.
 //!
 //! ## Synchronization
 //!
@@ -113,6 +116,13 @@
 //! It might be a good idea though to try and detect whether we are on an
 //! unsupported file system and emit a warning in that case. This is not yet
 //! implemented.
+//!
+//! ## Sharing Sessions
+//!
+//! With `-Z incremental-shared-cache`, finalized session directories are also
+//! published to a content-addressed store that several incremental directories
+//! can share, and a session directory with nothing to copy from locally starts
+//! out as a copy of the session last published there. See `shared.rs`.
 
 use rustc::session::{Session, CrateDisambiguator};
 use rustc_fs_util::{link_or_copy, LinkOrCopy};
@@ -128,6 +138,8 @@ use std::time::{UNIX_EPOCH, SystemTime, Duration};
 
 use rand::{RngCore, thread_rng};
 
+use super::shared;
+
 const LOCK_FILE_EXT: &'static str = ".lock";
 const DEP_GRAPH_FILENAME: &'static str = "dep-graph.bin";
 const WORK_PRODUCTS_FILENAME: &'static str = "work-products.bin";
@@ -149,10 +161,16 @@ pub fn dep_graph_path_from(incr_comp_session_dir: &Path) -> PathBuf {
 pub fn work_products_path(sess: &Session) -> PathBuf {
     in_incr_comp_dir_sess(sess, WORK_PRODUCTS_FILENAME)
 }
+pub fn work_products_path_from(incr_comp_session_dir: &Path) -> PathBuf {
+    in_incr_comp_dir(incr_comp_session_dir, WORK_PRODUCTS_FILENAME)
+}
 
 pub fn query_cache_path(sess: &Session) -> PathBuf {
     in_incr_comp_dir_sess(sess, QUERY_CACHE_FILENAME)
 }
+pub fn query_cache_path_from(incr_comp_session_dir: &Path) -> PathBuf {
+    in_incr_comp_dir(incr_comp_session_dir, QUERY_CACHE_FILENAME)
+}
 
 pub fn lock_file_path(session_dir: &Path) -> PathBuf {
     let crate_dir = session_dir.parent().unwrap();
@@ -248,6 +266,14 @@ pub fn prepare_session_directory(sess: &Session,
 
         let source_directory = if let Some(dir) = source_directory {
             dir
+        } else if shared::import_session(sess, &crate_dir, &session_dir) {
+            // There's nowhere to copy from locally, but the shared cache had
+            // a session for this crate.
+            debug!("no source directory found. Continuing with session \
+                    directory imported from the shared cache.");
+
+            sess.init_incr_comp_session(session_dir, directory_lock, true);
+            return
         } else {
             // There's nowhere to copy from, we're done
             debug!("no source directory found. Continuing with empty session \
@@ -363,6 +389,12 @@ pub fn finalize_session_directory(sess: &Session, svh: Svh) {
     let new_path = incr_comp_session_dir.parent().unwrap().join(new_sub_dir_name);
     debug!("finalize_session_directory() - new path: {}", new_path.display());
 
+    if !sess.has_errors() {
+        // The directory is still ours alone, so nothing changes while it is
+        // published.
+        shared::export_session(sess, &incr_comp_session_dir);
+    }
+
     match std_fs::rename(&*incr_comp_session_dir, &new_path) {
         Ok(_) => {
             debug!("finalize_session_directory() - directory renamed successfully");
@@ -520,6 +552,24 @@ fn delete_session_dir_lock_file(sess: &Session,
     }
 }
 
+/// Find the session directory the next session of this crate would start
+/// from, without creating or locking anything.
+pub fn find_last_session_directory(sess: &Session,
+                                   crate_name: &str,
+                                   crate_disambiguator: CrateDisambiguator)
+                                   -> Option<PathBuf> {
+    if sess.opts.incremental.is_none() {
+        return None
+    }
+
+    let crate_dir = crate_path(sess, crate_name, crate_disambiguator);
+    if !crate_dir.is_dir() {
+        return None
+    }
+
+    find_source_directory(&crate_dir, &FxHashSet::default())
+}
+
 /// Find the most recent published session directory that is not in the
 /// ignore-list.
 fn find_source_directory(crate_dir: &Path,
@@ -860,9 +910,108 @@ pub fn garbage_collect_session_directories(sess: &Session) -> io::Result<()> {
         mem::drop(lock);
     }
 
+    if let Some(limit) = sess.opts.debugging_opts.incremental_size_limit {
+        let incr_comp_directory = crate_directory.parent().unwrap();
+        enforce_size_limit(sess, incr_comp_directory, limit as u64 * 1024 * 1024)?;
+    }
+
+    Ok(())
+}
+
+/// Deletes finalized session directories of any crate in the incremental
+/// compilation directory, least recently used first, until all session
+/// directories together take up no more than `limit` bytes. Directories that
+/// are in use, like the one of the current session, only count towards the
+/// total. Files hard-linked between session directories are counted once per
+/// directory, so this errs on the side of deleting too much.
+fn enforce_size_limit(sess: &Session, incr_comp_directory: &Path, limit: u64) -> io::Result<()> {
+    let current_session_directory = sess.incr_comp_session_dir().clone();
+
+    let mut total_size = 0;
+    let mut candidates = vec![];
+    for crate_directory in try!(incr_comp_directory.read_dir()) {
+        let crate_directory = match crate_directory {
+            Ok(crate_directory) => crate_directory.path(),
+            _ => continue,
+        };
+        let dir_entries = match crate_directory.read_dir() {
+            Ok(dir_entries) => dir_entries,
+            _ => continue,
+        };
+
+        for dir_entry in dir_entries.filter_map(|dir_entry| dir_entry.ok()) {
+            let directory_name = dir_entry.file_name().to_string_lossy().into_owned();
+            if !is_session_directory(&directory_name) {
+                continue
+            }
+
+            let path = dir_entry.path();
+            let size = directory_size(&path);
+            total_size += size;
+
+            if path != current_session_directory && is_finalized(&directory_name) {
+                if let Ok(timestamp) = extract_timestamp_from_session_dir(&directory_name) {
+                    candidates.push((timestamp, path, size));
+                }
+            }
+        }
+    }
+
+    // A session directory is created every time a crate is compiled, so the
+    // oldest ones belong to the least recently used crates.
+    candidates.sort_by(|a, b| a.0.cmp(&b.0));
+
+    for (_, path, size) in candidates {
+        if total_size <= limit {
+            break
+        }
+
+        let lock_file_path = lock_file_path(&path);
+        let lock = match flock::Lock::new(&lock_file_path,
+                                          false,  // don't wait
+                                          false,  // don't create the lock-file
+                                          true) { // get an exclusive lock
+            Ok(lock) => lock,
+            Err(_) => {
+                debug!("enforce_size_limit() - not collecting `{}`, still in use",
+                       path.display());
+                continue
+            }
+        };
+
+        if let Err(err) = safe_remove_dir_all(&path) {
+            sess.warn(&format!("Failed to delete incremental compilation session \
+                                directory `{}` to stay under the size limit: {}",
+                               path.display(),
+                               err));
+            continue
+        }
+        delete_session_dir_lock_file(sess, &lock_file_path);
+        mem::drop(lock);
+
+        total_size -= size;
+        if sess.opts.debugging_opts.incremental_info {
+            println!("[incremental] deleted session directory `{}` ({} bytes) \
+                      to stay under the size limit",
+                     path.display(), size);
+        }
+    }
+
     Ok(())
 }
 
+fn directory_size(path: &Path) -> u64 {
+    match path.read_dir() {
+        Ok(dir_entries) => {
+            dir_entries.filter_map(|dir_entry| dir_entry.ok())
+                       .filter_map(|dir_entry| dir_entry.metadata().ok())
+                       .map(|metadata| metadata.len())
+                       .sum()
+        }
+        Err(_) => 0,
+    }
+}
+
 fn all_except_most_recent(deletion_candidates: Vec<(SystemTime, PathBuf, Option<flock::Lock>)>)
                           -> FxHashMap<PathBuf, Option<flock::Lock>> {
     let most_recent = deletion_candidates.iter()
@@ -11,13 +11,17 @@
 //! Code to save/load the dep-graph from files.
 
 use rustc_data_structures::fx::FxHashMap;
-use rustc::dep_graph::{PreviousDepGraph, SerializedDepGraph, WorkProduct, WorkProductId};
-use rustc::session::Session;
+use rustc_data_structures::indexed_vec::Idx;
+use rustc::dep_graph::{PreviousDepGraph, SerializedDepGraph, SerializedDepNodeIndex};
+use rustc::dep_graph::{WorkProduct, WorkProductId};
+use rustc::session::{Session, CrateDisambiguator};
 use rustc::ty::TyCtxt;
 use rustc::ty::query::OnDiskCache;
 use rustc::util::common::time_ext;
 use rustc_serialize::Decodable as RustcDecodable;
 use rustc_serialize::opaque::Decoder;
+use std::collections::BTreeMap;
+use std::fs as std_fs;
 use std::path::Path;
 use std;
 
@@ -215,3 +219,98 @@ pub fn load_query_result_cache<'sess>(sess: &'sess Session) -> OnDiskCache<'sess
         _ => OnDiskCache::new_empty(sess.source_map())
     }
 }
+
+/// Prints what the session directory the next session of this crate would
+/// start from contains, for `-Z incremental-dump`.
+pub fn dump_session_directory(sess: &Session,
+                              crate_name: &str,
+                              crate_disambiguator: CrateDisambiguator) {
+    if sess.opts.incremental.is_none() {
+        return
+    }
+    let session_dir = match find_last_session_directory(sess, crate_name, crate_disambiguator) {
+        Some(session_dir) => session_dir,
+        None => {
+            println!("[incremental] session directory: none");
+            return
+        }
+    };
+
+    let mut files = BTreeMap::new();
+    if let Ok(entries) = session_dir.read_dir() {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            files.insert(entry.file_name().to_string_lossy().into_owned(), size);
+        }
+    }
+    println!("[incremental] session directory `{}`: {} files, {} bytes",
+             session_dir.display(), files.len(), files.values().sum::<u64>());
+    for (file_name, size) in &files {
+        println!("[incremental]     {:>12} {}", size, file_name);
+    }
+
+    let dep_graph = match load_data(false, &dep_graph_path_from(&session_dir)) {
+        LoadResult::Ok { data: (bytes, start_pos) } => {
+            let mut decoder = Decoder::new(&bytes, start_pos);
+            u64::decode(&mut decoder).and_then(|_| SerializedDepGraph::decode(&mut decoder)).ok()
+        }
+        _ => None,
+    };
+    let dep_graph = match dep_graph {
+        Some(dep_graph) => dep_graph,
+        None => {
+            println!("[incremental] dep-graph: none");
+            return
+        }
+    };
+    println!("[incremental] dep-graph: {} nodes, {} edges",
+             dep_graph.nodes.len(), dep_graph.edge_list_data.len());
+    print_nodes_by_kind(&dep_graph, (0..dep_graph.nodes.len()).map(SerializedDepNodeIndex::new));
+
+    if sess.opts.debugging_opts.incremental_queries {
+        let cache = match load_data(false, &query_cache_path_from(&session_dir)) {
+            LoadResult::Ok { data: (bytes, start_pos) } => OnDiskCache::new(sess, bytes, start_pos),
+            _ => OnDiskCache::new_empty(sess.source_map()),
+        };
+        let results = cache.cached_results();
+        println!("[incremental] query cache: {} results, {} nodes with diagnostics",
+                 results.len(), cache.cached_diagnostics().len());
+        print_nodes_by_kind(&dep_graph, results.into_iter());
+    }
+
+    let work_products = match load_data(false, &work_products_path_from(&session_dir)) {
+        LoadResult::Ok { data: (bytes, start_pos) } => {
+            let mut decoder = Decoder::new(&bytes, start_pos);
+            Vec::<SerializedWorkProduct>::decode(&mut decoder).unwrap_or_default()
+        }
+        _ => Vec::new(),
+    };
+    println!("[incremental] work products: {}", work_products.len());
+    for swp in &work_products {
+        println!("[incremental]     {}", swp.work_product.cgu_name);
+        for &(kind, ref file_name) in &swp.work_product.saved_files {
+            let size = std_fs::metadata(session_dir.join(file_name)).map(|m| m.len());
+            match size {
+                Ok(size) => println!("[incremental]         {:>12} {} ({:?})",
+                                     size, file_name, kind),
+                Err(_) => println!("[incremental]         {:>12} {} ({:?})",
+                                   "missing", file_name, kind),
+            }
+        }
+    }
+}
+
+/// Prints how many of `nodes` there are of each kind, most common first.
+fn print_nodes_by_kind<I>(dep_graph: &SerializedDepGraph, nodes: I)
+    where I: Iterator<Item=SerializedDepNodeIndex>
+{
+    let mut counts = FxHashMap::default();
+    for index in nodes {
+        *counts.entry(format!("{:?}", dep_graph.nodes[index].kind)).or_insert(0) += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    for (kind, count) in counts {
+        println!("[incremental]     {:>12} {}", count, kind);
+    }
+}
@@ -17,6 +17,7 @@ mod dirty_clean;
 mod fs;
 mod load;
 mod save;
+mod shared;
 mod work_product;
 mod file_format;
 
@@ -26,6 +27,7 @@ pub use self::fs::in_incr_comp_dir;
 pub use self::fs::in_incr_comp_dir_sess;
 pub use self::fs::prepare_session_directory;
 pub use self::load::dep_graph_tcx_init;
+pub use self::load::dump_session_directory;
 pub use self::load::load_dep_graph;
 pub use self::load::load_query_result_cache;
 pub use self::load::LoadResult;
@@ -0,0 +1,285 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A content-addressed store of finalized session directories, shared between
+//! incremental directories and selected with `-Z incremental-shared-cache`.
+//!
+//! The session directories managed in `fs.rs` belong to one incremental
+//! directory, which usually means one checkout, so every new worktree or CI
+//! agent starts from scratch. With a shared store, a session directory that
+//! has nothing to copy from locally starts out as a copy of the session last
+//! published to the store for the same crate instead.
+//!
+//! The store looks like this:
+//!
+//! * `blobs/{xx}/{fingerprint}` are the files of the published sessions, i.e.
+//!   the dep-graph, the query result cache, the work product index and the
+//!   object files, named after the fingerprint of their contents. Files that
+//!   did not change between sessions or checkouts are only stored once.
+//! * `sessions/{fingerprint}` is the manifest of the session last published
+//!   for a crate, listing its files and their blobs. It is named after the
+//!   fingerprint of the crate name and disambiguator, the compiler version and
+//!   the dep-tracking hash of the command line, none of which depend on where
+//!   the crate or the incremental directory is.
+//! * `tmp/` holds blobs and manifests while they are written. They are renamed
+//!   into place once complete, so no process ever sees a partial file.
+//! * `store.lock` is locked shared while a session is imported or published,
+//!   and exclusively while garbage collecting.
+//!
+//! Garbage collection keeps the blobs under the size given with
+//! `-Z incremental-shared-cache-size`. It drops the least recently published
+//! manifests first and then deletes the blobs no manifest refers to anymore.
+
+use rustc::session::Session;
+use rustc_data_structures::flock;
+use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::stable_hasher::StableHasher;
+use rustc_fs_util::link_or_copy;
+
+use std::fs as std_fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use rand::{RngCore, thread_rng};
+
+use super::file_format::RUSTC_VERSION;
+
+const MANIFEST_HEADER: &str = "# rustc incremental shared cache manifest, format 1";
+const LOCK_FILE_NAME: &str = "store.lock";
+
+fn store_dir(sess: &Session) -> Option<PathBuf> {
+    sess.opts.debugging_opts.incremental_shared_cache.as_ref().map(PathBuf::from)
+}
+
+/// The manifest of a crate, identified by the name of its crate directory,
+/// which is made up of the crate name and disambiguator.
+fn manifest_path(sess: &Session, store: &Path, crate_dir: &Path) -> PathBuf {
+    let mut hasher = StableHasher::<u128>::new();
+    crate_dir.file_name().unwrap().to_string_lossy().hash(&mut hasher);
+    RUSTC_VERSION.hash(&mut hasher);
+    sess.opts.dep_tracking_hash().hash(&mut hasher);
+    store.join("sessions").join(format!("{:032x}", hasher.finish()))
+}
+
+fn blob_path(store: &Path, fingerprint: &str) -> PathBuf {
+    store.join("blobs").join(&fingerprint[..2]).join(fingerprint)
+}
+
+fn lock_store(store: &Path, exclusive: bool) -> io::Result<flock::Lock> {
+    std_fs::create_dir_all(store)?;
+    flock::Lock::new(&store.join(LOCK_FILE_NAME),
+                     false, // don't wait
+                     true,  // create the lock file
+                     exclusive)
+}
+
+fn temp_path(store: &Path) -> io::Result<PathBuf> {
+    let dir = store.join("tmp");
+    std_fs::create_dir_all(&dir)?;
+    let (a, b) = (thread_rng().next_u64(), thread_rng().next_u64());
+    Ok(dir.join(format!("{:016x}{:016x}", a, b)))
+}
+
+fn fingerprint_file(path: &Path) -> io::Result<String> {
+    let mut contents = Vec::new();
+    std_fs::File::open(path)?.read_to_end(&mut contents)?;
+    let mut hasher = StableHasher::<u128>::new();
+    hasher.write(&contents);
+    Ok(format!("{:032x}", hasher.finish()))
+}
+
+/// Reads a manifest into a list of file names and blob fingerprints.
+fn read_manifest(path: &Path) -> io::Result<Vec<(String, String)>> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData,
+                                    format!("`{}` is not a manifest", path.display()));
+    let mut lines = BufReader::new(std_fs::File::open(path)?).lines();
+    match lines.next() {
+        Some(Ok(ref header)) if header == MANIFEST_HEADER => {}
+        Some(Err(e)) => return Err(e),
+        _ => return Err(invalid()),
+    }
+    let mut files = Vec::new();
+    for line in lines {
+        let line = line?;
+        let mut parts = line.splitn(2, '\t');
+        match (parts.next(), parts.next()) {
+            (Some(fingerprint), Some(file_name)) if fingerprint.len() == 32 => {
+                files.push((file_name.to_owned(), fingerprint.to_owned()));
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(files)
+}
+
+/// Fills the empty `session_dir` with the files of the session last published
+/// for the crate of `crate_dir`. Returns whether there was such a session and
+/// all of its files could be copied.
+pub fn import_session(sess: &Session, crate_dir: &Path, session_dir: &Path) -> bool {
+    let store = match store_dir(sess) {
+        Some(store) => store,
+        None => return false,
+    };
+
+    // Don't import while the garbage collector might be deleting blobs.
+    let _lock = match lock_store(&store, false) {
+        Ok(lock) => lock,
+        Err(_) => return false,
+    };
+
+    let manifest = manifest_path(sess, &store, crate_dir);
+    let files = match read_manifest(&manifest) {
+        Ok(files) => files,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return false,
+        Err(err) => {
+            sess.warn(&format!("could not read incremental shared cache manifest `{}`: {}",
+                               manifest.display(), err));
+            return false
+        }
+    };
+
+    for &(ref file_name, ref fingerprint) in &files {
+        let blob = blob_path(&store, fingerprint);
+        if let Err(err) = link_or_copy(&blob, session_dir.join(file_name)) {
+            debug!("import_session() - could not copy `{}`: {}", blob.display(), err);
+            for &(ref file_name, _) in &files {
+                let _ = std_fs::remove_file(session_dir.join(file_name));
+            }
+            return false
+        }
+    }
+
+    if sess.opts.debugging_opts.incremental_info {
+        println!("[incremental] session directory: {} files imported from the \
+                  shared cache", files.len());
+    }
+    true
+}
+
+/// Publishes the files of the complete `session_dir` to the store, making it
+/// the session new checkouts of the crate start from, and trims the store.
+pub fn export_session(sess: &Session, session_dir: &Path) {
+    let store = match store_dir(sess) {
+        Some(store) => store,
+        None => return,
+    };
+
+    let result = lock_store(&store, false).and_then(|lock| {
+        let result = write_session(sess, &store, session_dir);
+        drop(lock);
+        result
+    });
+    if let Err(err) = result {
+        sess.warn(&format!("could not publish incremental compilation session to the \
+                            shared cache `{}`: {}", store.display(), err));
+        return
+    }
+
+    let max_size = sess.opts.debugging_opts.incremental_shared_cache_size as u64 * 1024 * 1024;
+    if let Err(err) = garbage_collect(&store, max_size) {
+        sess.warn(&format!("could not garbage collect the incremental shared cache \
+                            `{}`: {}", store.display(), err));
+    }
+}
+
+fn write_session(sess: &Session, store: &Path, session_dir: &Path) -> io::Result<()> {
+    let mut manifest = format!("{}\n", MANIFEST_HEADER);
+    for entry in session_dir.read_dir()? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue
+        }
+        let file_name = match entry.file_name().into_string() {
+            Ok(file_name) => file_name,
+            Err(_) => continue,
+        };
+
+        let fingerprint = fingerprint_file(&entry.path())?;
+        let blob = blob_path(store, &fingerprint);
+        if !blob.exists() {
+            let temp = temp_path(store)?;
+            link_or_copy(entry.path(), &temp)?;
+            std_fs::create_dir_all(blob.parent().unwrap())?;
+            std_fs::rename(&temp, &blob)?;
+        }
+        manifest.push_str(&format!("{}\t{}\n", fingerprint, file_name));
+    }
+
+    let manifest_path = manifest_path(sess, store, session_dir.parent().unwrap());
+    let temp = temp_path(store)?;
+    std_fs::File::create(&temp)?.write_all(manifest.as_bytes())?;
+    std_fs::create_dir_all(manifest_path.parent().unwrap())?;
+    std_fs::rename(&temp, &manifest_path)
+}
+
+fn garbage_collect(store: &Path, max_size: u64) -> io::Result<()> {
+    // Someone else is using the store, leave collecting it to a later session.
+    let _lock = match lock_store(store, true) {
+        Ok(lock) => lock,
+        Err(_) => return Ok(()),
+    };
+
+    // With the exclusive lock held, nobody is writing to `tmp/` and anything
+    // in it is left over from a crash.
+    if let Ok(entries) = store.join("tmp").read_dir() {
+        for entry in entries {
+            let _ = std_fs::remove_file(entry?.path());
+        }
+    }
+
+    let mut manifests = Vec::new();
+    if let Ok(entries) = store.join("sessions").read_dir() {
+        for entry in entries {
+            let path = entry?.path();
+            let modified = path.metadata()?.modified()?;
+            let files = read_manifest(&path).unwrap_or_default();
+            manifests.push((modified, path, files));
+        }
+    }
+    manifests.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut kept = FxHashSet::default();
+    let mut kept_size = 0;
+    for (i, (_, path, files)) in manifests.into_iter().enumerate() {
+        let new_blobs = files.into_iter()
+                             .map(|(_, fingerprint)| fingerprint)
+                             .filter(|fingerprint| !kept.contains(fingerprint))
+                             .collect::<FxHashSet<_>>();
+        let mut size = 0;
+        for fingerprint in &new_blobs {
+            size += blob_path(store, fingerprint).metadata().map(|m| m.len()).unwrap_or(0);
+        }
+        // The most recently published session is always kept.
+        if i == 0 || kept_size + size <= max_size {
+            kept.extend(new_blobs);
+            kept_size += size;
+        } else {
+            debug!("garbage_collect() - dropping manifest `{}`", path.display());
+            std_fs::remove_file(&path)?;
+        }
+    }
+
+    if let Ok(dirs) = store.join("blobs").read_dir() {
+        for dir in dirs {
+            for entry in dir?.path().read_dir()? {
+                let entry = entry?;
+                let referenced = entry.file_name()
+                                      .to_str()
+                                      .map_or(false, |fingerprint| kept.contains(fingerprint));
+                if !referenced {
+                    std_fs::remove_file(entry.path())?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
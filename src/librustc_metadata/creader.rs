@@ -12,21 +12,18 @@
 
 use cstore::{self, CStore, CrateSource, MetadataBlob};
 use locator::{self, CratePaths};
-use decoder::proc_macro_def_path_table;
 use schema::CrateRoot;
-use rustc_data_structures::sync::{Lrc, RwLock, Lock};
+use rustc_data_structures::sync::Lrc;
 
 use rustc::hir::def_id::CrateNum;
 use rustc_data_structures::svh::Svh;
 use rustc::middle::allocator::AllocatorKind;
 use rustc::middle::cstore::DepKind;
-use rustc::mir::interpret::AllocDecodingState;
 use rustc::session::{Session, CrateDisambiguator};
 use rustc::session::config::{Sanitizer, self};
 use rustc_target::spec::{PanicStrategy, TargetTriple};
 use rustc::session::search_paths::PathKind;
 use rustc::middle::cstore::{ExternCrate, ExternCrateSource};
-use rustc::util::common::record_time;
 use rustc::util::nodemap::FxHashSet;
 use rustc::hir::map::Definitions;
 
@@ -229,50 +226,19 @@ impl<'a> CrateLoader<'a> {
         let Library { dylib, rlib, rmeta, metadata } = lib;
         let cnum_map = self.resolve_crate_deps(root, &crate_root, &metadata, cnum, span, dep_kind);
 
-        let dependencies: Vec<CrateNum> = cnum_map.iter().cloned().collect();
-
         let proc_macros = crate_root.macro_derive_registrar.map(|_| {
             self.load_derive_macros(&crate_root, dylib.clone().map(|p| p.0), span)
         });
 
-        let def_path_table = record_time(&self.sess.perf_stats.decode_def_path_tables_time, || {
-            if let Some(proc_macros) = &proc_macros {
-                proc_macro_def_path_table(&crate_root, proc_macros)
-            } else {
-                crate_root.def_path_table.decode((&metadata, self.sess))
-            }
-        });
-
-        let interpret_alloc_index: Vec<u32> = crate_root.interpret_alloc_index
-                                                        .decode(&metadata)
-                                                        .collect();
-        let trait_impls = crate_root
-            .impls
-            .decode((&metadata, self.sess))
-            .map(|trait_impls| (trait_impls.trait_id, trait_impls.impls))
-            .collect();
-
-        let cmeta = cstore::CrateMetadata {
-            name: crate_root.name,
-            imported_name: ident,
-            extern_crate: Lock::new(None),
-            def_path_table: Lrc::new(def_path_table),
-            trait_impls,
-            proc_macros,
-            root: crate_root,
-            blob: metadata,
-            cnum_map,
-            cnum,
-            dependencies: Lock::new(dependencies),
-            source_map_import_info: RwLock::new(vec![]),
-            alloc_decoding_state: AllocDecodingState::new(interpret_alloc_index),
-            dep_kind: Lock::new(dep_kind),
-            source: cstore::CrateSource {
-                dylib,
-                rlib,
-                rmeta,
-            }
-        };
+        let cmeta = cstore::CrateMetadata::new(self.sess,
+                                               ident,
+                                               crate_root,
+                                               metadata,
+                                               cnum,
+                                               cnum_map,
+                                               dep_kind,
+                                               CrateSource { dylib, rlib, rmeta },
+                                               proc_macros);
 
         let cmeta = Lrc::new(cmeta);
         self.cstore.set_crate_data(cnum, cmeta.clone());
@@ -292,6 +258,9 @@ impl<'a> CrateLoader<'a> {
     ) -> Result<(CrateNum, Lrc<cstore::CrateMetadata>), LoadError<'b>> {
         info!("resolving crate `extern crate {} as {}`", name, ident);
         let result = if let Some(cnum) = self.existing_match(name, hash, path_kind) {
+            locator::explain_crate_search(self.sess, name, || {
+                format!("not searching, the crate was already loaded as crate #{}", cnum)
+            });
             LoadResult::Previous(cnum)
         } else {
             info!("falling back to a load");
@@ -371,6 +340,9 @@ impl<'a> CrateLoader<'a> {
                 if data.root.name == root.name && root.hash == data.root.hash {
                     assert!(locate_ctxt.hash.is_none());
                     info!("load success, going to previous cnum: {}", cnum);
+                    locator::explain_crate_search(self.sess, locate_ctxt.crate_name, || {
+                        format!("the chosen crate was already loaded as crate #{}", cnum)
+                    });
                     result = LoadResult::Previous(cnum);
                 }
             });
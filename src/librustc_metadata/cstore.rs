@@ -12,10 +12,13 @@
 // crates and libraries
 
 use schema;
+use decoder::proc_macro_def_path_table;
 use rustc::hir::def_id::{CrateNum, DefIndex};
 use rustc::hir::map::definitions::DefPathTable;
 use rustc::middle::cstore::{DepKind, ExternCrate, MetadataLoader};
 use rustc::mir::interpret::AllocDecodingState;
+use rustc::session::Session;
+use rustc::util::common::record_time;
 use rustc_data_structures::indexed_vec::IndexVec;
 use rustc::util::nodemap::{FxHashMap, NodeMap};
 
@@ -91,6 +94,60 @@ pub struct CrateMetadata {
     pub proc_macros: Option<Vec<(ast::Name, Lrc<SyntaxExtension>)>>,
 }
 
+impl CrateMetadata {
+    /// Decodes the tables of `blob` that are kept in memory for as long as
+    /// the crate is loaded. `root` is the root of `blob` and `cnum_map` maps
+    /// the crate numbers used in `blob` to `cnum` and the crate numbers of
+    /// its dependencies.
+    crate fn new(sess: &Session,
+                 imported_name: Symbol,
+                 root: schema::CrateRoot,
+                 blob: MetadataBlob,
+                 cnum: CrateNum,
+                 cnum_map: CrateNumMap,
+                 dep_kind: DepKind,
+                 source: CrateSource,
+                 proc_macros: Option<Vec<(ast::Name, Lrc<SyntaxExtension>)>>)
+                 -> CrateMetadata {
+        let dependencies: Vec<CrateNum> = cnum_map.iter().cloned().collect();
+
+        let def_path_table = record_time(&sess.perf_stats.decode_def_path_tables_time, || {
+            if let Some(proc_macros) = &proc_macros {
+                proc_macro_def_path_table(&root, proc_macros)
+            } else {
+                root.def_path_table.decode((&blob, sess))
+            }
+        });
+
+        let interpret_alloc_index: Vec<u32> = root.interpret_alloc_index
+                                                  .decode(&blob)
+                                                  .collect();
+        let trait_impls = root
+            .impls
+            .decode((&blob, sess))
+            .map(|trait_impls| (trait_impls.trait_id, trait_impls.impls))
+            .collect();
+
+        CrateMetadata {
+            name: root.name,
+            imported_name,
+            extern_crate: Lock::new(None),
+            def_path_table: Lrc::new(def_path_table),
+            trait_impls,
+            proc_macros,
+            root,
+            blob,
+            cnum_map,
+            cnum,
+            dependencies: Lock::new(dependencies),
+            source_map_import_info: RwLock::new(vec![]),
+            alloc_decoding_state: AllocDecodingState::new(interpret_alloc_index),
+            dep_kind: Lock::new(dep_kind),
+            source,
+        }
+    }
+}
+
 pub struct CStore {
     metas: RwLock<IndexVec<CrateNum, Option<Lrc<CrateMetadata>>>>,
     /// Map from NodeId's of local extern crate statements to crate numbers
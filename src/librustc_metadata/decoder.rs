@@ -32,7 +32,6 @@ use rustc::ty::codec::TyDecoder;
 use rustc::mir::Mir;
 use rustc::util::captures::Captures;
 
-use std::io;
 use std::mem;
 use std::u32;
 
@@ -384,26 +383,16 @@ impl<'a, 'tcx> MetadataBlob {
         Lazy::with_position(METADATA_HEADER.len() + 4).decode(self)
     }
 
-    pub fn get_root(&self) -> CrateRoot {
+    pub fn get_root_position(&self) -> usize {
         let slice = self.raw_bytes();
         let offset = METADATA_HEADER.len();
-        let pos = (((slice[offset + 0] as u32) << 24) | ((slice[offset + 1] as u32) << 16) |
-                   ((slice[offset + 2] as u32) << 8) |
-                   ((slice[offset + 3] as u32) << 0)) as usize;
-        Lazy::with_position(pos).decode(self)
-    }
-
-    pub fn list_crate_metadata(&self,
-                               out: &mut dyn io::Write) -> io::Result<()> {
-        write!(out, "=External Dependencies=\n")?;
-        let root = self.get_root();
-        for (i, dep) in root.crate_deps
-                            .decode(self)
-                            .enumerate() {
-            write!(out, "{} {}{}\n", i + 1, dep.name, dep.extra_filename)?;
-        }
-        write!(out, "\n")?;
-        Ok(())
+        (((slice[offset + 0] as u32) << 24) | ((slice[offset + 1] as u32) << 16) |
+         ((slice[offset + 2] as u32) << 8) |
+         ((slice[offset + 3] as u32) << 0)) as usize
+    }
+
+    pub fn get_root(&self) -> CrateRoot {
+        Lazy::with_position(self.get_root_position()).decode(self)
     }
 }
 
@@ -0,0 +1,366 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The crate metadata dump printed by `-Z ls`, meant for debugging the
+//! "found possibly newer version of crate" and SVH mismatch errors of
+//! `locator.rs`.
+//!
+//! The dump shows the crate root (name, hash, disambiguator, edition, panic
+//! strategy, ...), the dependencies the crate was compiled against along with
+//! their hashes, the public items reachable from the crate root, the native
+//! libraries, the proc-macro and plugin registrars and how many bytes each
+//! table of the metadata takes up.
+//!
+//! With `-Z ls-json`, the same information is printed as a JSON object. Its
+//! `format_version` field is bumped whenever a field is removed or changes
+//! its meaning, so tools can reject dumps they don't understand; adding new
+//! fields does not change the version. Hashes are printed the way they appear
+//! in error messages and symbol names.
+//!
+//! Metadata written by a different compiler version can only be decoded up to
+//! its version string, so for such crates the dump stops there.
+
+use cstore::{CrateMetadata, CrateSource, MetadataBlob};
+use schema::{CrateDep, CrateRoot, METADATA_VERSION, rustc_version};
+
+use rustc::hir::def::Def;
+use rustc::hir::def_id::{CrateNum, DefId, DefIndex, CRATE_DEF_INDEX};
+use rustc::middle::cstore::{DepKind, NativeLibraryKind};
+use rustc::session::Session;
+use rustc::ty;
+use rustc_data_structures::fx::FxHashSet;
+use rustc_serialize::json::{Json, ToJson};
+use syntax::print::pprust;
+
+use std::collections::BTreeMap;
+use std::io;
+
+/// Bumped whenever a field of the JSON output is removed or changes meaning.
+const FORMAT_VERSION: u32 = 1;
+
+/// A public item reachable from the crate root.
+struct ExportedItem {
+    path: String,
+    kind: &'static str,
+    /// The crate defining the item, if it is re-exported from a dependency.
+    reexported_from: Option<String>,
+}
+
+pub fn dump_crate_metadata(sess: &Session,
+                           blob: MetadataBlob,
+                           json: bool,
+                           out: &mut dyn io::Write)
+                           -> io::Result<()> {
+    let found_version = blob.get_rustc_version();
+    if found_version != rustc_version() {
+        if json {
+            let mut obj = BTreeMap::new();
+            obj.insert("format_version".to_string(), FORMAT_VERSION.to_json());
+            obj.insert("metadata_version".to_string(), (METADATA_VERSION as u32).to_json());
+            obj.insert("rustc_version".to_string(), found_version.to_json());
+            return write!(out, "{}\n", Json::Object(obj).pretty());
+        }
+        write!(out, "=Crate Root=\n")?;
+        write!(out, "rustc version: {}\n", found_version)?;
+        write!(out, "(written by a different compiler version, cannot decode further)\n")?;
+        return Ok(());
+    }
+
+    let table_sizes = table_sizes(&blob);
+    let cdata = crate_metadata(sess, blob);
+    let root = &cdata.root;
+    let deps: Vec<CrateDep> = root.crate_deps.decode(&cdata).collect();
+    let items = exported_items(sess, &cdata, &deps);
+    let native_libraries = cdata.get_native_libraries(sess);
+    let registrar = |index: Option<DefIndex>| {
+        index.map(|index| cdata.def_path(index).to_string_no_crate())
+    };
+    let plugin_registrar = registrar(root.plugin_registrar_fn);
+    let derive_registrar = registrar(root.macro_derive_registrar);
+
+    if json {
+        let mut obj = BTreeMap::new();
+        obj.insert("format_version".to_string(), FORMAT_VERSION.to_json());
+        obj.insert("metadata_version".to_string(), (METADATA_VERSION as u32).to_json());
+        obj.insert("rustc_version".to_string(), found_version.to_json());
+        obj.insert("crate_root".to_string(), crate_root_to_json(root));
+
+        let deps = deps.iter().enumerate().map(|(i, dep)| {
+            let mut obj = BTreeMap::new();
+            obj.insert("crate_num".to_string(), (i as u32 + 1).to_json());
+            obj.insert("name".to_string(), dep.name.to_string().to_json());
+            obj.insert("hash".to_string(), dep.hash.to_string().to_json());
+            obj.insert("kind".to_string(), dep_kind_name(dep.kind).to_json());
+            obj.insert("extra_filename".to_string(), dep.extra_filename.to_json());
+            Json::Object(obj)
+        }).collect();
+        obj.insert("dependencies".to_string(), Json::Array(deps));
+
+        let items = items.iter().map(|item| {
+            let mut obj = BTreeMap::new();
+            obj.insert("path".to_string(), item.path.to_json());
+            obj.insert("kind".to_string(), item.kind.to_json());
+            obj.insert("reexported_from".to_string(), item.reexported_from.to_json());
+            Json::Object(obj)
+        }).collect();
+        obj.insert("exported_items".to_string(), Json::Array(items));
+
+        let libs = native_libraries.iter().map(|lib| {
+            let mut obj = BTreeMap::new();
+            obj.insert("name".to_string(), lib.name.map(|n| n.to_string()).to_json());
+            obj.insert("kind".to_string(), native_library_kind_name(lib.kind).to_json());
+            obj.insert("cfg".to_string(),
+                       lib.cfg.as_ref().map(pprust::meta_item_to_string).to_json());
+            obj.insert("wasm_import_module".to_string(),
+                       lib.wasm_import_module.map(|m| m.to_string()).to_json());
+            Json::Object(obj)
+        }).collect();
+        obj.insert("native_libraries".to_string(), Json::Array(libs));
+
+        obj.insert("plugin_registrar_fn".to_string(), plugin_registrar.to_json());
+        obj.insert("macro_derive_registrar".to_string(), derive_registrar.to_json());
+
+        let sizes = table_sizes.iter().map(|&(name, size)| {
+            let mut obj = BTreeMap::new();
+            obj.insert("table".to_string(), name.to_json());
+            obj.insert("bytes".to_string(), size.to_json());
+            Json::Object(obj)
+        }).collect();
+        obj.insert("table_sizes".to_string(), Json::Array(sizes));
+
+        return write!(out, "{}\n", Json::Object(obj).pretty());
+    }
+
+    write!(out, "=Crate Root=\n")?;
+    write!(out, "name: {}\n", root.name)?;
+    write!(out, "rustc version: {}\n", found_version)?;
+    write!(out, "metadata version: {}\n", METADATA_VERSION)?;
+    write!(out, "hash: {}\n", root.hash)?;
+    write!(out, "disambiguator: {}\n", root.disambiguator)?;
+    write!(out, "extra filename: {}\n", root.extra_filename)?;
+    write!(out, "target: {}\n", root.triple)?;
+    write!(out, "edition: {}\n", root.edition)?;
+    write!(out, "panic strategy: {}\n", root.panic_strategy.desc())?;
+    let flags = crate_flags(root).into_iter()
+                                 .filter(|&(_, set)| set)
+                                 .map(|(name, _)| name)
+                                 .collect::<Vec<_>>();
+    write!(out, "flags: {}\n", flags.join(", "))?;
+    write!(out, "\n")?;
+
+    write!(out, "=External Dependencies=\n")?;
+    for (i, dep) in deps.iter().enumerate() {
+        write!(out, "{} {}{} {} {}\n",
+               i + 1, dep.name, dep.extra_filename, dep.hash, dep_kind_name(dep.kind))?;
+    }
+    write!(out, "\n")?;
+
+    write!(out, "=Exported Items=\n")?;
+    for item in &items {
+        match item.reexported_from {
+            Some(ref krate) => write!(out, "{} {} (from {})\n", item.kind, item.path, krate)?,
+            None => write!(out, "{} {}\n", item.kind, item.path)?,
+        }
+    }
+    write!(out, "\n")?;
+
+    write!(out, "=Native Libraries=\n")?;
+    for lib in &native_libraries {
+        let name = lib.name.map_or(String::new(), |n| n.to_string());
+        write!(out, "{} {}", native_library_kind_name(lib.kind), name)?;
+        if let Some(ref cfg) = lib.cfg {
+            write!(out, " if {}", pprust::meta_item_to_string(cfg))?;
+        }
+        if let Some(module) = lib.wasm_import_module {
+            write!(out, " (wasm import module {})", module)?;
+        }
+        write!(out, "\n")?;
+    }
+    write!(out, "\n")?;
+
+    write!(out, "=Registrars=\n")?;
+    if let Some(path) = plugin_registrar {
+        write!(out, "plugin registrar: {}\n", path)?;
+    }
+    if let Some(path) = derive_registrar {
+        write!(out, "proc-macro registrar: {}\n", path)?;
+    }
+    write!(out, "\n")?;
+
+    write!(out, "=Table Sizes=\n")?;
+    for &(name, size) in &table_sizes {
+        write!(out, "{:<24} {:>10}\n", name, size)?;
+    }
+    write!(out, "{:<24} {:>10}\n", "total", cdata.blob.raw_bytes().len())?;
+    write!(out, "\n")?;
+    Ok(())
+}
+
+/// Builds a `CrateMetadata` for a crate that is looked at on its own, the way
+/// the crate loader does. Its dependencies are not loaded, they just get crate
+/// numbers of their own so `DefId`s pointing into them can be told apart from
+/// the crate's own.
+fn crate_metadata(sess: &Session, blob: MetadataBlob) -> CrateMetadata {
+    let root = blob.get_root();
+    let cnum = CrateNum::new(1);
+    let cnum_map = (0..root.crate_deps.len + 1).map(|i| CrateNum::new(i + 1)).collect();
+    let source = CrateSource {
+        dylib: None,
+        rlib: None,
+        rmeta: None,
+    };
+    CrateMetadata::new(sess, root.name, root, blob, cnum, cnum_map,
+                       DepKind::Explicit, source, None)
+}
+
+/// Walks the public modules from the crate root and lists the public items in
+/// them, in the order they are encoded.
+fn exported_items(sess: &Session, cdata: &CrateMetadata, deps: &[CrateDep]) -> Vec<ExportedItem> {
+    let crate_name = |krate: CrateNum| {
+        if krate == cdata.cnum {
+            cdata.root.name.to_string()
+        } else {
+            deps[krate.as_usize() - cdata.cnum.as_usize() - 1].name.to_string()
+        }
+    };
+
+    let mut items = Vec::new();
+    let mut visited = FxHashSet::default();
+    let mut stack = vec![(CRATE_DEF_INDEX, cdata.root.name.to_string())];
+    visited.insert(CRATE_DEF_INDEX);
+    while let Some((index, module_path)) = stack.pop() {
+        let mut children = Vec::new();
+        cdata.each_child_of_item(index, |child| children.push(child), sess);
+        for child in children {
+            if child.vis != ty::Visibility::Public {
+                continue
+            }
+            let path = format!("{}::{}", module_path, child.ident);
+            let reexported_from = match exported_def_id(child.def) {
+                Some(def_id) if def_id.krate != cdata.cnum => Some(crate_name(def_id.krate)),
+                _ => None,
+            };
+            if let Def::Mod(DefId { krate, index }) = child.def {
+                if krate == cdata.cnum && visited.insert(index) {
+                    stack.push((index, path.clone()));
+                }
+            }
+            items.push(ExportedItem {
+                path,
+                kind: child.def.kind_name(),
+                reexported_from,
+            });
+        }
+    }
+    items
+}
+
+/// The `DefId` of an exported def. Some things that can be re-exported have
+/// none, such as primitive types and builtin attributes, and a re-export that
+/// failed to resolve is exported as `Def::Err`.
+fn exported_def_id(def: Def) -> Option<DefId> {
+    match def {
+        Def::Local(..) |
+        Def::Upvar(..) |
+        Def::Label(..) |
+        Def::PrimTy(..) |
+        Def::SelfTy(..) |
+        Def::ToolMod |
+        Def::NonMacroAttr(..) |
+        Def::Err => None,
+        _ => Some(def.def_id()),
+    }
+}
+
+/// Approximates the size of each table by the distance from its start to the
+/// start of the next one. The data a table refers to is encoded in front of
+/// it, so, for example, the signatures and MIR of the items are counted
+/// towards the item entries, which are the last thing before the index.
+fn table_sizes(blob: &MetadataBlob) -> Vec<(&'static str, usize)> {
+    let root = blob.get_root();
+    let mut starts = vec![
+        ("header", 0),
+        ("dependencies", root.crate_deps.position),
+        ("dylib dependency formats", root.dylib_dependency_formats.position),
+        ("lib features", root.lib_features.position),
+        ("lang items", root.lang_items.position),
+        ("missing lang items", root.lang_items_missing.position),
+        ("native libraries", root.native_libraries.position),
+        ("foreign modules", root.foreign_modules.position),
+        ("source map", root.source_map.position),
+        ("def path table", root.def_path_table.position),
+        ("trait impls", root.impls.position),
+        ("exported symbols", root.exported_symbols.position),
+        ("interpret alloc index", root.interpret_alloc_index.position),
+        ("index", root.index.position),
+        ("crate root", blob.get_root_position()),
+    ];
+    if let Some(&first_entry) = root.index.entry_positions(blob.raw_bytes()).iter().min() {
+        starts.push(("items", first_entry));
+    }
+    // Empty sequences are not encoded and have no position.
+    starts.retain(|&(name, position)| name == "header" || position != 0);
+    starts.sort_by_key(|&(_, position)| position);
+
+    let len = blob.raw_bytes().len();
+    starts.iter().enumerate().map(|(i, &(name, start))| {
+        let end = starts.get(i + 1).map_or(len, |&(_, next)| next);
+        (name, end - start)
+    }).collect()
+}
+
+fn crate_flags(root: &CrateRoot) -> Vec<(&'static str, bool)> {
+    vec![
+        ("has_global_allocator", root.has_global_allocator),
+        ("has_panic_handler", root.has_panic_handler),
+        ("has_default_lib_allocator", root.has_default_lib_allocator),
+        ("compiler_builtins", root.compiler_builtins),
+        ("needs_allocator", root.needs_allocator),
+        ("needs_panic_runtime", root.needs_panic_runtime),
+        ("no_builtins", root.no_builtins),
+        ("panic_runtime", root.panic_runtime),
+        ("profiler_runtime", root.profiler_runtime),
+        ("sanitizer_runtime", root.sanitizer_runtime),
+    ]
+}
+
+fn crate_root_to_json(root: &CrateRoot) -> Json {
+    let mut obj = BTreeMap::new();
+    obj.insert("name".to_string(), root.name.to_string().to_json());
+    obj.insert("hash".to_string(), root.hash.to_string().to_json());
+    obj.insert("disambiguator".to_string(), root.disambiguator.to_string().to_json());
+    obj.insert("extra_filename".to_string(), root.extra_filename.to_json());
+    obj.insert("target".to_string(), root.triple.to_string().to_json());
+    obj.insert("edition".to_string(), root.edition.to_string().to_json());
+    obj.insert("panic_strategy".to_string(), root.panic_strategy.desc().to_json());
+    for (name, set) in crate_flags(root) {
+        obj.insert(name.to_string(), set.to_json());
+    }
+    Json::Object(obj)
+}
+
+fn dep_kind_name(kind: DepKind) -> &'static str {
+    match kind {
+        DepKind::UnexportedMacrosOnly => "unexported-macros-only",
+        DepKind::MacrosOnly => "macros-only",
+        DepKind::Implicit => "implicit",
+        DepKind::Explicit => "explicit",
+    }
+}
+
+fn native_library_kind_name(kind: NativeLibraryKind) -> &'static str {
+    // The names of the kinds as they are given to `-l`.
+    match kind {
+        NativeLibraryKind::NativeStatic => "static",
+        NativeLibraryKind::NativeStaticNobundle => "static-nobundle",
+        NativeLibraryKind::NativeFramework => "framework",
+        NativeLibraryKind::NativeUnknown => "dylib",
+    }
+}
@@ -99,6 +99,16 @@ impl<'tcx> LazySeq<Index> {
             Some(Lazy::with_position(position as usize))
         }
     }
+
+    /// The positions of all the entries in the index, in no particular order.
+    pub fn entry_positions(&self, bytes: &[u8]) -> Vec<usize> {
+        let words = &bytes_to_words(&bytes[self.position..])[..self.len];
+        words[1..].iter()
+                  .map(|word| u32::from_le(word.get()))
+                  .filter(|&position| position != u32::MAX)
+                  .map(|position| position as usize)
+                  .collect()
+    }
 }
 
 #[repr(packed)]
@@ -57,6 +57,7 @@ mod schema;
 mod native_libs;
 mod link_args;
 mod foreign_modules;
+mod dump;
 
 pub mod creader;
 pub mod cstore;
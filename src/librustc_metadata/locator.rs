@@ -224,6 +224,7 @@
 
 use cstore::{MetadataRef, MetadataBlob};
 use creader::Library;
+use dump;
 use schema::{METADATA_HEADER, rustc_version};
 
 use rustc_data_structures::fx::FxHashSet;
@@ -306,8 +307,42 @@ impl CratePaths {
     }
 }
 
+/// Prints a line of the `-Z explain-crate-search` trace, if the search for
+/// `crate_name` is being explained.
+pub fn explain_crate_search<F: FnOnce() -> String>(sess: &Session, crate_name: Symbol, f: F) {
+    let explain = sess.opts.debugging_opts.explain_crate_search.as_ref()
+                      .map_or(false, |name| *name == *crate_name.as_str());
+    if explain {
+        println!("[crate-search] `{}`: {}", crate_name, f());
+    }
+}
+
+fn describe_library(rlib: &Option<(PathBuf, PathKind)>,
+                    rmeta: &Option<(PathBuf, PathKind)>,
+                    dylib: &Option<(PathBuf, PathKind)>)
+                    -> String {
+    let flavors = [(CrateFlavor::Rlib, rlib), (CrateFlavor::Rmeta, rmeta),
+                   (CrateFlavor::Dylib, dylib)];
+    flavors.iter().filter_map(|&(flavor, path)| {
+        path.as_ref().map(|&(ref path, _)| format!("{} {}", flavor, path.display()))
+    }).collect::<Vec<_>>().join(", ")
+}
+
 impl<'a> Context<'a> {
+    fn explain<F: FnOnce() -> String>(&self, f: F) {
+        explain_crate_search(self.sess, self.crate_name, f)
+    }
+
     pub fn maybe_load_library_crate(&mut self) -> Option<Library> {
+        self.explain(|| {
+            let hash = self.hash.map_or(String::new(), |hash| format!(" with hash `{}`", hash));
+            let proc_macro = match self.is_proc_macro {
+                Some(true) => " that is a proc-macro crate",
+                Some(false) => " that is not a proc-macro crate",
+                None => "",
+            };
+            format!("looking for a crate for target `{}`{}{}", self.triple, hash, proc_macro)
+        });
         let mut seen_paths = FxHashSet::default();
         match self.extra_filename {
             Some(s) => self.find_library_crate(s, &mut seen_paths)
@@ -440,6 +475,7 @@ impl<'a> Context<'a> {
             if let Some(s) = self.sess.opts.externs.get(&self.crate_name.as_str()) {
                 // Only use `--extern crate_name=path` here, not `--extern crate_name`.
                 if s.iter().any(|l| l.is_some()) {
+                    self.explain(|| "using the locations given with `--extern`".to_string());
                     return self.find_commandline_library(
                         s.iter().filter_map(|l| l.as_ref()),
                     );
@@ -456,6 +492,15 @@ impl<'a> Context<'a> {
         let rlib_prefix = format!("lib{}{}", self.crate_name, extra_prefix);
         let staticlib_prefix = format!("{}{}{}", staticpair.0, self.crate_name, extra_prefix);
 
+        self.explain(|| format!("looking for files named {}*.rlib, {}*.rmeta or {}*{}",
+                                rlib_prefix, rlib_prefix, dylib_prefix, dypair.1));
+        self.filesearch.for_each_lib_search_path(|path, kind| {
+            self.explain(|| {
+                let missing = if path.is_dir() { "" } else { ", which does not exist" };
+                format!("search path {} ({:?}){}", path.display(), kind, missing)
+            });
+        });
+
         let mut candidates: FxHashMap<
             _,
             (FxHashMap<_, _>, FxHashMap<_, _>, FxHashMap<_, _>),
@@ -490,6 +535,8 @@ impl<'a> Context<'a> {
                     (&file[(dylib_prefix.len())..(file.len() - dypair.1.len())], CrateFlavor::Dylib)
                 } else {
                     if file.starts_with(&staticlib_prefix) && file.ends_with(&staticpair.1) {
+                        self.explain(|| format!("rejected {}: it is a static library, not a \
+                                                 Rust library", path.display()));
                         staticlibs.push(CrateMismatch {
                             path: path.to_path_buf(),
                             got: "static".to_string(),
@@ -499,6 +546,7 @@ impl<'a> Context<'a> {
                 };
 
             info!("lib candidate: {}", path.display());
+            self.explain(|| format!("candidate {} ({})", path.display(), found_kind));
 
             let hash_str = hash.to_string();
             let slot = candidates.entry(hash_str).or_default();
@@ -506,6 +554,8 @@ impl<'a> Context<'a> {
             fs::canonicalize(path)
                 .map(|p| {
                     if seen_paths.contains(&p) {
+                        self.explain(|| format!("skipped {}: {} was already considered",
+                                                path.display(), p.display()));
                         return FileDoesntMatch
                     };
                     seen_paths.insert(p.clone());
@@ -535,6 +585,8 @@ impl<'a> Context<'a> {
             let rmeta = self.extract_one(rmetas, CrateFlavor::Rmeta, &mut slot);
             let dylib = self.extract_one(dylibs, CrateFlavor::Dylib, &mut slot);
             if let Some((h, m)) = slot {
+                self.explain(|| format!("found crate with hash `{}`: {}",
+                                        h, describe_library(&rlib, &rmeta, &dylib)));
                 libraries.insert(h,
                                  Library {
                                      dylib,
@@ -549,9 +601,20 @@ impl<'a> Context<'a> {
         // what we've got and figure out if we found multiple candidates for
         // libraries or not.
         match libraries.len() {
-            0 => None,
-            1 => Some(libraries.into_iter().next().unwrap().1),
+            0 => {
+                self.explain(|| "no matching crate found".to_string());
+                None
+            }
+            1 => {
+                let library = libraries.into_iter().next().unwrap().1;
+                self.explain(|| format!("chose {}", describe_library(&library.rlib,
+                                                                     &library.rmeta,
+                                                                     &library.dylib)));
+                Some(library)
+            }
             _ => {
+                self.explain(|| format!("found {} matching crates with different hashes, \
+                                         rejecting all of them", libraries.len()));
                 let mut err = struct_span_err!(self.sess,
                                                self.span,
                                                E0464,
@@ -604,7 +667,11 @@ impl<'a> Context<'a> {
             if m.is_empty() {
                 return None;
             } else if m.len() == 1 {
-                return Some(m.into_iter().next().unwrap());
+                let (lib, kind) = m.into_iter().next().unwrap();
+                self.explain(|| format!("accepted {} as the {} without reading its metadata, \
+                                         the crate was already identified by another file",
+                                        lib.display(), flavor));
+                return Some((lib, kind));
             }
         }
 
@@ -623,11 +690,17 @@ impl<'a> Context<'a> {
                     }
                     Err(err) => {
                         warn!("no metadata found: {}", err);
+                        self.explain(|| format!("rejected {}: no metadata found: {}",
+                                                lib.display(), err));
                         continue;
                     }
                 };
             // If we see multiple hashes, emit an error about duplicate candidates.
             if slot.as_ref().map_or(false, |s| s.0 != hash) {
+                self.explain(|| format!("rejected {} and {}: there are several {} candidates \
+                                         with different hashes",
+                                        ret.as_ref().unwrap().0.display(),
+                                        lib.display(), flavor));
                 let mut e = struct_span_err!(self.sess,
                                              self.span,
                                              E0465,
@@ -648,6 +721,10 @@ impl<'a> Context<'a> {
                 *slot = None;
             }
             if error > 0 {
+                if error > 1 {
+                    self.explain(|| format!("rejected {}: there are several {} candidates with \
+                                             different hashes", lib.display(), flavor));
+                }
                 error += 1;
                 err.as_mut().unwrap().span_note(self.span,
                                                 &format!(r"candidate #{}: {}",
@@ -680,9 +757,14 @@ impl<'a> Context<'a> {
                 let sysroot = sysroot.canonicalize()
                                      .unwrap_or_else(|_| sysroot.to_path_buf());
                 if prev.starts_with(&sysroot) {
+                    self.explain(|| format!("skipped {}: it is the same crate as {}, which is \
+                                             preferred because it is in the sysroot",
+                                            lib.display(), prev.display()));
                     continue
                 }
             }
+            self.explain(|| format!("accepted {} as the {}, crate hash `{}`",
+                                    lib.display(), flavor, hash));
             *slot = Some((hash, metadata));
             ret = Some((lib, kind));
         }
@@ -702,6 +784,8 @@ impl<'a> Context<'a> {
             info!("Rejecting via version: expected {} got {}",
                   rustc_version,
                   found_version);
+            self.explain(|| format!("rejected {}: compiled by `{}` instead of `{}`",
+                                    libpath.display(), found_version, rustc_version));
             self.rejected_via_version.push(CrateMismatch {
                 path: libpath.to_path_buf(),
                 got: found_version,
@@ -712,6 +796,9 @@ impl<'a> Context<'a> {
         let root = metadata.get_root();
         if let Some(is_proc_macro) = self.is_proc_macro {
             if root.macro_derive_registrar.is_some() != is_proc_macro {
+                self.explain(|| format!("rejected {}: it is {}a proc-macro crate",
+                                        libpath.display(),
+                                        if is_proc_macro { "not " } else { "" }));
                 return None;
             }
         }
@@ -719,6 +806,8 @@ impl<'a> Context<'a> {
         if self.should_match_name {
             if self.crate_name != root.name {
                 info!("Rejecting via crate name");
+                self.explain(|| format!("rejected {}: it is crate `{}`",
+                                        libpath.display(), root.name));
                 return None;
             }
         }
@@ -727,6 +816,8 @@ impl<'a> Context<'a> {
             info!("Rejecting via crate triple: expected {} got {}",
                   self.triple,
                   root.triple);
+            self.explain(|| format!("rejected {}: compiled for target `{}` instead of `{}`",
+                                    libpath.display(), root.triple, self.triple));
             self.rejected_via_triple.push(CrateMismatch {
                 path: libpath.to_path_buf(),
                 got: root.triple.to_string(),
@@ -737,6 +828,9 @@ impl<'a> Context<'a> {
         if let Some(myhash) = self.hash {
             if *myhash != root.hash {
                 info!("Rejecting via hash: expected {} got {}", *myhash, root.hash);
+                self.explain(|| format!("rejected {}: its hash is `{}`, but the crate depending \
+                                         on it was compiled against `{}`",
+                                        libpath.display(), root.hash, myhash));
                 self.rejected_via_hash.push(CrateMismatch {
                     path: libpath.to_path_buf(),
                     got: myhash.to_string(),
@@ -776,6 +870,7 @@ impl<'a> Context<'a> {
         let mut dylibs = FxHashMap::default();
         {
             let locs = locs.map(|l| PathBuf::from(l)).filter(|loc| {
+                self.explain(|| format!("candidate {}", loc.display()));
                 if !loc.exists() {
                     sess.err(&format!("extern location for {} does not exist: {}",
                                       self.crate_name,
@@ -801,6 +896,9 @@ impl<'a> Context<'a> {
                     }
                 }
 
+                self.explain(|| format!("rejected {}: file name should be lib*.rlib, \
+                                         lib*.rmeta or {}*{}",
+                                        loc.display(), dylibname.0, dylibname.1));
                 self.rejected_via_filename.push(CrateMismatch {
                     path: loc.clone(),
                     got: String::new(),
@@ -829,8 +927,10 @@ impl<'a> Context<'a> {
         let dylib = self.extract_one(dylibs, CrateFlavor::Dylib, &mut slot);
 
         if rlib.is_none() && rmeta.is_none() && dylib.is_none() {
+            self.explain(|| "no matching crate found".to_string());
             return None;
         }
+        self.explain(|| format!("chose {}", describe_library(&rlib, &rmeta, &dylib)));
         slot.map(|(_, metadata)|
             Library {
                 dylib,
@@ -906,9 +1006,10 @@ fn get_metadata_section_imp(target: &Target,
 }
 
 // A diagnostic function for dumping crate metadata to an output stream
-pub fn list_file_metadata(target: &Target,
+pub fn list_file_metadata(sess: &Session,
                           path: &Path,
                           loader: &dyn MetadataLoader,
+                          json: bool,
                           out: &mut dyn io::Write)
                           -> io::Result<()> {
     let filename = path.file_name().unwrap().to_str().unwrap();
@@ -919,8 +1020,8 @@ pub fn list_file_metadata(target: &Target,
     } else {
         CrateFlavor::Dylib
     };
-    match get_metadata_section(target, flavor, path, loader) {
-        Ok(metadata) => metadata.list_crate_metadata(out),
+    match get_metadata_section(&sess.target.target, flavor, path, loader) {
+        Ok(metadata) => dump::dump_crate_metadata(sess, metadata, json, out),
         Err(msg) => write!(out, "{}\n", msg),
     }
 }
@@ -8,10 +8,33 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use borrow_check::location::{LocationIndex, LocationTable};
+//! Writing the facts gathered for Polonius to disk, for `-Z nll-facts`.
+//!
+//! The facts of each function go into a directory of their own, under
+//! `nll-facts/`. Each kind of fact is written to `<kind>.facts`, with one
+//! fact per line. The columns of a line are separated by tabs, and every
+//! column is a quoted string:
+//!
+//! - regions are written as `'_#3r`;
+//! - borrows are written as `bw2`;
+//! - points are written as `Start(bb1[4])` or `Mid(bb1[4])`, that is the
+//!   start or the mid-point of statement 4 of basic block 1 (see
+//!   `LocationTable`).
+//!
+//! Two more files tie the facts back to the source:
+//!
+//! - `locations.facts` has one line per point, with the index of the point
+//!   (its `LocationIndex`), the point, the MIR statement or terminator at
+//!   that point and its span;
+//! - `region_names.facts` has one line per region that stands for `'static`
+//!   or a named region of the function, with the region and its name.
+
+use borrow_check::location::{LocationIndex, LocationTable, RichLocation};
+use borrow_check::nll::region_infer::RegionInferenceContext;
 use dataflow::indexes::BorrowIndex;
 use polonius_engine::AllFacts as PoloniusAllFacts;
 use polonius_engine::Atom;
+use rustc::mir::Mir;
 use rustc::ty::{RegionVid, TyCtxt};
 use rustc_data_structures::indexed_vec::Idx;
 use std::error::Error;
@@ -27,10 +50,13 @@ crate trait AllFactsExt {
     /// current `-Z` flags.
     fn enabled(tcx: TyCtxt<'_, '_, '_>) -> bool;
 
-    fn write_to_dir(
+    fn write_to_dir<'tcx>(
         &self,
         dir: impl AsRef<Path>,
+        tcx: TyCtxt<'_, '_, 'tcx>,
+        mir: &Mir<'tcx>,
         location_table: &LocationTable,
+        regioncx: &RegionInferenceContext<'tcx>,
     ) -> Result<(), Box<dyn Error>>;
 }
 
@@ -41,10 +67,13 @@ impl AllFactsExt for AllFacts {
             || tcx.sess.opts.debugging_opts.polonius
     }
 
-    fn write_to_dir(
+    fn write_to_dir<'tcx>(
         &self,
         dir: impl AsRef<Path>,
+        tcx: TyCtxt<'_, '_, 'tcx>,
+        mir: &Mir<'tcx>,
         location_table: &LocationTable,
+        regioncx: &RegionInferenceContext<'tcx>,
     ) -> Result<(), Box<dyn Error>> {
         let dir: &Path = dir.as_ref();
         fs::create_dir_all(dir)?;
@@ -72,6 +101,33 @@ impl AllFactsExt for AllFacts {
                 invalidates,
             ])
         }
+
+        let locations: Vec<_> = location_table.all_points().map(|point| {
+            let location = match location_table.to_location(point) {
+                RichLocation::Start(location) | RichLocation::Mid(location) => location,
+            };
+            let data = &mir[location.block];
+            let mir_text = match data.statements.get(location.statement_index) {
+                Some(statement) => format!("{:?}", statement),
+                None => format!("{:?}", data.terminator().kind),
+            };
+            let span = tcx.sess.source_map().span_to_string(mir.source_info(location).span);
+            (Idx::index(point), point, mir_text, span)
+        }).collect();
+        wr.write_facts_to_path(&locations, "locations.facts")?;
+
+        let region_names: Vec<_> = regioncx.regions().filter_map(|region| {
+            regioncx.external_name(region).map(|name| {
+                // Anonymous regions have no name to display.
+                let mut name_text = format!("{}", name);
+                if name_text.is_empty() {
+                    name_text = format!("{:?}", name);
+                }
+                (region, name_text)
+            })
+        }).collect();
+        wr.write_facts_to_path(&region_names, "region_names.facts")?;
+
         Ok(())
     }
 }
@@ -212,6 +268,12 @@ impl<A: Debug> FactCell for A {
     }
 }
 
+impl FactCell for String {
+    fn to_string(&self, _location_table: &LocationTable) -> String {
+        self.clone()
+    }
+}
+
 impl FactCell for LocationIndex {
     fn to_string(&self, location_table: &LocationTable) -> String {
         format!("{:?}", location_table.to_location(*self))
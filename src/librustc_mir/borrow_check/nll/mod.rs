@@ -179,7 +179,7 @@ pub(in borrow_check) fn compute_regions<'cx, 'gcx, 'tcx>(
             let def_path = infcx.tcx.hir.def_path(def_id);
             let dir_path =
                 PathBuf::from("nll-facts").join(def_path.to_filename_friendly_no_crate());
-            all_facts.write_to_dir(dir_path, location_table).unwrap();
+            all_facts.write_to_dir(dir_path, infcx.tcx, mir, location_table, &regioncx).unwrap();
         }
 
         if infcx.tcx.sess.opts.debugging_opts.polonius {
@@ -368,6 +368,12 @@ impl<'tcx> RegionInferenceContext<'tcx> {
         self.definitions.indices()
     }
 
+    /// Returns the name of `r`, if it is `'static` or one of the named
+    /// regions of the function.
+    crate fn external_name(&self, r: RegionVid) -> Option<ty::Region<'tcx>> {
+        self.definitions[r].external_name
+    }
+
     /// Given a universal region in scope on the MIR, returns the
     /// corresponding index.
     ///
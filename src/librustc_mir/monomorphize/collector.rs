@@ -575,7 +575,7 @@ impl<'a, 'tcx> MirVisitor<'tcx> for MirNeighborCollector<'a, 'tcx> {
                         let instance = monomorphize::resolve_closure(
                             self.tcx, def_id, substs, ty::ClosureKind::FnOnce);
                         if should_monomorphize_locally(self.tcx, &instance) {
-                            self.output.push(create_fn_mono_item(instance));
+                            self.output.push(create_fn_mono_item(self.tcx, instance));
                         }
                     }
                     _ => bug!(),
@@ -589,7 +589,7 @@ impl<'a, 'tcx> MirVisitor<'tcx> for MirNeighborCollector<'a, 'tcx> {
                     .unwrap_or_else(|e| tcx.sess.fatal(&e));
                 let instance = Instance::mono(tcx, exchange_malloc_fn_def_id);
                 if should_monomorphize_locally(tcx, &instance) {
-                    self.output.push(create_fn_mono_item(instance));
+                    self.output.push(create_fn_mono_item(self.tcx, instance));
                 }
             }
             _ => { /* not interesting */ }
@@ -710,17 +710,17 @@ fn visit_instance_use<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
         ty::InstanceDef::DropGlue(_, None) => {
             // don't need to emit shim if we are calling directly.
             if !is_direct_call {
-                output.push(create_fn_mono_item(instance));
+                output.push(create_fn_mono_item(tcx, instance));
             }
         }
         ty::InstanceDef::DropGlue(_, Some(_)) => {
-            output.push(create_fn_mono_item(instance));
+            output.push(create_fn_mono_item(tcx, instance));
         }
         ty::InstanceDef::ClosureOnceShim { .. } |
         ty::InstanceDef::Item(..) |
         ty::InstanceDef::FnPtrShim(..) |
         ty::InstanceDef::CloneShim(..) => {
-            output.push(create_fn_mono_item(instance));
+            output.push(create_fn_mono_item(tcx, instance));
         }
     }
 }
@@ -769,10 +769,10 @@ fn should_monomorphize_locally<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, instance:
                                                -> bool {
         debug_assert!(!def_id.is_local());
 
-        // If we are not in share generics mode, we don't link to upstream
+        // If we don't reuse upstream generics, we don't link to upstream
         // monomorphizations but always instantiate our own internal versions
         // instead.
-        if !tcx.sess.opts.share_generics() {
+        if !tcx.sess.opts.reuse_upstream_generics() {
             return false
         }
 
@@ -894,9 +894,11 @@ fn find_vtable_types_for_unsizing<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     }
 }
 
-fn create_fn_mono_item<'a, 'tcx>(instance: Instance<'tcx>) -> MonoItem<'tcx> {
+fn create_fn_mono_item<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                 instance: Instance<'tcx>)
+                                 -> MonoItem<'tcx> {
     debug!("create_fn_mono_item(instance={})", instance);
-    MonoItem::Fn(instance)
+    MonoItem::Fn(instance.polymorphize(tcx))
 }
 
 /// Creates a `MonoItem` for each method that is referenced by the vtable for
@@ -921,7 +923,7 @@ fn create_mono_items_for_vtable_methods<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                     def_id,
                     substs).unwrap())
             .filter(|&instance| should_monomorphize_locally(tcx, &instance))
-            .map(|instance| create_fn_mono_item(instance));
+            .map(|instance| create_fn_mono_item(tcx, instance));
         output.extend(methods);
         // Also add the destructor
         visit_drop_use(tcx, impl_ty, false, output);
@@ -1050,7 +1052,7 @@ impl<'b, 'a, 'v> RootCollector<'b, 'a, 'v> {
             debug!("RootCollector::push_if_root: found root def_id={:?}", def_id);
 
             let instance = Instance::mono(self.tcx, def_id);
-            self.output.push(create_fn_mono_item(instance));
+            self.output.push(create_fn_mono_item(self.tcx, instance));
         }
     }
 
@@ -1092,7 +1094,7 @@ impl<'b, 'a, 'v> RootCollector<'b, 'a, 'v> {
             self.tcx.intern_substs(&[main_ret_ty.into()])
         ).unwrap();
 
-        self.output.push(create_fn_mono_item(start_instance));
+        self.output.push(create_fn_mono_item(self.tcx, start_instance));
     }
 }
 
@@ -1146,7 +1148,7 @@ fn create_mono_items_for_default_impls<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
                                                          method.def_id,
                                                          substs).unwrap();
 
-                    let mono_item = create_fn_mono_item(instance);
+                    let mono_item = create_fn_mono_item(tcx, instance);
                     if mono_item.is_instantiable(tcx)
                         && should_monomorphize_locally(tcx, &instance) {
                         output.push(mono_item);
@@ -1184,7 +1186,7 @@ fn collect_miri<'a, 'tcx>(
         Some(AllocType::Function(fn_instance)) => {
             if should_monomorphize_locally(tcx, &fn_instance) {
                 trace!("collecting {:?} with {:#?}", alloc_id, fn_instance);
-                output.push(create_fn_mono_item(fn_instance));
+                output.push(create_fn_mono_item(tcx, fn_instance));
             }
         }
         None => bug!("alloc id without corresponding allocation: {}", alloc_id),
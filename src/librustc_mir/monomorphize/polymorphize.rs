@@ -0,0 +1,133 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Finding the type parameters a generic item doesn't depend on.
+//!
+//! Every set of generic arguments normally gets its own instance, even when
+//! the code generated for it is the same for all of them. The typical case is
+//! a closure defined in a generic function: it inherits all the parameters of
+//! its parent, but often captures and uses none of them. If a type parameter
+//! is mentioned nowhere in the MIR of an item, the item's code can't depend
+//! on it, and with `-Z polymorphize` `Instance::polymorphize` uses the result
+//! of `unused_generic_params` to collapse such instances into one.
+
+use rustc::hir::def_id::DefId;
+use rustc::mir::Location;
+use rustc::mir::visit::{TyContext, Visitor};
+use rustc::ty::{self, Ty, TyCtxt, TypeFoldable, TypeVisitor};
+use rustc::ty::query::Providers;
+use rustc::ty::subst::{Substs, UnpackedKind};
+
+use std::cmp;
+
+pub fn provide(providers: &mut Providers) {
+    providers.unused_generic_params = unused_generic_params;
+}
+
+/// Returns a bitmask with bit `i` set if the type parameter with index `i` is
+/// not used by the MIR of `def_id`. Only the first 64 parameters are
+/// considered, the others are always treated as used.
+fn unused_generic_params<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> u64 {
+    let generics = tcx.generics_of(def_id);
+    if generics.count() == 0 || !tcx.is_mir_available(def_id) {
+        return 0;
+    }
+
+    let self_ty = tcx.type_of(def_id);
+    let own_params_start = match self_ty.sty {
+        // The own parameters of a closure carry its kind and signature.
+        ty::Closure(..) => generics.parent_count,
+        // Generators are left alone, their layout depends on the whole body.
+        ty::Generator(..) => return 0,
+        _ => generics.count(),
+    };
+
+    let mut unused = 0;
+    let identity_substs = Substs::identity_for_item(tcx, def_id);
+    for (index, kind) in identity_substs.iter().enumerate().take(cmp::min(own_params_start, 64)) {
+        if let UnpackedKind::Type(_) = kind.unpack() {
+            unused |= 1 << index;
+        }
+    }
+    if unused == 0 {
+        return 0;
+    }
+
+    let mir = tcx.optimized_mir(def_id);
+    let mut visitor = UsedParamsVisitor {
+        params: UsedParams { tcx, def_id, unused },
+    };
+    visitor.visit_mir(mir);
+    for promoted in mir.promoted.iter() {
+        visitor.visit_mir(promoted);
+    }
+    debug!("unused_generic_params({:?}) = {:#b}", def_id, visitor.params.unused);
+    visitor.params.unused
+}
+
+/// Clears the bits of the type parameters found in the visited types.
+struct UsedParams<'a, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    def_id: DefId,
+    unused: u64,
+}
+
+impl<'a, 'tcx> TypeVisitor<'tcx> for UsedParams<'a, 'tcx> {
+    fn visit_ty(&mut self, ty: Ty<'tcx>) -> bool {
+        if !ty.has_param_types() {
+            return false;
+        }
+        match ty.sty {
+            ty::Param(param) => {
+                if param.idx < 64 {
+                    self.unused &= !(1 << param.idx);
+                }
+                false
+            }
+            // The environment of a closure inherits all the parameters of the
+            // enclosing function, but only what it captures and its signature
+            // make it into the generated code.
+            ty::Closure(def_id, substs) if def_id == self.def_id => {
+                for upvar_ty in substs.upvar_tys(def_id, self.tcx) {
+                    upvar_ty.visit_with(self);
+                }
+                substs.closure_sig_ty(def_id, self.tcx).visit_with(self);
+                false
+            }
+            _ => ty.super_visit_with(self),
+        }
+    }
+}
+
+struct UsedParamsVisitor<'a, 'tcx: 'a> {
+    params: UsedParams<'a, 'tcx>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for UsedParamsVisitor<'a, 'tcx> {
+    fn visit_ty(&mut self, ty: &Ty<'tcx>, _: TyContext) {
+        ty.visit_with(&mut self.params);
+    }
+
+    fn visit_const(&mut self, constant: &&'tcx ty::Const<'tcx>, _: Location) {
+        constant.visit_with(&mut self.params);
+    }
+
+    fn visit_substs(&mut self, substs: &&'tcx Substs<'tcx>, _: Location) {
+        substs.visit_with(&mut self.params);
+    }
+
+    fn visit_closure_substs(&mut self, substs: &ty::ClosureSubsts<'tcx>, _: Location) {
+        substs.visit_with(&mut self.params);
+    }
+
+    fn visit_generator_substs(&mut self, substs: &ty::GeneratorSubsts<'tcx>, _: Location) {
+        substs.visit_with(&mut self.params);
+    }
+}
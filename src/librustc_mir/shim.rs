@@ -211,7 +211,7 @@ fn build_drop_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let mut mir = Mir::new(
         blocks,
         IndexVec::from_elem_n(
-            SourceScopeData { span: span, parent_scope: None }, 1
+            SourceScopeData { span: span, parent_scope: None, inlined: None }, 1
         ),
         ClearCrossCrate::Clear,
         IndexVec::new(),
@@ -370,7 +370,7 @@ impl<'a, 'tcx> CloneShimBuilder<'a, 'tcx> {
         Mir::new(
             self.blocks,
             IndexVec::from_elem_n(
-                SourceScopeData { span: self.span, parent_scope: None }, 1
+                SourceScopeData { span: self.span, parent_scope: None, inlined: None }, 1
             ),
             ClearCrossCrate::Clear,
             IndexVec::new(),
@@ -818,7 +818,7 @@ fn build_call_shim<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
     let mut mir = Mir::new(
         blocks,
         IndexVec::from_elem_n(
-            SourceScopeData { span: span, parent_scope: None }, 1
+            SourceScopeData { span: span, parent_scope: None, inlined: None }, 1
         ),
         ClearCrossCrate::Clear,
         IndexVec::new(),
@@ -895,7 +895,7 @@ pub fn build_adt_ctor<'a, 'gcx, 'tcx>(infcx: &infer::InferCtxt<'a, 'gcx, 'tcx>,
     Mir::new(
         IndexVec::from_elem_n(start_block, 1),
         IndexVec::from_elem_n(
-            SourceScopeData { span: span, parent_scope: None }, 1
+            SourceScopeData { span: span, parent_scope: None, inlined: None }, 1
         ),
         ClearCrossCrate::Clear,
         IndexVec::new(),
@@ -440,7 +440,11 @@ impl<'a, 'mir, 'tcx> ConstPropagator<'a, 'mir, 'tcx> {
                         } else {
                             "left"
                         };
-                        let node_id = source_scope_local_data[source_info.scope].lint_root;
+                        // Scopes added by the MIR inliner have no local data.
+                        let node_id = match source_scope_local_data.get(source_info.scope) {
+                            Some(data) => data.lint_root,
+                            None => return None,
+                        };
                         self.tcx.lint_node(
                             ::rustc::lint::builtin::EXCEEDING_BITSHIFTS,
                             node_id,
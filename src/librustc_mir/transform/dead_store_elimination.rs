@@ -0,0 +1,203 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Dead store elimination: removing assignments to locals that are never
+//! read afterwards.
+//!
+//! A store to a local (or to a field of it) is dead if the local isn't live
+//! after the store. Liveness doesn't see reads through references though,
+//! so stores to locals that may have been borrowed at that point are kept.
+//! Whether a local may have been borrowed comes from the
+//! `HaveBeenBorrowedLocals` dataflow analysis.
+//!
+//! Removing a store can make the operands it read dead in turn, so the pass
+//! repeats until there is nothing left to remove.
+//!
+//! `util::liveness` doesn't count `return` as a read of the return place,
+//! which suits its other users, so the return place is made live on the way
+//! to a `return` here.
+
+use dataflow::{do_dataflow, DebugFormatted, FlowAtLocation, FlowsAtLocation};
+use dataflow::HaveBeenBorrowedLocals;
+use rustc::mir::*;
+use rustc::mir::visit::{PlaceContext, Visitor};
+use rustc::session::config::DebugInfo;
+use rustc::ty::TyCtxt;
+use rustc_data_structures::bit_set::BitSet;
+use rustc_data_structures::work_queue::WorkQueue;
+use syntax::ast;
+use transform::{MirPass, MirSource};
+use util::liveness::{self, DefUse, IdentityMap, LivenessResult};
+
+pub struct DeadStoreElimination;
+
+impl MirPass for DeadStoreElimination {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        // This is new and not exercised much yet, so only do it when
+        // optimizing MIR harder than by default.
+        if tcx.sess.opts.debugging_opts.mir_opt_level < 2 {
+            return;
+        }
+
+        // Keep the values of user variables around for the debugger.
+        let keep_named = tcx.sess.opts.debuginfo == DebugInfo::Full;
+
+        let node_id = tcx.hir.as_local_node_id(source.def_id).unwrap();
+        loop {
+            let dead_stores = find_dead_stores(tcx, node_id, mir, keep_named);
+            if dead_stores.is_empty() {
+                break;
+            }
+            for location in dead_stores {
+                debug!("DeadStoreElimination: removing {:?}", mir[location.block]
+                    .statements[location.statement_index]);
+                mir.make_statement_nop(location);
+            }
+        }
+    }
+}
+
+/// Returns the local written by `statement`, if it is a store that can be
+/// removed when the local is dead.
+fn stored_local(statement: &Statement) -> Option<Local> {
+    let mut place = match statement.kind {
+        StatementKind::Assign(ref place, _) |
+        StatementKind::SetDiscriminant { ref place, .. } => place,
+        _ => return None,
+    };
+    loop {
+        match *place {
+            Place::Local(local) => return Some(local),
+            Place::Projection(ref proj) => {
+                // Writing through a pointer isn't a store to the local.
+                if let ProjectionElem::Deref = proj.elem {
+                    return None;
+                }
+                place = &proj.base;
+            }
+            Place::Promoted(_) | Place::Static(_) => return None,
+        }
+    }
+}
+
+fn find_dead_stores<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                              node_id: ast::NodeId,
+                              mir: &Mir<'tcx>,
+                              keep_named: bool)
+                              -> Vec<Location> {
+    let dead_unwinds = BitSet::new_empty(mir.basic_blocks().len());
+    let mut borrowed = FlowAtLocation::new(do_dataflow(
+        tcx, mir, node_id, &[], &dead_unwinds, HaveBeenBorrowedLocals::new(mir),
+        |bd, p| DebugFormatted::new(&bd.mir().local_decls[p])));
+    let map = IdentityMap::new(mir);
+    let mut liveness = liveness::liveness_of_locals(mir, &map);
+    add_return_place_liveness(mir, &mut liveness);
+
+    let mut dead_stores = vec![];
+    for (bb, data) in mir.basic_blocks().iter_enumerated() {
+        // Find the stores to locals that can't have been borrowed.
+        let mut candidates = BitSet::new_empty(data.statements.len());
+        borrowed.reset_to_entry_of(bb);
+        for (statement_index, statement) in data.statements.iter().enumerate() {
+            let location = Location { block: bb, statement_index };
+            borrowed.reconstruct_statement_effect(location);
+            borrowed.apply_local_effect(location);
+            if let Some(local) = stored_local(statement) {
+                if !borrowed.contains(local) &&
+                   !(keep_named && mir.local_decls[local].name.is_some()) {
+                    candidates.insert(statement_index);
+                }
+            }
+        }
+
+        // Go backwards through the block, and drop the candidates whose local
+        // isn't live afterwards. A removed store doesn't make anything live.
+        let mut live = liveness.outs[bb].clone();
+        terminator_liveness(data.terminator(), &mut live);
+        for (statement_index, statement) in data.statements.iter().enumerate().rev() {
+            if candidates.contains(statement_index) {
+                let local = stored_local(statement).unwrap();
+                if !live.contains(local) {
+                    dead_stores.push(Location { block: bb, statement_index });
+                    continue;
+                }
+            }
+            statement_liveness(statement, &mut live);
+        }
+    }
+    dead_stores
+}
+
+/// Makes the return place live on exit from every block that can reach a
+/// `return` without assigning it first.
+fn add_return_place_liveness<'tcx>(mir: &Mir<'tcx>, liveness: &mut LivenessResult<Local>) {
+    let mut live = BitSet::new_empty(mir.local_decls.len());
+    let mut dirty_queue: WorkQueue<BasicBlock> = WorkQueue::with_all(mir.basic_blocks().len());
+    let predecessors = mir.predecessors();
+
+    while let Some(bb) = dirty_queue.pop() {
+        let data = &mir[bb];
+        live.clear();
+        if liveness.outs[bb].contains(RETURN_PLACE) {
+            live.insert(RETURN_PLACE);
+        }
+        terminator_liveness(data.terminator(), &mut live);
+        for statement in data.statements.iter().rev() {
+            statement_liveness(statement, &mut live);
+        }
+
+        if live.contains(RETURN_PLACE) {
+            for &pred_bb in &predecessors[bb] {
+                if liveness.outs[pred_bb].insert(RETURN_PLACE) {
+                    dirty_queue.insert(pred_bb);
+                }
+            }
+        }
+    }
+}
+
+/// Turns `live`, the set of locals live after `statement`, into the set of
+/// locals live before it.
+fn statement_liveness<'tcx>(statement: &Statement<'tcx>, live: &mut BitSet<Local>) {
+    let location = Location { block: START_BLOCK, statement_index: 0 };
+    TransferVisitor(live).visit_statement(START_BLOCK, statement, location);
+}
+
+/// Like `statement_liveness`, for the terminator of a block. Unlike in
+/// `util::liveness`, `return` reads the return place.
+fn terminator_liveness<'tcx>(terminator: &Terminator<'tcx>, live: &mut BitSet<Local>) {
+    if let TerminatorKind::Return = terminator.kind {
+        live.insert(RETURN_PLACE);
+    }
+    let location = Location { block: START_BLOCK, statement_index: 0 };
+    TransferVisitor(live).visit_terminator(START_BLOCK, terminator, location);
+}
+
+/// Applies the defs and uses of a single statement or terminator directly to
+/// the set of live locals. Within one statement the mention visited last
+/// wins, just like in `util::liveness`.
+struct TransferVisitor<'a>(&'a mut BitSet<Local>);
+
+impl<'a, 'tcx> Visitor<'tcx> for TransferVisitor<'a> {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext<'tcx>, _: Location) {
+        match liveness::categorize(context) {
+            Some(DefUse::Def) => {
+                self.0.remove(local);
+            }
+            Some(DefUse::Use) | Some(DefUse::Drop) => {
+                self.0.insert(local);
+            }
+            None => {}
+        }
+    }
+}
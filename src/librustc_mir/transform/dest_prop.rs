@@ -0,0 +1,422 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Destination propagation: merging locals whose live ranges don't overlap.
+//!
+//! We look for assignments between two locals:
+//!
+//!     DEST = SRC
+//!
+//! If neither local is ever written while the other one holds a value that
+//! is still needed, the two can share their storage. Every mention of `SRC`
+//! is then renamed to `DEST`, and the assignment becomes a self-assignment
+//! that is removed. For the return place this is the named return value
+//! optimization:
+//!
+//!     _1 = [const 0u8; 1024];
+//!     _0 = move _1;
+//!     return;
+//!
+//! becomes
+//!
+//!     _0 = [const 0u8; 1024];
+//!     nop;
+//!     return;
+//!
+//! Unlike copy propagation, the destination and the source may be defined
+//! and used any number of times.
+//!
+//! The liveness computation considers a local to be used only at the point
+//! of a borrow, not where the reference is used, so locals that are ever
+//! borrowed are left alone.
+
+use rustc::mir::*;
+use rustc::mir::visit::{MutVisitor, PlaceContext, Visitor};
+use rustc::ty::TyCtxt;
+use rustc_data_structures::bit_set::{BitMatrix, BitSet};
+use rustc_data_structures::indexed_vec::IndexVec;
+use rustc_data_structures::work_queue::WorkQueue;
+use transform::{MirPass, MirSource};
+use util::liveness::{self, DefUse, LiveVarSet, LiveVariableMap, LivenessResult};
+
+pub struct DestinationPropagation;
+
+impl MirPass for DestinationPropagation {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          _source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        // Like copy propagation, this merges user variables, which confuses
+        // debuggers. Only do it when optimizing MIR harder than by default.
+        if tcx.sess.opts.debugging_opts.mir_opt_level < 2 {
+            return;
+        }
+
+        // Merging two locals changes the live ranges of those two only, so
+        // each round merges any number of disjoint pairs before the liveness
+        // is recomputed.
+        loop {
+            let candidates = find_candidates(mir);
+            if candidates.is_empty() {
+                break;
+            }
+            let map = CandidateMap::new(mir, &candidates);
+            let conflicts = find_conflicts(mir, &map);
+
+            let mut renames: IndexVec<Local, Local> = mir.local_decls.indices().collect();
+            let mut merged = BitSet::new_empty(mir.local_decls.len());
+            for &(dest, src) in &candidates {
+                if merged.contains(dest) || merged.contains(src) {
+                    continue;
+                }
+                let (dest_var, src_var) = (map.from_local[dest].unwrap(),
+                                           map.from_local[src].unwrap());
+                if conflicts.contains(dest_var, src_var) {
+                    debug!("DestinationPropagation: {:?} and {:?} conflict", dest, src);
+                    continue;
+                }
+
+                // Arguments and the return place have to stay where they are.
+                let (keep, replace) = if is_fixed(mir, src) { (src, dest) } else { (dest, src) };
+                debug!("DestinationPropagation: replacing {:?} with {:?}", replace, keep);
+                renames[replace] = keep;
+                merged.insert(dest);
+                merged.insert(src);
+            }
+            if merged.is_empty() {
+                break;
+            }
+
+            LocalRenamer { renames: &renames, merged: &merged }.visit_mir(mir);
+        }
+    }
+}
+
+/// Whether `local` is an argument or the return place, which can't be
+/// renamed.
+fn is_fixed(mir: &Mir, local: Local) -> bool {
+    match mir.local_kind(local) {
+        LocalKind::Arg | LocalKind::ReturnPointer => true,
+        LocalKind::Var | LocalKind::Temp => false,
+    }
+}
+
+/// Returns the `(dest, src)` pairs of all assignments `dest = src` whose
+/// locals could be merged if their live ranges allow it.
+fn find_candidates<'tcx>(mir: &Mir<'tcx>) -> Vec<(Local, Local)> {
+    let mut borrowed = BorrowedLocals(BitSet::new_empty(mir.local_decls.len()));
+    borrowed.visit_mir(mir);
+    let borrowed = borrowed.0;
+
+    let mut candidates = vec![];
+    for data in mir.basic_blocks() {
+        for statement in &data.statements {
+            let (dest, src) = match statement.kind {
+                StatementKind::Assign(
+                    Place::Local(dest),
+                    box Rvalue::Use(Operand::Copy(Place::Local(src))),
+                ) |
+                StatementKind::Assign(
+                    Place::Local(dest),
+                    box Rvalue::Use(Operand::Move(Place::Local(src))),
+                ) => (dest, src),
+                _ => continue,
+            };
+
+            if dest == src ||
+               mir.local_decls[dest].ty != mir.local_decls[src].ty ||
+               borrowed.contains(dest) || borrowed.contains(src) ||
+               (is_fixed(mir, dest) && is_fixed(mir, src)) ||
+               mir.spread_arg == Some(dest) || mir.spread_arg == Some(src) {
+                continue;
+            }
+            candidates.push((dest, src));
+        }
+    }
+    candidates
+}
+
+/// Collects the locals that are borrowed, or have some part borrowed.
+struct BorrowedLocals(BitSet<Local>);
+
+impl<'tcx> Visitor<'tcx> for BorrowedLocals {
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        if let Rvalue::Ref(_, _, ref place) = *rvalue {
+            let mut place = place;
+            while let Place::Projection(ref proj) = *place {
+                place = &proj.base;
+            }
+            if let Place::Local(local) = *place {
+                self.0.insert(local);
+            }
+        }
+        self.super_rvalue(rvalue, location);
+    }
+}
+
+newtype_index! {
+    pub struct CandidateVar { .. }
+}
+
+/// Restricts the liveness computation to the locals mentioned by candidates.
+struct CandidateMap {
+    from_local: IndexVec<Local, Option<CandidateVar>>,
+    to_local: IndexVec<CandidateVar, Local>,
+}
+
+impl CandidateMap {
+    fn new(mir: &Mir, candidates: &[(Local, Local)]) -> Self {
+        let mut from_local = IndexVec::from_elem(None, &mir.local_decls);
+        let mut to_local = IndexVec::new();
+        for &(dest, src) in candidates {
+            for &local in &[dest, src] {
+                if from_local[local].is_none() {
+                    from_local[local] = Some(to_local.push(local));
+                }
+            }
+        }
+        CandidateMap { from_local, to_local }
+    }
+}
+
+impl LiveVariableMap for CandidateMap {
+    type LiveVar = CandidateVar;
+
+    fn from_local(&self, local: Local) -> Option<Self::LiveVar> {
+        self.from_local[local]
+    }
+
+    fn from_live_var(&self, var: Self::LiveVar) -> Local {
+        self.to_local[var]
+    }
+
+    fn num_variables(&self) -> usize {
+        self.to_local.len()
+    }
+}
+
+/// Two locals conflict if one of them is written while the other is live.
+/// Writing `SRC` to `DEST` is the exception: afterwards both hold the same
+/// value, so sharing their storage doesn't change anything.
+fn find_conflicts<'tcx>(mir: &Mir<'tcx>,
+                        map: &CandidateMap)
+                        -> BitMatrix<CandidateVar, CandidateVar> {
+    let num_vars = map.num_variables();
+    let mut liveness = liveness::liveness_of_locals(mir, map);
+    add_return_place_liveness(mir, map, &mut liveness);
+    let mut conflicts = BitMatrix::new(num_vars, num_vars);
+    let mut effects = Effects::new(map);
+
+    for (bb, data) in mir.basic_blocks().iter_enumerated() {
+        let mut live = liveness.outs[bb].clone();
+
+        // `return` reads the return place, which the visitor doesn't see.
+        let mut location = Location { block: bb, statement_index: data.statements.len() };
+        if let TerminatorKind::Return = data.terminator().kind {
+            if let Some(var) = map.from_local(RETURN_PLACE) {
+                live.insert(var);
+            }
+        }
+        effects.visit_terminator(bb, data.terminator(), location);
+        effects.apply(&mut live, &mut conflicts, None);
+
+        for statement in data.statements.iter().rev() {
+            location.statement_index -= 1;
+            let copied = match statement.kind {
+                StatementKind::Assign(
+                    Place::Local(_),
+                    box Rvalue::Use(Operand::Copy(Place::Local(src))),
+                ) |
+                StatementKind::Assign(
+                    Place::Local(_),
+                    box Rvalue::Use(Operand::Move(Place::Local(src))),
+                ) => map.from_local(src),
+                _ => None,
+            };
+            effects.visit_statement(bb, statement, location);
+            effects.apply(&mut live, &mut conflicts, copied);
+        }
+
+        // The arguments are written on entry to the function.
+        if bb == START_BLOCK {
+            for arg in mir.args_iter() {
+                if let Some(arg) = map.from_local(arg) {
+                    for other in live.iter().filter(|&other| other != arg) {
+                        conflicts.insert(arg, other);
+                        conflicts.insert(other, arg);
+                    }
+                }
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// `return` reads the return place without mentioning it, so
+/// `liveness_of_locals` doesn't consider the return place to be live on the
+/// way to a `return`, and a write to a local merged into it there would go
+/// unnoticed. This makes the return place live on exit from every block that
+/// can reach a `return` without assigning it first.
+fn add_return_place_liveness<'tcx>(mir: &Mir<'tcx>,
+                                   map: &CandidateMap,
+                                   liveness: &mut LivenessResult<CandidateVar>) {
+    let return_var = match map.from_local(RETURN_PLACE) {
+        Some(return_var) => return_var,
+        None => return,
+    };
+
+    let mut effects = Effects::new(map);
+    let mut live = LiveVarSet::new_empty(map.num_variables());
+    let mut dirty_queue: WorkQueue<BasicBlock> = WorkQueue::with_all(mir.basic_blocks().len());
+    let predecessors = mir.predecessors();
+
+    while let Some(bb) = dirty_queue.pop() {
+        let data = &mir[bb];
+        live.clear();
+        if liveness.outs[bb].contains(return_var) {
+            live.insert(return_var);
+        }
+        if let TerminatorKind::Return = data.terminator().kind {
+            live.insert(return_var);
+        }
+
+        let mut location = Location { block: bb, statement_index: data.statements.len() };
+        effects.visit_terminator(bb, data.terminator(), location);
+        effects.transfer(&mut live);
+        for statement in data.statements.iter().rev() {
+            location.statement_index -= 1;
+            effects.visit_statement(bb, statement, location);
+            effects.transfer(&mut live);
+        }
+
+        if live.contains(return_var) {
+            for &pred_bb in &predecessors[bb] {
+                if liveness.outs[pred_bb].insert(return_var) {
+                    dirty_queue.insert(pred_bb);
+                }
+            }
+        }
+    }
+}
+
+/// The effect of one statement or terminator on the candidate locals.
+struct Effects<'a> {
+    map: &'a CandidateMap,
+    /// Locals that are written to, even partially.
+    writes: Vec<CandidateVar>,
+    /// Same as in `util::liveness`.
+    defs: LiveVarSet<CandidateVar>,
+    uses: LiveVarSet<CandidateVar>,
+}
+
+impl<'a> Effects<'a> {
+    fn new(map: &'a CandidateMap) -> Self {
+        let num_vars = map.num_variables();
+        Effects {
+            map,
+            writes: vec![],
+            defs: LiveVarSet::new_empty(num_vars),
+            uses: LiveVarSet::new_empty(num_vars),
+        }
+    }
+
+    /// Records the conflicts of the visited statement and turns `live` into
+    /// the set of live locals before it.
+    fn apply(&mut self,
+             live: &mut LiveVarSet<CandidateVar>,
+             conflicts: &mut BitMatrix<CandidateVar, CandidateVar>,
+             copied: Option<CandidateVar>) {
+        for &written in &self.writes {
+            for other in live.iter() {
+                if other != written && Some(other) != copied {
+                    conflicts.insert(written, other);
+                    conflicts.insert(other, written);
+                }
+            }
+        }
+        self.transfer(live);
+    }
+
+    /// Turns `live` into the set of live locals before the visited statement.
+    fn transfer(&mut self, live: &mut LiveVarSet<CandidateVar>) {
+        live.subtract(&self.defs);
+        live.union(&self.uses);
+
+        self.writes.clear();
+        self.defs.clear();
+        self.uses.clear();
+    }
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for Effects<'a> {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext<'tcx>, _: Location) {
+        if let Some(var) = self.map.from_local(local) {
+            if context.is_mutating_use() {
+                self.writes.push(var);
+            }
+            match liveness::categorize(context) {
+                Some(DefUse::Def) => {
+                    self.uses.remove(var);
+                    self.defs.insert(var);
+                }
+                Some(DefUse::Use) | Some(DefUse::Drop) => {
+                    self.defs.remove(var);
+                    self.uses.insert(var);
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+/// Renames the merged locals, and removes their storage statements and the
+/// self-assignments left over from the merge. The merged locals are live for
+/// the whole function afterwards.
+struct LocalRenamer<'a> {
+    renames: &'a IndexVec<Local, Local>,
+    merged: &'a BitSet<Local>,
+}
+
+impl<'a, 'tcx> MutVisitor<'tcx> for LocalRenamer<'a> {
+    fn visit_local(&mut self, local: &mut Local, _: PlaceContext<'tcx>, _: Location) {
+        *local = self.renames[*local];
+    }
+
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &mut Statement<'tcx>,
+                       location: Location) {
+        match statement.kind {
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) if self.merged.contains(local) => {
+                statement.make_nop();
+                return;
+            }
+            _ => {}
+        }
+
+        self.super_statement(block, statement, location);
+
+        match statement.kind {
+            StatementKind::Assign(
+                Place::Local(dest),
+                box Rvalue::Use(Operand::Copy(Place::Local(src))),
+            ) |
+            StatementKind::Assign(
+                Place::Local(dest),
+                box Rvalue::Use(Operand::Move(Place::Local(src))),
+            ) if dest == src => {
+                statement.make_nop();
+            }
+            _ => {}
+        }
+    }
+}
@@ -35,6 +35,8 @@ const HINT_THRESHOLD: usize = 100;
 
 const INSTR_COST: usize = 5;
 const CALL_PENALTY: usize = 25;
+const LANDINGPAD_PENALTY: usize = 50;
+const RESUME_PENALTY: usize = 45;
 
 const UNKNOWN_SIZE_COST: usize = 10;
 
@@ -53,7 +55,11 @@ impl MirPass for Inline {
                           tcx: TyCtxt<'a, 'tcx, 'tcx>,
                           source: MirSource,
                           mir: &mut Mir<'tcx>) {
-        if tcx.sess.opts.debugging_opts.mir_opt_level >= 2 {
+        let enabled = match tcx.sess.opts.debugging_opts.inline_mir {
+            Some(enabled) => enabled,
+            None => tcx.sess.opts.debugging_opts.mir_opt_level >= 2,
+        };
+        if enabled {
             Inliner { tcx, source }.run_pass(mir);
         }
     }
@@ -77,6 +83,11 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
         // in depth. It is unclear if this is the best heuristic,
         // really, but that's true of all the heuristics in this
         // file. =)
+        //
+        // Every callsite also carries the functions that were inlined to
+        // get to it. The query system only catches cycles between local
+        // functions, so this is what keeps us from unrolling recursion
+        // through upstream functions forever.
 
         let mut callsites = VecDeque::new();
 
@@ -93,7 +104,7 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                                                                      bb_data,
                                                                      caller_mir,
                                                                      param_env) {
-                    callsites.push_back(callsite);
+                    callsites.push_back((callsite, vec![]));
                 }
             }
         } else {
@@ -105,8 +116,13 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
 
         loop {
             local_change = false;
-            while let Some(callsite) = callsites.pop_front() {
+            while let Some((callsite, history)) = callsites.pop_front() {
                 debug!("checking whether to inline callsite {:?}", callsite);
+                if callsite.callee == self.source.def_id || history.contains(&callsite.callee) {
+                    debug!("checking whether to inline callsite {:?} - recursive", callsite);
+                    continue;
+                }
+
                 if !self.tcx.is_mir_available(callsite.callee) {
                     debug!("checking whether to inline callsite {:?} - MIR unavailable", callsite);
                     continue;
@@ -144,10 +160,9 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                                                                              bb_data,
                                                                              caller_mir,
                                                                              param_env) {
-                        // Don't inline the same function multiple times.
-                        if callsite.callee != new_callsite.callee {
-                            callsites.push_back(new_callsite);
-                        }
+                        let mut new_history = history.clone();
+                        new_history.push(callsite.callee);
+                        callsites.push_back((new_callsite, new_history));
                     }
                 }
 
@@ -257,20 +272,28 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
             attr::InlineAttr::None => false,
         };
 
-        // Only inline local functions if they would be eligible for cross-crate
-        // inlining. This is to ensure that the final crate doesn't have MIR that
-        // reference unexported symbols
-        if callsite.callee.is_local() {
-            if callsite.substs.types().count() == 0 && !hinted {
-                debug!("    callee is an exported function - not inlining");
-                return false;
-            }
+        // Only inline functions that are eligible for cross-crate inlining,
+        // as those are the only ones whose bodies are guaranteed to refer to
+        // exported symbols only. This goes for upstream functions as well:
+        // with `-Z always-encode-mir`, the MIR of all of them is available.
+        if callsite.substs.types().count() == 0 && !hinted {
+            debug!("    callee is an exported function - not inlining");
+            return false;
+        }
+
+        // The callee may use instructions that the caller isn't allowed to.
+        let caller_fn_attrs = tcx.codegen_fn_attrs(self.source.def_id);
+        if codegen_fn_attrs.target_features.iter()
+            .any(|feature| !caller_fn_attrs.target_features.contains(feature))
+        {
+            debug!("    callee has target features the caller lacks - not inlining");
+            return false;
         }
 
         let mut threshold = if hinted {
-            HINT_THRESHOLD
+            tcx.sess.opts.debugging_opts.inline_mir_hint_threshold.unwrap_or(HINT_THRESHOLD)
         } else {
-            DEFAULT_THRESHOLD
+            tcx.sess.opts.debugging_opts.inline_mir_threshold.unwrap_or(DEFAULT_THRESHOLD)
         };
 
         // Significantly lower the threshold for inlining cold functions
@@ -324,6 +347,7 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                     if ty.needs_drop(tcx, param_env) {
                         cost += CALL_PENALTY;
                         if let Some(unwind) = unwind {
+                            cost += LANDINGPAD_PENALTY;
                             work_list.push(unwind);
                         }
                     } else {
@@ -338,18 +362,27 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                     threshold = 0;
                 }
 
-                TerminatorKind::Call {func: Operand::Constant(ref f), .. } => {
-                    if let ty::FnDef(def_id, _) = f.ty.sty {
-                        // Don't give intrinsics the extra penalty for calls
-                        let f = tcx.fn_sig(def_id);
-                        if f.abi() == Abi::RustIntrinsic || f.abi() == Abi::PlatformIntrinsic {
-                            cost += INSTR_COST;
-                        } else {
-                            cost += CALL_PENALTY;
+                TerminatorKind::Call { ref func, cleanup, .. } => {
+                    // Don't give intrinsics the extra penalty for calls
+                    let is_intrinsic = match func.ty(callee_mir, tcx).sty {
+                        ty::FnDef(def_id, _) => {
+                            let f = tcx.fn_sig(def_id);
+                            f.abi() == Abi::RustIntrinsic || f.abi() == Abi::PlatformIntrinsic
                         }
+                        _ => false,
+                    };
+                    cost += if is_intrinsic { INSTR_COST } else { CALL_PENALTY };
+                    if cleanup.is_some() {
+                        cost += LANDINGPAD_PENALTY;
                     }
                 }
-                TerminatorKind::Assert { .. } => cost += CALL_PENALTY,
+                TerminatorKind::Assert { cleanup, .. } => {
+                    cost += CALL_PENALTY;
+                    if cleanup.is_some() {
+                        cost += LANDINGPAD_PENALTY;
+                    }
+                }
+                TerminatorKind::Resume => cost += RESUME_PENALTY,
                 _ => cost += INSTR_COST
             }
 
@@ -407,14 +440,16 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
                 let mut scope_map = IndexVec::with_capacity(callee_mir.source_scopes.len());
                 let mut promoted_map = IndexVec::with_capacity(callee_mir.promoted.len());
 
+                // The callee's statements keep their spans, so its scopes
+                // do too. Its outermost scope is marked as inlined, which
+                // lets codegen emit debuginfo for an inlined frame.
                 for mut scope in callee_mir.source_scopes.iter().cloned() {
                     if scope.parent_scope.is_none() {
                         scope.parent_scope = Some(callsite.location.scope);
                         scope.span = callee_mir.span;
+                        scope.inlined = Some((callsite.callee, callsite.location.span));
                     }
 
-                    scope.span = callsite.location.span;
-
                     let idx = caller_mir.source_scopes.push(scope);
                     scope_map.push(idx);
                 }
@@ -424,7 +459,6 @@ impl<'a, 'tcx> Inliner<'a, 'tcx> {
 
                     local.source_info.scope =
                         scope_map[local.source_info.scope];
-                    local.source_info.span = callsite.location.span;
                     local.visibility_scope = scope_map[local.visibility_scope];
 
                     let idx = caller_mir.local_decls.push(local);
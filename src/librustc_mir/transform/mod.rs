@@ -42,11 +42,15 @@ pub mod dump_mir;
 pub mod deaggregator;
 pub mod instcombine;
 pub mod copy_prop;
+pub mod dest_prop;
+pub mod sroa;
+pub mod dead_store_elimination;
 pub mod const_prop;
 pub mod generator;
 pub mod inline;
 pub mod lower_128bit;
 pub mod uniform_array_move_out;
+pub mod validate;
 
 pub(crate) fn provide(providers: &mut Providers) {
     self::qualify_consts::provide(providers);
@@ -163,6 +167,7 @@ pub fn run_passes(
     passes: &[&dyn MirPass],
 ) {
     let phase_index = mir_phase.phase_index();
+    let validate_mir = tcx.sess.opts.debugging_opts.validate_mir;
 
     let run_passes = |mir: &mut Mir<'tcx>, promoted| {
         if mir.phase >= mir_phase {
@@ -174,6 +179,7 @@ pub fn run_passes(
             promoted,
         };
         let mut index = 0;
+        let mut passes_run = vec![];
         let mut run_pass = |pass: &dyn MirPass| {
             let run_hooks = |mir: &_, index, is_after| {
                 dump_mir::on_mir_pass(tcx, &format_args!("{:03}-{:03}", phase_index, index),
@@ -183,6 +189,12 @@ pub fn run_passes(
             pass.run_pass(tcx, source, mir);
             run_hooks(mir, index, true);
 
+            if validate_mir {
+                passes_run.push(pass.name().into_owned());
+                validate::validate(tcx, source, mir, mir_phase, &passes_run,
+                                   &format!("after pass `{}`", pass.name()));
+            }
+
             index += 1;
         };
 
@@ -191,6 +203,12 @@ pub fn run_passes(
         }
 
         mir.phase = mir_phase;
+
+        if validate_mir {
+            let passes_run: Vec<_> = passes.iter().map(|pass| pass.name().into_owned()).collect();
+            validate::validate(tcx, source, mir, mir_phase, &passes_run,
+                               &format!("after phase change to {:?}", mir_phase));
+        }
     };
 
     run_passes(mir, None);
@@ -258,6 +276,8 @@ fn optimized_mir<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> &'tcx
         // Remove all `FakeRead` statements and the borrows that are only
         // used for checking matches
         &cleanup_post_borrowck::CleanFakeReadsAndBorrows,
+        // Only does something in the tests of `-Z validate-mir`.
+        &validate::BreakMirForValidation,
 
         &simplify::SimplifyCfg::new("early-opt"),
 
@@ -296,7 +316,10 @@ fn optimized_mir<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> &'tcx
         &const_prop::ConstProp,
         &simplify_branches::SimplifyBranches::new("after-const-prop"),
         &deaggregator::Deaggregator,
+        &sroa::ScalarReplacementOfAggregates,
         &copy_prop::CopyPropagation,
+        &dest_prop::DestinationPropagation,
+        &dead_store_elimination::DeadStoreElimination,
         &remove_noop_landing_pads::RemoveNoopLandingPads,
         &simplify::SimplifyCfg::new("final"),
         &simplify::SimplifyLocals,
@@ -0,0 +1,209 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Scalar replacement of aggregates: splitting tuple and struct locals into
+//! one local per field.
+//!
+//! After the deaggregator, a tuple or struct is often built field by field
+//! and then only ever read field by field:
+//!
+//!     (_3.0: u32) = move _1;
+//!     (_3.1: u32) = move _2;
+//!     _6 = (_3.0: u32);
+//!
+//! Such a local doesn't need to exist as a whole. Each of its fields gets a
+//! local of its own, so that later passes and codegen only see scalars:
+//!
+//!     _9 = move _1;
+//!     _10 = move _2;
+//!     _6 = _9;
+//!
+//! A local is only split if every mention of it is a field projection, or a
+//! `StorageLive`/`StorageDead` statement. Locals that are used as a whole,
+//! or that have any part borrowed, are left alone.
+
+use rustc::mir::*;
+use rustc::mir::visit::{MutVisitor, PlaceContext, Visitor};
+use rustc::session::config::DebugInfo;
+use rustc::ty::{self, Ty, TyCtxt};
+use rustc_data_structures::bit_set::BitSet;
+use rustc_data_structures::indexed_vec::IndexVec;
+use std::collections::BTreeMap;
+use transform::{MirPass, MirSource};
+
+pub struct ScalarReplacementOfAggregates;
+
+impl MirPass for ScalarReplacementOfAggregates {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          _source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        // This is new and not exercised much yet, so only do it when
+        // optimizing MIR harder than by default.
+        if tcx.sess.opts.debugging_opts.mir_opt_level < 2 {
+            return;
+        }
+
+        // Splitting a user variable would hide it from the debugger.
+        let split_vars = tcx.sess.opts.debuginfo != DebugInfo::Full;
+
+        // The fields of a split local can be aggregates themselves, so keep
+        // going until nothing changes.
+        loop {
+            let fields = find_splittable_locals(tcx, mir, split_vars);
+            if fields.iter().all(|fields| fields.is_empty()) {
+                break;
+            }
+
+            // Give every used field a local of its own.
+            let mut replacements = IndexVec::from_elem(BTreeMap::new(), &mir.local_decls);
+            for (local, fields) in fields.iter_enumerated() {
+                let span = mir.local_decls[local].source_info.span;
+                for (&field, &ty) in fields {
+                    let new_local = mir.local_decls.push(LocalDecl::new_temp(ty, span));
+                    debug!("ScalarReplacementOfAggregates: {:?}.{:?} is now {:?}",
+                           local, field, new_local);
+                    replacements[local].insert(field, new_local);
+                }
+            }
+
+            let mut replacer = FieldReplacer { replacements: &replacements };
+            replacer.visit_mir(mir);
+
+            for data in mir.basic_blocks_mut() {
+                data.expand_statements(|statement| {
+                    let (local, live) = match statement.kind {
+                        StatementKind::StorageLive(local) => (local, true),
+                        StatementKind::StorageDead(local) => (local, false),
+                        _ => return None,
+                    };
+                    if replacements[local].is_empty() {
+                        return None;
+                    }
+
+                    let source_info = statement.source_info;
+                    statement.make_nop();
+                    Some(replacements[local].values().map(move |&new_local| Statement {
+                        source_info,
+                        kind: if live {
+                            StatementKind::StorageLive(new_local)
+                        } else {
+                            StatementKind::StorageDead(new_local)
+                        },
+                    }).collect::<Vec<_>>().into_iter())
+                });
+            }
+        }
+    }
+}
+
+/// Returns the fields used of each local that can be split, together with
+/// their types. The fields of all other locals are empty.
+fn find_splittable_locals<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                                    mir: &Mir<'tcx>,
+                                    split_vars: bool)
+                                    -> IndexVec<Local, BTreeMap<Field, Ty<'tcx>>> {
+    let mut visitor = FieldUses {
+        fields: IndexVec::from_elem(BTreeMap::new(), &mir.local_decls),
+        escaping: BitSet::new_empty(mir.local_decls.len()),
+    };
+    visitor.visit_mir(mir);
+
+    let mut fields = visitor.fields;
+    for (local, decl) in mir.local_decls.iter_enumerated() {
+        let splittable = match mir.local_kind(local) {
+            LocalKind::Temp => true,
+            LocalKind::Var => split_vars,
+            LocalKind::Arg | LocalKind::ReturnPointer => false,
+        } && !visitor.escaping.contains(local) && match decl.ty.sty {
+            ty::Tuple(..) => true,
+            ty::Adt(adt_def, _) => {
+                adt_def.is_struct() && !adt_def.repr.simd() && !adt_def.has_dtor(tcx)
+            }
+            _ => false,
+        };
+        if !splittable {
+            fields[local].clear();
+        }
+    }
+    fields
+}
+
+struct FieldUses<'tcx> {
+    /// The fields projected out of each local.
+    fields: IndexVec<Local, BTreeMap<Field, Ty<'tcx>>>,
+    /// Locals that are used other than through a field, or borrowed.
+    escaping: BitSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for FieldUses<'tcx> {
+    fn visit_place(&mut self,
+                   place: &Place<'tcx>,
+                   context: PlaceContext<'tcx>,
+                   location: Location) {
+        if let Place::Projection(ref proj) = *place {
+            if let (&Place::Local(local), &ProjectionElem::Field(field, ty)) =
+                (&proj.base, &proj.elem)
+            {
+                self.fields[local].insert(field, ty);
+                return;
+            }
+        }
+        self.super_place(place, context, location);
+    }
+
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        // A reference to a field would make the other fields reachable too.
+        if let Rvalue::Ref(_, _, ref place) = *rvalue {
+            let mut place = place;
+            while let Place::Projection(ref proj) = *place {
+                if let ProjectionElem::Deref = proj.elem {
+                    break;
+                }
+                place = &proj.base;
+            }
+            if let Place::Local(local) = *place {
+                self.escaping.insert(local);
+            }
+        }
+        self.super_rvalue(rvalue, location);
+    }
+
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext<'tcx>, _: Location) {
+        if !context.is_storage_marker() {
+            self.escaping.insert(local);
+        }
+    }
+}
+
+struct FieldReplacer<'a> {
+    replacements: &'a IndexVec<Local, BTreeMap<Field, Local>>,
+}
+
+impl<'a, 'tcx> MutVisitor<'tcx> for FieldReplacer<'a> {
+    fn visit_place(&mut self,
+                   place: &mut Place<'tcx>,
+                   context: PlaceContext<'tcx>,
+                   location: Location) {
+        let replacement = match *place {
+            Place::Projection(ref proj) => match (&proj.base, &proj.elem) {
+                (&Place::Local(local), &ProjectionElem::Field(field, _)) => {
+                    self.replacements[local].get(&field).cloned()
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        match replacement {
+            Some(new_local) => *place = Place::Local(new_local),
+            None => self.super_place(place, context, location),
+        }
+    }
+}
@@ -0,0 +1,466 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Checks the invariants of the MIR between passes.
+//!
+//! With `-Z validate-mir`, `run_passes` calls `validate` after every pass,
+//! and once more after the MIR reached its new phase. A pass that breaks the
+//! MIR is then reported by name, instead of causing a crash in a later pass
+//! or in LLVM. The MIR is checked against the phase the running passes bring
+//! it to, not the one it is still marked with. The checks are:
+//!
+//! - every local and basic block mentioned exists, `SwitchInt` has one more
+//!   target than it has values, and control only enters cleanup blocks along
+//!   unwind edges and never leaves them;
+//! - assignments, calls and the other terminators agree on the types
+//!   involved, up to regions;
+//! - locals with `StorageLive`/`StorageDead` statements are only used where
+//!   their storage may be live, and arguments and the return place have none;
+//! - constructs that only exist for borrowck or generators are gone once the
+//!   pass of the optimization phase that removes them has run.
+
+use dataflow::{do_dataflow, has_rustc_mir_with, DebugFormatted, MaybeStorageLive};
+use rustc::mir::*;
+use rustc::mir::traversal;
+use rustc::mir::visit::{PlaceContext, Visitor};
+use rustc::ty::{self, Ty, TyCtxt, TypeFoldable};
+use rustc_data_structures::bit_set::BitSet;
+use rustc_data_structures::indexed_vec::Idx;
+use syntax_pos::Span;
+use transform::{MirPass, MirSource};
+
+/// The constructs that only exist before the MIR is optimized, along with the
+/// pass of `MirPhase::Optimized` that removes each of them.
+const REMOVED_FOR_OPTIMIZED_MIR: &[(&str, &str)] = &[
+    ("FalseEdges", "SimplifyBranches-initial"),
+    ("FalseUnwind", "SimplifyBranches-initial"),
+    ("AscribeUserType", "CleanAscribeUserType"),
+    ("FakeRead", "CleanFakeReadsAndBorrows"),
+    ("DropAndReplace", "ElaborateDrops"),
+    ("Yield", "StateTransform"),
+    ("GeneratorDrop", "StateTransform"),
+];
+
+/// Checks `mir` and ICEs if it is broken. `phase` is the phase the running
+/// passes bring the MIR to and `passes_run` are those of them that already
+/// ran, all of them once the phase is reached. `when` describes what last
+/// changed the MIR, e.g. ``after pass `SimplifyCfg-initial` ``.
+pub fn validate<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &Mir<'tcx>,
+                          phase: MirPhase,
+                          passes_run: &[String],
+                          when: &str) {
+    let mut errors = vec![];
+    check_structure(mir, &mut errors);
+
+    // The remaining checks compute types and run dataflow, which would
+    // crash on dangling locals or basic blocks.
+    if errors.is_empty() {
+        TypeChecker::new(tcx, source, mir, phase, passes_run, &mut errors).check();
+    }
+
+    let handler = tcx.sess.diagnostic();
+    let mut errors = errors.into_iter().map(|(span, msg)| {
+        let promoted = source.promoted.map_or(String::new(), |p| format!(" ({:?})", p));
+        (span, format!("broken MIR in {:?}{} {}: {}", source.def_id, promoted, when, msg))
+    }).peekable();
+    while let Some((span, msg)) = errors.next() {
+        if errors.peek().is_some() {
+            handler.span_bug_no_panic(span, &msg);
+        } else {
+            handler.span_bug(span, &msg);
+        }
+    }
+}
+
+fn check_structure<'tcx>(mir: &Mir<'tcx>, errors: &mut Vec<(Span, String)>) {
+    let mut locals = LocalBoundsChecker { mir, errors: vec![] };
+    locals.visit_mir(mir);
+    errors.extend(locals.errors);
+
+    for (bb, data) in mir.basic_blocks().iter_enumerated() {
+        let terminator = data.terminator();
+        let span = terminator.source_info.span;
+
+        if let TerminatorKind::SwitchInt { ref values, ref targets, .. } = terminator.kind {
+            if values.len() + 1 != targets.len() {
+                errors.push((span, format!("{:?} switches on {} values but has {} targets",
+                                           bb, values.len(), targets.len())));
+            }
+        }
+        if let TerminatorKind::Return = terminator.kind {
+            if data.is_cleanup {
+                errors.push((span, format!("cleanup block {:?} returns", bb)));
+            }
+        }
+
+        let unwind = terminator.kind.unwind().and_then(|unwind| *unwind);
+        if data.is_cleanup && unwind.is_some() {
+            errors.push((span, format!("cleanup block {:?} has an unwind edge", bb)));
+        }
+        for &target in terminator.successors() {
+            if target.index() >= mir.basic_blocks().len() {
+                errors.push((span, format!("{:?} jumps to nonexistent {:?}", bb, target)));
+                continue;
+            }
+            let target_is_cleanup = mir[target].is_cleanup;
+            if Some(target) == unwind {
+                if !target_is_cleanup {
+                    errors.push((span, format!("{:?} unwinds to non-cleanup block {:?}",
+                                               bb, target)));
+                }
+            } else if data.is_cleanup != target_is_cleanup {
+                errors.push((span, format!("{:?} jumps to {:?} without unwinding, but only one \
+                                            of them is a cleanup block", bb, target)));
+            }
+        }
+    }
+}
+
+struct LocalBoundsChecker<'a, 'tcx: 'a> {
+    mir: &'a Mir<'tcx>,
+    errors: Vec<(Span, String)>,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for LocalBoundsChecker<'a, 'tcx> {
+    fn visit_local(&mut self, local: &Local, _: PlaceContext<'tcx>, location: Location) {
+        if local.index() >= self.mir.local_decls.len() {
+            let span = self.mir.source_info(location).span;
+            self.errors.push((span, format!("use of nonexistent local {:?}", local)));
+        }
+    }
+}
+
+struct TypeChecker<'a, 'b, 'tcx: 'a> {
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    source: MirSource,
+    mir: &'a Mir<'tcx>,
+    phase: MirPhase,
+    passes_run: &'a [String],
+    /// The locals mentioned in `StorageLive` or `StorageDead` statements. All
+    /// other locals have live storage for the whole body.
+    storage_annotated: BitSet<Local>,
+    /// The locals whose storage may be live at the current location.
+    storage_live: BitSet<Local>,
+    errors: &'b mut Vec<(Span, String)>,
+}
+
+impl<'a, 'b, 'tcx> TypeChecker<'a, 'b, 'tcx> {
+    fn new(tcx: TyCtxt<'a, 'tcx, 'tcx>,
+           source: MirSource,
+           mir: &'a Mir<'tcx>,
+           phase: MirPhase,
+           passes_run: &'a [String],
+           errors: &'b mut Vec<(Span, String)>)
+           -> Self {
+        let mut storage_annotated = BitSet::new_empty(mir.local_decls.len());
+        for data in mir.basic_blocks() {
+            for statement in &data.statements {
+                match statement.kind {
+                    StatementKind::StorageLive(local) |
+                    StatementKind::StorageDead(local) => {
+                        storage_annotated.insert(local);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        TypeChecker {
+            tcx,
+            param_env: tcx.param_env(source.def_id),
+            source,
+            mir,
+            phase,
+            passes_run,
+            storage_annotated,
+            storage_live: BitSet::new_empty(mir.local_decls.len()),
+            errors,
+        }
+    }
+
+    fn check(&mut self) {
+        let node_id = self.tcx.hir.as_local_node_id(self.source.def_id).unwrap();
+        let dead_unwinds = BitSet::new_empty(self.mir.basic_blocks().len());
+        let storage_live = do_dataflow(self.tcx, self.mir, node_id, &[], &dead_unwinds,
+                                       MaybeStorageLive::new(self.mir),
+                                       |bd, p| DebugFormatted::new(&bd.mir().local_decls[p]));
+
+        // According to the dataflow nothing is live in unreachable blocks, so
+        // only check the reachable ones.
+        for (bb, data) in traversal::preorder(self.mir) {
+            self.storage_live.overwrite(storage_live.sets().on_entry_set_for(bb.index()));
+
+            let mut location = bb.start_location();
+            for statement in &data.statements {
+                self.visit_statement(bb, statement, location);
+                match statement.kind {
+                    StatementKind::StorageLive(local) => { self.storage_live.insert(local); }
+                    StatementKind::StorageDead(local) => { self.storage_live.remove(local); }
+                    _ => {}
+                }
+                location = location.successor_within_block();
+            }
+            self.visit_terminator(bb, data.terminator(), location);
+        }
+    }
+
+    /// Whether `construct` should be gone from the MIR by now.
+    fn is_removed(&self, construct: &str) -> bool {
+        self.phase >= MirPhase::Optimized &&
+            REMOVED_FOR_OPTIMIZED_MIR.iter().any(|&(removed, by_pass)| {
+                removed == construct && self.passes_run.iter().any(|pass| *pass == by_pass)
+            })
+    }
+
+    fn fail(&mut self, location: Location, msg: String) {
+        let span = self.mir.source_info(location).span;
+        self.errors.push((span, format!("{:?}: {}", location, msg)));
+    }
+
+    /// Whether `a` and `b` are the same type, ignoring regions and looking
+    /// through associated types and `impl Trait`. Subtyping only differs
+    /// from type equality in the regions, so this is also what an assignment
+    /// requires.
+    fn equal_up_to_regions(&self, a: Ty<'tcx>, b: Ty<'tcx>) -> bool {
+        if a == b || a.references_error() || b.references_error() {
+            return true;
+        }
+
+        let tcx = self.tcx;
+        let erase = |ty| tcx.fold_regions(&ty, &mut false, |_, _| tcx.types.re_erased);
+        let (a, b) = (erase(a), erase(b));
+        if a == b {
+            return true;
+        }
+
+        let param_env = self.param_env.with_reveal_all();
+        erase(tcx.normalize_erasing_regions(param_env, a)) ==
+            erase(tcx.normalize_erasing_regions(param_env, b))
+    }
+
+    fn check_types(&mut self,
+                   location: Location,
+                   what: &str,
+                   expected: Ty<'tcx>,
+                   found: Ty<'tcx>) {
+        if !self.equal_up_to_regions(expected, found) {
+            self.fail(location, format!("{} has type `{}`, expected `{}`", what, found, expected));
+        }
+    }
+
+    /// Checks the operands of `rvalue` against each other. Returns `false` if
+    /// they are inconsistent enough that `Rvalue::ty` would crash.
+    fn check_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) -> bool {
+        match *rvalue {
+            Rvalue::BinaryOp(op, ref lhs, ref rhs) |
+            Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs) => {
+                let lhs_ty = lhs.ty(self.mir, self.tcx);
+                let rhs_ty = rhs.ty(self.mir, self.tcx);
+                match op {
+                    BinOp::Shl | BinOp::Shr | BinOp::Offset => true,
+                    // `BinOp::ty` asserts that these have the exact same type.
+                    BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem |
+                    BinOp::BitXor | BinOp::BitAnd | BinOp::BitOr => {
+                        if lhs_ty != rhs_ty {
+                            self.fail(location, format!("`{:?}` of `{}` and `{}`",
+                                                        op, lhs_ty, rhs_ty));
+                        }
+                        lhs_ty == rhs_ty
+                    }
+                    BinOp::Eq | BinOp::Lt | BinOp::Le |
+                    BinOp::Ne | BinOp::Ge | BinOp::Gt => {
+                        if !self.equal_up_to_regions(lhs_ty, rhs_ty) {
+                            self.fail(location, format!("`{:?}` of `{}` and `{}`",
+                                                        op, lhs_ty, rhs_ty));
+                        }
+                        true
+                    }
+                }
+            }
+            Rvalue::Len(ref place) => {
+                let ty = place.ty(self.mir, self.tcx).to_ty(self.tcx);
+                match ty.sty {
+                    ty::Array(..) | ty::Slice(..) => {}
+                    _ => self.fail(location, format!("length of non-array type `{}`", ty)),
+                }
+                true
+            }
+            Rvalue::Aggregate(box AggregateKind::Array(elem_ty), ref operands) => {
+                for operand in operands {
+                    let ty = operand.ty(self.mir, self.tcx);
+                    self.check_types(location, "array element", elem_ty, ty);
+                }
+                true
+            }
+            _ => true,
+        }
+    }
+
+    fn check_call(&mut self,
+                  func: &Operand<'tcx>,
+                  args: &[Operand<'tcx>],
+                  destination: &Option<(Place<'tcx>, BasicBlock)>,
+                  location: Location) {
+        let func_ty = func.ty(self.mir, self.tcx);
+        let sig = match func_ty.sty {
+            ty::FnDef(..) | ty::FnPtr(_) => func_ty.fn_sig(self.tcx),
+            _ => {
+                self.fail(location, format!("call of non-function type `{}`", func_ty));
+                return;
+            }
+        };
+        let sig = self.tcx.erase_late_bound_regions(&sig);
+
+        if args.len() != sig.inputs().len() && !(sig.variadic && args.len() > sig.inputs().len()) {
+            self.fail(location, format!("call of `{}` with {} arguments", func_ty, args.len()));
+            return;
+        }
+        for (arg, &input_ty) in args.iter().zip(sig.inputs()) {
+            let arg_ty = arg.ty(self.mir, self.tcx);
+            self.check_types(location, "argument", input_ty, arg_ty);
+        }
+        if let Some((ref place, _)) = *destination {
+            let place_ty = place.ty(self.mir, self.tcx).to_ty(self.tcx);
+            self.check_types(location, "call destination", place_ty, sig.output());
+        }
+    }
+}
+
+impl<'a, 'b, 'tcx> Visitor<'tcx> for TypeChecker<'a, 'b, 'tcx> {
+    fn visit_statement(&mut self,
+                       block: BasicBlock,
+                       statement: &Statement<'tcx>,
+                       location: Location) {
+        match statement.kind {
+            StatementKind::StorageLive(local) |
+            StatementKind::StorageDead(local) => {
+                match self.mir.local_kind(local) {
+                    LocalKind::Arg | LocalKind::ReturnPointer => {
+                        self.fail(location, format!("storage statement for {:?}, which is an \
+                                                     argument or the return place", local));
+                    }
+                    LocalKind::Var | LocalKind::Temp => {}
+                }
+            }
+            StatementKind::FakeRead(..) if self.is_removed("FakeRead") => {
+                self.fail(location, format!("`{:?}` after `FakeRead`s were removed", statement));
+            }
+            StatementKind::AscribeUserType(..) if self.is_removed("AscribeUserType") => {
+                self.fail(location, format!("`{:?}` after `AscribeUserType`s were removed",
+                                            statement));
+            }
+            _ => {}
+        }
+        self.super_statement(block, statement, location);
+    }
+
+    fn visit_assign(&mut self,
+                    block: BasicBlock,
+                    place: &Place<'tcx>,
+                    rvalue: &Rvalue<'tcx>,
+                    location: Location) {
+        if self.check_rvalue(rvalue, location) {
+            let place_ty = place.ty(self.mir, self.tcx).to_ty(self.tcx);
+            let rvalue_ty = rvalue.ty(self.mir, self.tcx);
+            self.check_types(location, "assigned value", place_ty, rvalue_ty);
+        }
+        self.super_assign(block, place, rvalue, location);
+    }
+
+    fn visit_terminator_kind(&mut self,
+                             block: BasicBlock,
+                             kind: &TerminatorKind<'tcx>,
+                             location: Location) {
+        match *kind {
+            TerminatorKind::SwitchInt { ref discr, switch_ty, .. } => {
+                let discr_ty = discr.ty(self.mir, self.tcx);
+                self.check_types(location, "switch discriminant", switch_ty, discr_ty);
+            }
+            TerminatorKind::Assert { ref cond, .. } => {
+                let cond_ty = cond.ty(self.mir, self.tcx);
+                self.check_types(location, "assert condition", self.tcx.types.bool, cond_ty);
+            }
+            TerminatorKind::Call { ref func, ref args, ref destination, .. } => {
+                self.check_call(func, args, destination, location);
+            }
+            TerminatorKind::DropAndReplace { location: ref place, ref value, .. } => {
+                let place_ty = place.ty(self.mir, self.tcx).to_ty(self.tcx);
+                let value_ty = value.ty(self.mir, self.tcx);
+                self.check_types(location, "replacement value", place_ty, value_ty);
+            }
+            _ => {}
+        }
+
+        match *kind {
+            TerminatorKind::Yield { .. } if self.mir.yield_ty.is_none() => {
+                self.fail(location, "`yield` outside of a generator".to_string());
+            }
+            _ => {}
+        }
+
+        let construct = match *kind {
+            TerminatorKind::DropAndReplace { .. } => Some("DropAndReplace"),
+            TerminatorKind::Yield { .. } => Some("Yield"),
+            TerminatorKind::GeneratorDrop => Some("GeneratorDrop"),
+            TerminatorKind::FalseEdges { .. } => Some("FalseEdges"),
+            TerminatorKind::FalseUnwind { .. } => Some("FalseUnwind"),
+            _ => None,
+        };
+        if let Some(construct) = construct {
+            if self.is_removed(construct) {
+                self.fail(location, format!("`{:?}` after `{}`s were removed", kind, construct));
+            }
+        }
+
+        self.super_terminator_kind(block, kind, location);
+    }
+
+    fn visit_constant(&mut self, constant: &Constant<'tcx>, location: Location) {
+        self.check_types(location, "constant", constant.ty, constant.literal.ty);
+        self.super_constant(constant, location);
+    }
+
+    fn visit_local(&mut self, local: &Local, context: PlaceContext<'tcx>, location: Location) {
+        if context.is_use() &&
+           self.storage_annotated.contains(*local) &&
+           !self.storage_live.contains(*local) {
+            self.fail(location, format!("use of {:?}, whose storage is dead", local));
+        }
+    }
+}
+
+/// Adds a `FakeRead` to the start of functions marked
+/// `#[rustc_mir(break_mir_for_validation)]` after `FakeRead`s were removed,
+/// so tests can check that `validate` reports it with the name of this pass.
+pub struct BreakMirForValidation;
+
+impl MirPass for BreakMirForValidation {
+    fn run_pass<'a, 'tcx>(&self,
+                          tcx: TyCtxt<'a, 'tcx, 'tcx>,
+                          source: MirSource,
+                          mir: &mut Mir<'tcx>) {
+        if source.promoted.is_some() || !tcx.has_attr(source.def_id, "rustc_mir") {
+            return;
+        }
+        let attributes = tcx.get_attrs(source.def_id);
+        if has_rustc_mir_with(&attributes, "break_mir_for_validation").is_none() {
+            return;
+        }
+
+        let source_info = *mir.source_info(START_BLOCK.start_location());
+        mir.basic_blocks_mut()[START_BLOCK].statements.insert(0, Statement {
+            source_info,
+            kind: StatementKind::FakeRead(FakeReadCause::ForLet, Place::Local(RETURN_PLACE)),
+        });
+    }
+}
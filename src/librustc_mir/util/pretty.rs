@@ -475,7 +475,12 @@ fn write_scope_tree(
     for &child in children {
         let data = &mir.source_scopes[child];
         assert_eq!(data.parent_scope, Some(parent));
-        writeln!(w, "{0:1$}scope {2} {{", "", indent, child.index())?;
+        if let Some((callee, _)) = data.inlined {
+            writeln!(w, "{0:1$}scope {2} (inlined {3}) {{",
+                     "", indent, child.index(), tcx.item_path_str(callee))?;
+        } else {
+            writeln!(w, "{0:1$}scope {2} {{", "", indent, child.index())?;
+        }
 
         // User variable types (including the user's name in a comment).
         for local in mir.vars_iter() {
@@ -241,13 +241,18 @@ impl Options {
             Some("json") => ErrorOutputType::Json(false),
             Some("pretty-json") => ErrorOutputType::Json(true),
             Some("short") => ErrorOutputType::Short(color),
+            Some("sarif") => ErrorOutputType::Sarif,
             None => ErrorOutputType::HumanReadable(color),
             Some(arg) => {
                 early_error(ErrorOutputType::default(),
-                            &format!("argument for --error-format must be `human`, `json` or \
-                                      `short` (instead was `{}`)", arg));
+                            &format!("argument for --error-format must be `human`, `json`, \
+                                      `short` or `sarif` (instead was `{}`)", arg));
             }
         };
+        if error_format == ErrorOutputType::Sarif &&
+           !nightly_options::is_unstable_enabled(matches) {
+            early_error(ErrorOutputType::default(), "--error-format=sarif is unstable");
+        }
 
         let codegen_options = build_codegen_options(matches, error_format);
         let debugging_options = build_debugging_options(matches, error_format);
@@ -30,6 +30,7 @@ use syntax::ast::{self, Ident, NodeId};
 use syntax::source_map;
 use syntax::feature_gate::UnstableFeatures;
 use syntax::json::JsonEmitter;
+use syntax::sarif::SarifEmitter;
 use syntax::ptr::P;
 use syntax::symbol::keywords;
 use syntax_pos::DUMMY_SP;
@@ -268,8 +269,8 @@ impl DocAccessLevels for AccessLevels<DefId> {
 
 /// Creates a new diagnostic `Handler` that can be used to emit warnings and errors.
 ///
-/// If the given `error_format` is `ErrorOutputType::Json` and no `SourceMap` is given, a new one
-/// will be created for the handler.
+/// If the given `error_format` is `ErrorOutputType::Json` or `ErrorOutputType::Sarif` and no
+/// `SourceMap` is given, a new one will be created for the handler.
 pub fn new_handler(error_format: ErrorOutputType,
                    source_map: Option<Lrc<source_map::SourceMap>>,
                    treat_err_as_bug: bool,
@@ -305,6 +306,11 @@ pub fn new_handler(error_format: ErrorOutputType,
                 true,
                 false)
         ),
+        ErrorOutputType::Sarif => {
+            let source_map = source_map.unwrap_or_else(
+                || Lrc::new(source_map::SourceMap::new(sessopts.file_path_mapping())));
+            Box::new(SarifEmitter::stderr(None, source_map))
+        },
     };
 
     errors::Handler::with_emitter_and_flags(
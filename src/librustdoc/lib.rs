@@ -298,7 +298,7 @@ fn opts() -> Vec<RustcOptGroup> {
             o.optopt("",
                      "error-format",
                      "How errors and other messages are produced",
-                     "human|json|short")
+                     "human|json|short|sarif")
         }),
         unstable("disable-minification", |o| {
              o.optflag("",
@@ -643,6 +643,7 @@ impl Tester for Collector {
                 // compiler failures are test failures
                 should_panic: testing::ShouldPanic::No,
                 allow_fail: config.allow_fail,
+                timeout: None,
             },
             testfn: testing::DynTestFn(box move || {
                 let panic = io::set_panic(None);
@@ -504,6 +504,9 @@ declare_features! (
 
     // `reason = ` in lint attributes and `expect` lint attribute
     (active, lint_reasons, "1.31.0", Some(54503), None),
+
+    // Allows a test to set its own time limit with `#[test_timeout = "SECS"]`
+    (active, test_timeout, "1.32.0", None, None),
 );
 
 declare_features! (
@@ -1135,6 +1138,11 @@ pub const BUILTIN_ATTRIBUTES: &'static [(&'static str, AttributeType, AttributeG
                                  "allow_fail attribute is currently unstable",
                                  cfg_fn!(allow_fail))),
 
+    ("test_timeout", Normal, Gated(Stability::Unstable,
+                                   "test_timeout",
+                                   "test_timeout attribute is currently unstable",
+                                   cfg_fn!(test_timeout))),
+
     ("rustc_std_internal_symbol", Whitelisted, Gated(Stability::Unstable,
                                      "rustc_attrs",
                                      "this is an internal attribute that will \
@@ -154,6 +154,7 @@ pub mod util {
 }
 
 pub mod json;
+pub mod sarif;
 
 pub mod syntax {
     pub use ext;
@@ -0,0 +1,305 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A SARIF 2.1 emitter for errors, selected with `--error-format=sarif`.
+//!
+//! Unlike the JSON emitter, which prints one object per diagnostic, SARIF
+//! wants a single log for the whole compilation. The diagnostics are turned
+//! into SARIF results as they are emitted and the log is written out when the
+//! emitter is dropped, i.e. when the session goes away.
+//!
+//! The mapping is as follows:
+//!
+//! * the level and message become the result's `level` and `message`, and the
+//!   error code or lint name becomes its `ruleId`;
+//! * primary spans become `locations` and secondary spans `relatedLocations`,
+//!   with their labels as messages;
+//! * children (notes and helps) become `relatedLocations` if they have spans,
+//!   and are appended to the result's message otherwise;
+//! * every substitution of a `CodeSuggestion` becomes one entry of `fixes`.
+
+use source_map::{SourceMap, FilePathMapping};
+use syntax_pos::{FileName, MultiSpan, Span};
+use errors::registry::Registry;
+use errors::{DiagnosticBuilder, DiagnosticId, CodeSuggestion, Level};
+use errors::emitter::Emitter;
+
+use rustc_data_structures::sync::Lrc;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use rustc_serialize::json::{Json, ToJson};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+pub struct SarifEmitter {
+    dst: Box<dyn Write + Send>,
+    registry: Option<Registry>,
+    sm: Lrc<SourceMap>,
+    /// One entry per error code or lint name seen so far.
+    rules: BTreeMap<String, Json>,
+    results: Vec<Json>,
+}
+
+impl SarifEmitter {
+    pub fn stderr(registry: Option<Registry>, source_map: Lrc<SourceMap>) -> SarifEmitter {
+        SarifEmitter::new(Box::new(io::stderr()), registry, source_map)
+    }
+
+    pub fn basic() -> SarifEmitter {
+        let file_path_mapping = FilePathMapping::empty();
+        SarifEmitter::stderr(None, Lrc::new(SourceMap::new(file_path_mapping)))
+    }
+
+    pub fn new(dst: Box<dyn Write + Send>,
+               registry: Option<Registry>,
+               source_map: Lrc<SourceMap>) -> SarifEmitter {
+        SarifEmitter {
+            dst,
+            registry,
+            sm: source_map,
+            rules: BTreeMap::new(),
+            results: Vec::new(),
+        }
+    }
+
+    fn rule_id(&mut self, code: &DiagnosticId) -> String {
+        let (id, is_error) = match *code {
+            DiagnosticId::Error(ref s) => (s, true),
+            DiagnosticId::Lint(ref s) => (s, false),
+        };
+        if !self.rules.contains_key(id) {
+            let explanation = self.registry
+                                  .as_ref()
+                                  .and_then(|registry| registry.find_description(id));
+            let help_uri = if is_error {
+                Some(format!("https://doc.rust-lang.org/error-index.html#{}", id))
+            } else {
+                None
+            };
+            let rule = object(vec![
+                ("id", id.to_json()),
+                ("fullDescription", explanation.map(text).to_json()),
+                ("helpUri", help_uri.to_json()),
+            ]);
+            self.rules.insert(id.clone(), rule);
+        }
+        id.clone()
+    }
+
+    fn result(&mut self, db: &DiagnosticBuilder) -> Json {
+        let rule_id = db.code.as_ref().map(|code| self.rule_id(code));
+
+        let mut message = db.message();
+        let mut related = self.locations(&db.span, false);
+        for child in &db.children {
+            let child_message = format!("{}: {}", child.level, child.message());
+            let span = child.render_span.as_ref().unwrap_or(&child.span);
+            if span.primary_spans().is_empty() {
+                message.push('\n');
+                message.push_str(&child_message);
+            } else {
+                related.extend(span.primary_spans()
+                                   .iter()
+                                   .map(|&sp| self.location(sp, Some(&child_message))));
+            }
+        }
+
+        let fixes = db.suggestions
+                      .iter()
+                      .flat_map(|sugg| self.fixes(sugg))
+                      .collect::<Vec<_>>();
+
+        object(vec![
+            ("ruleId", rule_id.to_json()),
+            ("level", level(db.level).to_json()),
+            ("message", text(&message)),
+            ("locations", self.locations(&db.span, true).to_json()),
+            ("relatedLocations", related.to_json()),
+            ("fixes", fixes.to_json()),
+        ])
+    }
+
+    /// The primary or the secondary spans of `msp`, with their labels.
+    fn locations(&self, msp: &MultiSpan, primary: bool) -> Vec<Json> {
+        msp.span_labels()
+           .into_iter()
+           .filter(|span_label| span_label.is_primary == primary)
+           .map(|span_label| {
+               self.location(span_label.span, span_label.label.as_ref().map(|l| &l[..]))
+           })
+           .collect()
+    }
+
+    fn location(&self, span: Span, message: Option<&str>) -> Json {
+        let start = self.sm.lookup_char_pos(span.lo());
+        object(vec![
+            ("physicalLocation", object(vec![
+                ("artifactLocation", artifact_location(&start.file.name)),
+                ("region", self.region(span)),
+            ])),
+            ("message", message.map(text).to_json()),
+        ])
+    }
+
+    /// Lines and columns are 1-based, the end column is exclusive and columns
+    /// count characters, which is what `columnKind: unicodeCodePoints` says.
+    fn region(&self, span: Span) -> Json {
+        let start = self.sm.lookup_char_pos(span.lo());
+        let end = self.sm.lookup_char_pos(span.hi());
+        let snippet = self.sm.span_to_snippet(span).ok();
+        object(vec![
+            ("startLine", start.line.to_json()),
+            ("startColumn", (start.col.0 + 1).to_json()),
+            ("endLine", end.line.to_json()),
+            ("endColumn", (end.col.0 + 1).to_json()),
+            ("byteOffset", (span.lo().0 - start.file.start_pos.0).to_json()),
+            ("byteLength", (span.hi().0 - span.lo().0).to_json()),
+            ("snippet", snippet.map(|s| text(&s)).to_json()),
+        ])
+    }
+
+    fn fixes(&self, suggestion: &CodeSuggestion) -> Vec<Json> {
+        suggestion.substitutions.iter().map(|substitution| {
+            // SARIF groups the replacements by the file they apply to.
+            let mut changes: Vec<(FileName, Vec<Json>)> = Vec::new();
+            for part in &substitution.parts {
+                let file_name = self.sm.lookup_char_pos(part.span.lo()).file.name.clone();
+                let replacement = object(vec![
+                    ("deletedRegion", self.region(part.span)),
+                    ("insertedContent", text(&part.snippet)),
+                ]);
+                match changes.iter().position(|&(ref name, _)| *name == file_name) {
+                    Some(i) => changes[i].1.push(replacement),
+                    None => changes.push((file_name, vec![replacement])),
+                }
+            }
+            let changes = changes.into_iter().map(|(file_name, replacements)| {
+                object(vec![
+                    ("artifactLocation", artifact_location(&file_name)),
+                    ("replacements", replacements.to_json()),
+                ])
+            }).collect::<Vec<_>>();
+
+            object(vec![
+                ("description", text(&suggestion.msg)),
+                ("artifactChanges", changes.to_json()),
+                ("properties", object(vec![
+                    ("applicability", format!("{:?}", suggestion.applicability).to_json()),
+                ])),
+            ])
+        }).collect()
+    }
+
+    fn log(&mut self) -> Json {
+        let rules = ::std::mem::replace(&mut self.rules, BTreeMap::new());
+        let results = ::std::mem::replace(&mut self.results, Vec::new());
+        object(vec![
+            ("$schema", SCHEMA.to_json()),
+            ("version", "2.1.0".to_json()),
+            ("runs", vec![object(vec![
+                ("tool", object(vec![
+                    ("driver", object(vec![
+                        ("name", "rustc".to_json()),
+                        ("informationUri", "https://www.rust-lang.org/".to_json()),
+                        ("rules", rules.into_iter().map(|(_, rule)| rule)
+                                       .collect::<Vec<_>>().to_json()),
+                    ])),
+                ])),
+                ("columnKind", "unicodeCodePoints".to_json()),
+                ("results", results.to_json()),
+            ])].to_json()),
+        ])
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit(&mut self, db: &DiagnosticBuilder) {
+        // The "for more information" note only points at `rustc --explain`,
+        // the rules already carry the explanations.
+        if db.level == Level::FailureNote {
+            return;
+        }
+        let result = self.result(db);
+        self.results.push(result);
+    }
+
+    fn should_show_explain(&self) -> bool {
+        false
+    }
+}
+
+impl Drop for SarifEmitter {
+    fn drop(&mut self) {
+        let log = self.log();
+        // Panicking here would abort the process if we are already unwinding,
+        // e.g. from an ICE, so the failure is only reported. `eprintln!`
+        // panics as well if stderr is gone, hence the explicit `writeln!`.
+        if let Err(e) = writeln!(&mut self.dst, "{}", log).and_then(|_| self.dst.flush()) {
+            let _ = writeln!(io::stderr(), "failed to print diagnostics: {:?}", e);
+        }
+    }
+}
+
+fn level(level: Level) -> &'static str {
+    match level {
+        Level::Bug | Level::Fatal | Level::PhaseFatal | Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note | Level::Help => "note",
+        Level::Cancelled | Level::FailureNote => "none",
+    }
+}
+
+/// A SARIF message or artifact content.
+fn text(s: &str) -> Json {
+    object(vec![("text", s.to_json())])
+}
+
+/// Builds a JSON object, leaving out the properties that are `null` or empty
+/// arrays, which SARIF expects to be absent instead.
+fn object(properties: Vec<(&str, Json)>) -> Json {
+    Json::Object(properties.into_iter().filter(|&(_, ref value)| {
+        match *value {
+            Json::Null => false,
+            Json::Array(ref elements) => !elements.is_empty(),
+            _ => true,
+        }
+    }).map(|(key, value)| (key.to_owned(), value)).collect())
+}
+
+fn artifact_location(file_name: &FileName) -> Json {
+    let uri = match *file_name {
+        FileName::Real(ref path) if path.is_absolute() => {
+            let path = path.display().to_string().replace('\\', "/");
+            if path.starts_with('/') {
+                format!("file://{}", escape_uri(&path))
+            } else {
+                format!("file:///{}", escape_uri(&path))
+            }
+        }
+        FileName::Real(ref path) => escape_uri(&path.display().to_string().replace('\\', "/")),
+        ref other => escape_uri(&other.to_string()),
+    };
+    object(vec![("uri", uri.to_json())])
+}
+
+/// Percent-encodes everything but the unreserved characters and the path and
+/// drive separators.
+fn escape_uri(s: &str) -> String {
+    let mut uri = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' |
+            b'-' | b'.' | b'_' | b'~' | b'/' | b':' => uri.push(byte as char),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
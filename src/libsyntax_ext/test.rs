@@ -162,6 +162,14 @@ pub fn expand_test_or_bench(
                     field("ignore", cx.expr_bool(sp, should_ignore(&item))),
                     // allow_fail: true | false
                     field("allow_fail", cx.expr_bool(sp, should_fail(&item))),
+                    // timeout: None | Some(secs)
+                    field("timeout", match test_timeout(cx, &item) {
+                        Some(secs) => cx.expr_some(sp, cx.expr_lit(sp, ast::LitKind::Int(
+                            secs as u128,
+                            ast::LitIntType::Unsigned(ast::UintTy::U64),
+                        ))),
+                        None => cx.expr_none(sp),
+                    }),
                     // should_panic: ...
                     field("should_panic", match should_panic(cx, &item) {
                         // test::ShouldPanic::No
@@ -220,6 +228,21 @@ fn should_fail(i: &ast::Item) -> bool {
     attr::contains_name(&i.attrs, "allow_fail")
 }
 
+fn test_timeout(cx: &ExtCtxt, i: &ast::Item) -> Option<u64> {
+    let attr = attr::find_by_name(&i.attrs, "test_timeout")?;
+    let secs = attr.value_str()
+        .and_then(|s| s.as_str().parse::<u64>().ok())
+        .filter(|&secs| secs > 0);
+    if secs.is_none() {
+        cx.parse_sess.span_diagnostic.span_err(
+            attr.span(),
+            "attribute must be of the form: `#[test_timeout = \"SECS\"]` \
+             with a non-zero number of seconds"
+        );
+    }
+    secs
+}
+
 fn should_panic(cx: &ExtCtxt, i: &ast::Item) -> ShouldPanic {
     match attr::find_by_name(&i.attrs, "should_panic") {
         Some(attr) => {
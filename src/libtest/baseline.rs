@@ -0,0 +1,250 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Saved benchmark results (`--save-baseline`) and the comparison of a new
+//! run against them (`--baseline`).
+//!
+//! A baseline is a text file next to the test binary, at
+//! `bench-baselines/<name>/<binary name>`. Every line after the header holds
+//! the `BenchSamples` of one benchmark as tab-separated values, with the
+//! benchmark name last.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use stats::Summary;
+use super::{BenchSamples, BENCH_SAMPLES};
+
+const HEADER: &str = "# libtest benchmark baseline, format 1";
+const NUM_FIELDS: usize = 15;
+
+/// The change of a benchmark's median relative to the baseline, in percent,
+/// with its 95% confidence interval.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BenchChange {
+    pub pct: f64,
+    pub low_pct: f64,
+    pub high_pct: f64,
+}
+
+impl BenchChange {
+    pub fn is_regression(&self) -> bool {
+        self.low_pct > 0.0
+    }
+
+    pub fn is_improvement(&self) -> bool {
+        self.high_pct < 0.0
+    }
+
+    pub fn describe(&self) -> &'static str {
+        if self.is_regression() {
+            "regressed"
+        } else if self.is_improvement() {
+            "improved"
+        } else {
+            "no change"
+        }
+    }
+}
+
+pub struct Baseline(BTreeMap<String, BenchSamples>);
+
+impl Baseline {
+    pub fn load(name: &str) -> io::Result<Baseline> {
+        let path = baseline_path(name)?;
+        let file = File::open(&path).map_err(|e| io::Error::new(
+            e.kind(),
+            format!("could not read benchmark baseline `{}` from {}: {}",
+                    name, path.display(), e),
+        ))?;
+        Baseline::read(file, &path)
+    }
+
+    fn read(file: File, path: &Path) -> io::Result<Baseline> {
+        let invalid = || io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is not a benchmark baseline", path.display()),
+        );
+
+        let mut lines = BufReader::new(file).lines();
+        match lines.next() {
+            Some(Ok(ref header)) if header == HEADER => {}
+            Some(Err(e)) => return Err(e),
+            _ => return Err(invalid()),
+        }
+
+        let mut benches = BTreeMap::new();
+        for line in lines {
+            let line = line?;
+            let fields = line.splitn(NUM_FIELDS + 1, '\t').collect::<Vec<_>>();
+            if fields.len() != NUM_FIELDS + 1 {
+                return Err(invalid());
+            }
+            let mut values = [0.0; NUM_FIELDS];
+            for (value, field) in values.iter_mut().zip(&fields) {
+                *value = field.parse().map_err(|_| invalid())?;
+            }
+            let bs = BenchSamples {
+                ns_iter_summ: Summary {
+                    sum: values[1],
+                    min: values[2],
+                    max: values[3],
+                    mean: values[4],
+                    median: values[5],
+                    var: values[6],
+                    std_dev: values[7],
+                    std_dev_pct: values[8],
+                    median_abs_dev: values[9],
+                    median_abs_dev_pct: values[10],
+                    quartiles: (values[11], values[12], values[13]),
+                    iqr: values[14],
+                },
+                mb_s: values[0] as usize,
+                change: None,
+            };
+            benches.insert(fields[NUM_FIELDS].to_owned(), bs);
+        }
+        Ok(Baseline(benches))
+    }
+
+    pub fn save(name: &str, benches: &[(String, BenchSamples)]) -> io::Result<()> {
+        let path = baseline_path(name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        Baseline::write(File::create(&path)?, benches)
+    }
+
+    fn write(file: File, benches: &[(String, BenchSamples)]) -> io::Result<()> {
+        let mut out = BufWriter::new(file);
+        writeln!(out, "{}", HEADER)?;
+        for &(ref name, ref bs) in benches {
+            let s = &bs.ns_iter_summ;
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                bs.mb_s,
+                s.sum,
+                s.min,
+                s.max,
+                s.mean,
+                s.median,
+                s.var,
+                s.std_dev,
+                s.std_dev_pct,
+                s.median_abs_dev,
+                s.median_abs_dev_pct,
+                s.quartiles.0,
+                s.quartiles.1,
+                s.quartiles.2,
+                s.iqr,
+                name
+            )?;
+        }
+        out.flush()
+    }
+
+    /// Compares the result of benchmark `name` against the baseline, if the
+    /// baseline has it.
+    pub fn compare(&self, name: &str, new: &BenchSamples) -> Option<BenchChange> {
+        self.0.get(name).and_then(|old| compare(&old.ns_iter_summ, &new.ns_iter_summ))
+    }
+}
+
+/// Estimates the change of the median from `old` to `new`.
+///
+/// The standard error of the median of `n` normally distributed samples is
+/// about `1.2533 * σ / √n`, and `median_abs_dev` is already scaled to be an
+/// estimator of `σ`, so this also holds up against the outliers that
+/// benchmarks tend to have.
+fn compare(old: &Summary, new: &Summary) -> Option<BenchChange> {
+    if old.median <= 0.0 {
+        return None;
+    }
+    let n = BENCH_SAMPLES as f64;
+    let std_err = 1.2533 * (old.median_abs_dev.powi(2) + new.median_abs_dev.powi(2)).sqrt()
+        / n.sqrt();
+    let diff = new.median - old.median;
+    let margin = 1.96 * std_err;
+    Some(BenchChange {
+        pct: diff / old.median * 100.0,
+        low_pct: (diff - margin) / old.median * 100.0,
+        high_pct: (diff + margin) / old.median * 100.0,
+    })
+}
+
+/// Baselines are kept next to the test binary, with one file per binary so
+/// that the benchmarks of several crates can share a baseline name.
+fn baseline_path(name: &str) -> io::Result<PathBuf> {
+    let exe = env::current_exe()?;
+    let dir = exe.parent().map(|p| p.to_owned()).unwrap_or_else(PathBuf::new);
+    let file_name = exe.file_stem().map(|s| s.to_owned()).unwrap_or_default();
+    Ok(dir.join("bench-baselines").join(name).join(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, Baseline};
+    use stats::Summary;
+    use std::env;
+    use std::fs::{self, File};
+    use std::process;
+    use BenchSamples;
+
+    #[test]
+    fn save_load_compare() {
+        let summ = Summary::new(&[100.0, 101.0, 99.0, 100.0, 102.0, 98.0]);
+        let slower = Summary::new(&[150.0, 151.0, 149.0, 150.0, 152.0, 148.0]);
+        let benches = vec![
+            ("a::fast".to_string(), BenchSamples { ns_iter_summ: summ, mb_s: 12, change: None }),
+            ("b\tweird name".to_string(),
+             BenchSamples { ns_iter_summ: slower, mb_s: 0, change: None }),
+        ];
+
+        let path = env::temp_dir().join(format!("libtest-baseline-{}", process::id()));
+        Baseline::write(File::create(&path).unwrap(), &benches).unwrap();
+        let baseline = Baseline::read(File::open(&path).unwrap(), &path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(baseline.0.len(), 2);
+        let loaded = &baseline.0["a::fast"];
+        assert_eq!(loaded.mb_s, 12);
+        assert_eq!(loaded.ns_iter_summ.median, summ.median);
+        assert_eq!(loaded.ns_iter_summ.quartiles, summ.quartiles);
+        assert_eq!(baseline.0["b\tweird name"].ns_iter_summ.median, slower.median);
+
+        let change = baseline.compare("a::fast", &benches[0].1).unwrap();
+        assert!(!change.is_regression() && !change.is_improvement());
+        let new = BenchSamples { ns_iter_summ: slower, mb_s: 12, change: None };
+        assert!(baseline.compare("a::fast", &new).unwrap().is_regression());
+        assert_eq!(baseline.compare("c::missing", &new), None);
+    }
+
+    #[test]
+    fn compare_medians() {
+        let old = Summary::new(&[100.0, 101.0, 99.0, 100.0, 102.0, 98.0]);
+        let same = Summary::new(&[101.0, 100.0, 99.0, 100.0, 98.0, 102.0]);
+        let slower = Summary::new(&[150.0, 151.0, 149.0, 150.0, 152.0, 148.0]);
+
+        let change = compare(&old, &same).unwrap();
+        assert!(!change.is_regression() && !change.is_improvement());
+
+        let change = compare(&old, &slower).unwrap();
+        assert!(change.is_regression());
+        assert!(change.low_pct < 50.0 && 50.0 < change.high_pct);
+
+        let change = compare(&slower, &old).unwrap();
+        assert!(change.is_improvement());
+    }
+}
@@ -48,10 +48,18 @@ impl<T: Write> JsonFormatter<T> {
 }
 
 impl<T: Write> OutputFormatter for JsonFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+    ) -> io::Result<()> {
+        let seed = match shuffle_seed {
+            Some(seed) => format!(r#", "shuffle_seed": {}"#, seed),
+            None => String::new(),
+        };
         self.write_message(&*format!(
-            r#"{{ "type": "suite", "event": "started", "test_count": {} }}"#,
-            test_count
+            r#"{{ "type": "suite", "event": "started", "test_count": {}{} }}"#,
+            test_count, seed
         ))
     }
 
@@ -97,6 +105,19 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
                 self.write_event("test", desc.name.as_slice(), "allowed_failure", None)
             }
 
+            TrTimedOut => {
+                let extra_data = if stdout.len() > 0 {
+                    Some(format!(
+                        r#""stdout": "{}""#,
+                        EscapedString(String::from_utf8_lossy(stdout))
+                    ))
+                } else {
+                    None
+                };
+
+                self.write_event("test", desc.name.as_slice(), "timed_out", extra_data)
+            }
+
             TrBench(ref bs) => {
                 let median = bs.ns_iter_summ.median as usize;
                 let deviation = (bs.ns_iter_summ.max - bs.ns_iter_summ.min) as usize;
@@ -107,12 +128,23 @@ impl<T: Write> OutputFormatter for JsonFormatter<T> {
                     format!(r#", "mib_per_second": {}"#, bs.mb_s)
                 };
 
+                let change = match bs.change {
+                    Some(change) => format!(
+                        ", \"change_pct\": {}, \
+                         \"change_low_pct\": {}, \
+                         \"change_high_pct\": {}, \
+                         \"change\": \"{}\"",
+                        change.pct, change.low_pct, change.high_pct, change.describe()
+                    ),
+                    None => String::new(),
+                };
+
                 let line = format!(
                     "{{ \"type\": \"bench\", \
                      \"name\": \"{}\", \
                      \"median\": {}, \
-                     \"deviation\": {}{} }}",
-                    desc.name, median, deviation, mbps
+                     \"deviation\": {}{}{} }}",
+                    desc.name, median, deviation, mbps, change
                 );
 
                 self.write_message(&*line)
@@ -0,0 +1,233 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use super::*;
+use std::collections::HashMap;
+use std::mem;
+
+/// Writes a JUnit XML report once all tests have finished.
+///
+/// JUnit wants the totals as attributes of the `<testsuite>` element, so the
+/// results are buffered and only written out by `write_run_finish`.
+pub(crate) struct JunitFormatter<T> {
+    out: OutputLocation<T>,
+    started: HashMap<TestDesc, Instant>,
+    shuffle_seed: Option<u64>,
+    results: Vec<(TestDesc, TestResult, Duration, Vec<u8>)>,
+}
+
+impl<T: Write> JunitFormatter<T> {
+    pub fn new(out: OutputLocation<T>) -> Self {
+        Self {
+            out,
+            started: HashMap::new(),
+            shuffle_seed: None,
+            results: Vec::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
+    fn write_message(&mut self, s: &str) -> io::Result<()> {
+        assert!(!s.contains('\n'));
+
+        self.out.write_all(s.as_ref())?;
+        self.out.write_all(b"\n")
+    }
+
+    fn write_system_out(&mut self, stdout: &[u8]) -> io::Result<()> {
+        if stdout.is_empty() {
+            return Ok(());
+        }
+        let output = String::from_utf8_lossy(stdout);
+        self.write_message(&*format!(
+            "<system-out>{}</system-out>",
+            EscapedString(&*output)
+        ))
+    }
+}
+
+impl<T: Write> OutputFormatter for JunitFormatter<T> {
+    fn write_run_start(
+        &mut self,
+        _test_count: usize,
+        shuffle_seed: Option<u64>,
+    ) -> io::Result<()> {
+        self.shuffle_seed = shuffle_seed;
+        self.write_message(r#"<?xml version="1.0" encoding="UTF-8"?>"#)
+    }
+
+    fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
+        self.started.insert(desc.clone(), Instant::now());
+        Ok(())
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        stdout: &[u8],
+    ) -> io::Result<()> {
+        let duration = self.started
+            .remove(desc)
+            .map(|start| start.elapsed())
+            .unwrap_or(Duration::new(0, 0));
+        self.results.push((desc.clone(), result.clone(), duration, stdout.to_vec()));
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // The warning has no place in the report; the result follows anyway.
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        let total_time = self.results
+            .iter()
+            .fold(Duration::new(0, 0), |total, &(_, _, duration, _)| total + duration);
+
+        self.write_message("<testsuites>")?;
+        self.write_message(&*format!(
+            "<testsuite name=\"test\" errors=\"0\" failures=\"{}\" skipped=\"{}\" \
+             tests=\"{}\" time=\"{}\">",
+            state.failed,
+            state.ignored + state.allowed_fail,
+            self.results.len(),
+            fmt_secs(total_time)
+        ))?;
+        if let Some(seed) = self.shuffle_seed {
+            self.write_message("<properties>")?;
+            self.write_message(&*format!(
+                r#"<property name="shuffle_seed" value="{}"/>"#,
+                seed
+            ))?;
+            self.write_message("</properties>")?;
+        }
+
+        for (desc, result, duration, stdout) in mem::replace(&mut self.results, Vec::new()) {
+            let (class_name, test_name) = split_test_name(&desc);
+            let testcase = format!(
+                r#"<testcase classname="{}" name="{}" time="{}""#,
+                EscapedString(class_name),
+                EscapedString(test_name),
+                fmt_secs(duration)
+            );
+
+            match result {
+                TrOk => {
+                    self.write_message(&*format!("{}/>", testcase))?;
+                }
+                TrFailed => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_message(r#"<failure type="assert"/>"#)?;
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+                TrFailedMsg(ref m) => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_message(&*format!(
+                        r#"<failure type="assert" message="{}"/>"#,
+                        EscapedString(m)
+                    ))?;
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+                TrTimedOut => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_message(
+                        r#"<failure type="timeout" message="test exceeded its time limit"/>"#,
+                    )?;
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+                TrIgnored => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_message("<skipped/>")?;
+                    self.write_message("</testcase>")?;
+                }
+                TrAllowedFail => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_message(r#"<skipped message="failed (allowed)"/>"#)?;
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+                TrBench(ref bs) => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_system_out(fmt_bench_samples(bs).trim().as_bytes())?;
+                    self.write_message("</testcase>")?;
+                }
+            }
+        }
+
+        self.write_message("</testsuite>")?;
+        self.write_message("</testsuites>")?;
+
+        Ok(state.failed == 0)
+    }
+}
+
+/// Splits `module::path::test` into the JUnit class name `module::path` and
+/// the test name `test`. Tests at the crate root get the class name `crate`.
+fn split_test_name(desc: &TestDesc) -> (&str, &str) {
+    let name = desc.name.as_slice();
+    match name.rfind("::") {
+        Some(i) => (&name[..i], &name[i + 2..]),
+        None => ("crate", name),
+    }
+}
+
+fn fmt_secs(duration: Duration) -> String {
+    format!("{}.{:06}", duration.as_secs(), duration.subsec_micros())
+}
+
+/// A formatting utility used to print strings with characters in need of
+/// escaping in XML text and attribute values.
+struct EscapedString<S: AsRef<str>>(S);
+
+impl<S: AsRef<str>> ::std::fmt::Display for EscapedString<S> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        let mut start = 0;
+
+        for (i, byte) in self.0.as_ref().bytes().enumerate() {
+            let escaped = match byte {
+                b'<' => "&lt;",
+                b'>' => "&gt;",
+                b'&' => "&amp;",
+                b'"' => "&quot;",
+                b'\'' => "&apos;",
+                b'\n' => "&#10;",
+                b'\r' => "&#13;",
+                b'\t' => "&#9;",
+                // Other control characters are not allowed in XML 1.0 at all.
+                b'\x00'..=b'\x1f' => "\u{fffd}",
+                _ => {
+                    continue;
+                }
+            };
+
+            if start < i {
+                f.write_str(&self.0.as_ref()[start..i])?;
+            }
+
+            f.write_str(escaped)?;
+
+            start = i + 1;
+        }
+
+        if start != self.0.as_ref().len() {
+            f.write_str(&self.0.as_ref()[start..])?;
+        }
+
+        Ok(())
+    }
+}
@@ -56,6 +56,10 @@ impl<T: Write> PrettyFormatter<T> {
         self.write_short_result("FAILED (allowed)", term::color::YELLOW)
     }
 
+    pub fn write_timed_out(&mut self) -> io::Result<()> {
+        self.write_short_result("FAILED (timed out)", term::color::RED)
+    }
+
     pub fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
@@ -155,9 +159,19 @@ impl<T: Write> PrettyFormatter<T> {
 }
 
 impl<T: Write> OutputFormatter for PrettyFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+    ) -> io::Result<()> {
         let noun = if test_count != 1 { "tests" } else { "test" };
-        self.write_plain(&format!("\nrunning {} {}\n", test_count, noun))
+        match shuffle_seed {
+            Some(seed) => self.write_plain(&format!(
+                "\nrunning {} {} (shuffle seed: {})\n",
+                test_count, noun, seed
+            )),
+            None => self.write_plain(&format!("\nrunning {} {}\n", test_count, noun)),
+        }
     }
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -182,6 +196,7 @@ impl<T: Write> OutputFormatter for PrettyFormatter<T> {
             TrFailed | TrFailedMsg(_) => self.write_failed(),
             TrIgnored => self.write_ignored(),
             TrAllowedFail => self.write_allowed_fail(),
+            TrTimedOut => self.write_timed_out(),
             TrBench(ref bs) => {
                 self.write_bench()?;
                 self.write_plain(&format!(": {}\n", fmt_bench_samples(bs)))
@@ -54,6 +54,10 @@ impl<T: Write> TerseFormatter<T> {
         self.write_short_result("a", term::color::YELLOW)
     }
 
+    pub fn write_timed_out(&mut self) -> io::Result<()> {
+        self.write_short_result("T", term::color::RED)
+    }
+
     pub fn write_bench(&mut self) -> io::Result<()> {
         self.write_pretty("bench", term::color::CYAN)
     }
@@ -162,10 +166,20 @@ impl<T: Write> TerseFormatter<T> {
 }
 
 impl<T: Write> OutputFormatter for TerseFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(
+        &mut self,
+        test_count: usize,
+        shuffle_seed: Option<u64>,
+    ) -> io::Result<()> {
         self.total_test_count = test_count;
         let noun = if test_count != 1 { "tests" } else { "test" };
-        self.write_plain(&format!("\nrunning {} {}\n", test_count, noun))
+        match shuffle_seed {
+            Some(seed) => self.write_plain(&format!(
+                "\nrunning {} {} (shuffle seed: {})\n",
+                test_count, noun, seed
+            )),
+            None => self.write_plain(&format!("\nrunning {} {}\n", test_count, noun)),
+        }
     }
 
     fn write_test_start(&mut self, desc: &TestDesc) -> io::Result<()> {
@@ -186,6 +200,7 @@ impl<T: Write> OutputFormatter for TerseFormatter<T> {
             TrFailed | TrFailedMsg(_) => self.write_failed(),
             TrIgnored => self.write_ignored(),
             TrAllowedFail => self.write_allowed_fail(),
+            TrTimedOut => self.write_timed_out(),
             TrBench(ref bs) => {
                 if self.is_multithreaded {
                     self.write_test_name(desc)?;
@@ -79,25 +79,34 @@ use std::process::Termination;
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::borrow::Cow;
-use std::process;
+use std::process::{self, Command, ExitStatus, Stdio};
 
 const TEST_WARN_TIMEOUT_S: u64 = 60;
 const QUIET_MODE_MAX_COLUMN: usize = 100; // insert a '\n' after 100 tests in quiet mode
+const BENCH_SAMPLES: usize = 50; // number of samples summarized for each benchmark
+
+// Set in the environment of the processes spawned by `RunStrategy::SpawnPrimary`
+const SUBPROCESS_ENV: &str = "__RUST_TEST_SUBPROCESS";
+// Exit codes of a spawned test process that ran its test to completion
+const TR_OK: i32 = 50;
+const TR_FAILED: i32 = 51;
 
 // to be used by rustc to compile tests in libtest
 pub mod test {
     pub use {assert_test_result, filter_tests, parse_opts, run_test, test_main, test_main_static,
              Bencher, DynTestFn, DynTestName, Metric, MetricMap, Options, RunIgnored, ShouldPanic,
              StaticBenchFn, StaticTestFn, StaticTestName, TestDesc, TestDescAndFn, TestName,
-             TestOpts, TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk};
+             TestOpts, TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk, TrTimedOut};
 }
 
 pub mod stats;
+mod baseline;
 mod formatters;
 
-use formatters::{JsonFormatter, OutputFormatter, PrettyFormatter, TerseFormatter};
+use baseline::{BenchChange, Baseline};
+use formatters::{JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TerseFormatter};
 
 // The name of a test. By convention this follows the rules for rust
 // paths; i.e. it should be a series of identifiers separated by double
@@ -234,6 +243,9 @@ pub struct TestDesc {
     pub ignore: bool,
     pub should_panic: ShouldPanic,
     pub allow_fail: bool,
+    /// Time limit in seconds after which the test is reported as timed out,
+    /// overriding `--test-timeout`.
+    pub timeout: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -286,6 +298,9 @@ pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Options) {
     };
 
     opts.options = options;
+    if env::var_os(SUBPROCESS_ENV).is_some() {
+        run_test_in_spawned_subprocess(&opts, tests);
+    }
     if opts.list {
         if let Err(e) = list_tests_console(&opts, tests) {
             eprintln!("error: io error when listing tests: {:?}", e);
@@ -355,6 +370,17 @@ pub enum OutputFormat {
     Pretty,
     Terse,
     Json,
+    Junit,
+}
+
+/// How the test runner isolates the tests from each other.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RunStrategy {
+    /// Run each test on its own thread of the test runner process.
+    InProcess,
+    /// Re-execute the test binary once for each test, so that a test
+    /// aborting or exiting the process only takes itself down.
+    SpawnPrimary,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -377,6 +403,16 @@ pub struct TestOpts {
     pub color: ColorConfig,
     pub format: OutputFormat,
     pub test_threads: Option<usize>,
+    pub test_timeout: Option<Duration>,
+    pub run_strategy: RunStrategy,
+    /// Only run the tests of shard `.0` out of `.1` shards.
+    pub shard: Option<(usize, usize)>,
+    /// Run the tests in an order shuffled with this seed.
+    pub shuffle_seed: Option<u64>,
+    /// Compare the benchmarks against the baseline saved under this name.
+    pub baseline: Option<String>,
+    /// Save the benchmark results as a baseline under this name.
+    pub save_baseline: Option<String>,
     pub skip: Vec<String>,
     pub options: Options,
 }
@@ -396,6 +432,12 @@ impl TestOpts {
             color: AutoColor,
             format: OutputFormat::Pretty,
             test_threads: None,
+            test_timeout: None,
+            run_strategy: RunStrategy::InProcess,
+            shard: None,
+            shuffle_seed: None,
+            baseline: None,
+            save_baseline: None,
             skip: vec![],
             options: Options::new(),
         }
@@ -433,6 +475,52 @@ fn optgroups() -> getopts::Options {
              in parallel",
             "n_threads",
         )
+        .optopt(
+            "",
+            "test-timeout",
+            "Report tests running longer than SECS seconds as \
+             timed out instead of waiting for them",
+            "SECS",
+        )
+        .optflag(
+            "",
+            "process-isolation",
+            "Run each test in its own process instead of on a thread",
+        )
+        .optopt(
+            "",
+            "shard-index",
+            "Only run the tests of shard INDEX (counting from 0), \
+             see --shard-count",
+            "INDEX",
+        )
+        .optopt(
+            "",
+            "shard-count",
+            "Split the tests into COUNT shards of about the same size",
+            "COUNT",
+        )
+        .optflagopt(
+            "",
+            "shuffle",
+            "Run the tests in a random order. The seed is printed \
+             so that the order can be reproduced with --shuffle=SEED",
+            "SEED",
+        )
+        .optopt(
+            "",
+            "save-baseline",
+            "Save the benchmark results under NAME for later runs \
+             to compare against",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline",
+            "Compare the benchmark results against the ones saved \
+             under NAME",
+            "NAME",
+        )
         .optmulti(
             "",
             "skip",
@@ -466,8 +554,9 @@ fn optgroups() -> getopts::Options {
             "Configure formatting of output:
             pretty = Print verbose output;
             terse  = Display one character per test;
-            json   = Output a json document",
-            "pretty|terse|json",
+            json   = Output a json document
+            junit  = Output a JUnit XML document",
+            "pretty|terse|json|junit",
         )
         .optopt(
             "Z",
@@ -508,7 +597,10 @@ Test Attributes:
     #[ignore]      - When applied to a function which is already attributed as a
                      test, then the test runner will ignore these tests during
                      normal test runs. Running with --ignored or --include-ignored will run
-                     these tests."#,
+                     these tests.
+    #[test_timeout = "SECS"] - This function (also labeled with #[test]) is
+                     reported as timed out if it runs for longer than SECS
+                     seconds. Overrides --test-timeout."#,
         usage = options.usage(&message)
     );
 }
@@ -609,6 +701,128 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         None => None,
     };
 
+    let test_timeout = match matches.opt_str("test-timeout") {
+        Some(secs_str) => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"test-timeout\" flag is only accepted on the nightly compiler".into()
+                ));
+            }
+            match secs_str.parse::<u64>() {
+                Ok(0) => return Some(Err("argument for --test-timeout must not be 0".to_string())),
+                Ok(secs) => Some(Duration::from_secs(secs)),
+                Err(e) => {
+                    return Some(Err(format!(
+                        "argument for --test-timeout must be a number > 0 \
+                         (error: {})",
+                        e
+                    )))
+                }
+            }
+        }
+        None => None,
+    };
+
+    let process_isolation = matches.opt_present("process-isolation");
+    if !allow_unstable && process_isolation {
+        return Some(Err(
+            "The \"process-isolation\" flag is only accepted on the nightly compiler".into()
+        ));
+    }
+    let run_strategy = if process_isolation {
+        RunStrategy::SpawnPrimary
+    } else {
+        RunStrategy::InProcess
+    };
+
+    let shard = match (matches.opt_str("shard-index"), matches.opt_str("shard-count")) {
+        (None, None) => None,
+        (Some(index_str), Some(count_str)) => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"shard-index\" and \"shard-count\" flags are only accepted on \
+                     the nightly compiler".into()
+                ));
+            }
+            let count = match count_str.parse::<usize>() {
+                Ok(0) => return Some(Err("argument for --shard-count must not be 0".to_string())),
+                Ok(n) => n,
+                Err(e) => {
+                    return Some(Err(format!(
+                        "argument for --shard-count must be a number > 0 \
+                         (error: {})",
+                        e
+                    )))
+                }
+            };
+            let index = match index_str.parse::<usize>() {
+                Ok(n) if n < count => n,
+                Ok(n) => {
+                    return Some(Err(format!(
+                        "argument for --shard-index must be less than --shard-count \
+                         (was {})",
+                        n
+                    )))
+                }
+                Err(e) => {
+                    return Some(Err(format!(
+                        "argument for --shard-index must be a number (error: {})",
+                        e
+                    )))
+                }
+            };
+            Some((index, count))
+        }
+        _ => {
+            return Some(Err(
+                "the options --shard-index and --shard-count must be used together".into()
+            ))
+        }
+    };
+
+    let shuffle_seed = if matches.opt_present("shuffle") {
+        if !allow_unstable {
+            return Some(Err(
+                "The \"shuffle\" flag is only accepted on the nightly compiler".into()
+            ));
+        }
+        match matches.opt_str("shuffle") {
+            Some(seed_str) => match seed_str.parse::<u64>() {
+                Ok(seed) => Some(seed),
+                Err(e) => {
+                    return Some(Err(format!(
+                        "argument for --shuffle must be a number (error: {})",
+                        e
+                    )))
+                }
+            },
+            None => Some(random_seed()),
+        }
+    } else {
+        None
+    };
+
+    let baseline = matches.opt_str("baseline");
+    let save_baseline = matches.opt_str("save-baseline");
+    for (flag, name) in &[("baseline", &baseline), ("save-baseline", &save_baseline)] {
+        if let Some(ref name) = **name {
+            if !allow_unstable {
+                return Some(Err(format!(
+                    "The \"{}\" flag is only accepted on the nightly compiler",
+                    flag
+                )));
+            }
+            if name.is_empty() || name == "." || name == ".." ||
+                name.contains(|c: char| c == '/' || c == '\\')
+            {
+                return Some(Err(format!(
+                    "argument for --{} must be a plain name (was {})",
+                    flag, name
+                )));
+            }
+        }
+    }
+
     let color = match matches.opt_str("color").as_ref().map(|s| &**s) {
         Some("auto") | None => AutoColor,
         Some("always") => AlwaysColor,
@@ -635,10 +849,18 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
             }
             OutputFormat::Json
         }
+        Some("junit") => {
+            if !allow_unstable {
+                return Some(Err(
+                    "The \"junit\" format is only accepted on the nightly compiler".into(),
+                ));
+            }
+            OutputFormat::Junit
+        }
 
         Some(v) => {
             return Some(Err(format!(
-                "argument for --format must be pretty, terse, or json (was \
+                "argument for --format must be pretty, terse, json, or junit (was \
                  {})",
                 v
             )))
@@ -657,6 +879,12 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
         color,
         format,
         test_threads,
+        test_timeout,
+        run_strategy,
+        shard,
+        shuffle_seed,
+        baseline,
+        save_baseline,
         skip: matches.opt_strs("skip"),
         options: Options::new(),
     };
@@ -664,10 +892,18 @@ pub fn parse_opts(args: &[String]) -> Option<OptRes> {
     Some(Ok(test_opts))
 }
 
+fn random_seed() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::new(0, 0));
+    now.as_secs() ^ ((now.subsec_nanos() as u64) << 32) ^ process::id() as u64
+}
+
 #[derive(Clone, PartialEq)]
 pub struct BenchSamples {
     ns_iter_summ: stats::Summary,
     mb_s: usize,
+    change: Option<BenchChange>,
 }
 
 #[derive(Clone, PartialEq)]
@@ -678,6 +914,7 @@ pub enum TestResult {
     TrIgnored,
     TrAllowedFail,
     TrBench(BenchSamples),
+    TrTimedOut,
 }
 
 unsafe impl Send for TestResult {}
@@ -716,6 +953,9 @@ struct ConsoleTestState {
     failures: Vec<(TestDesc, Vec<u8>)>,
     not_failures: Vec<(TestDesc, Vec<u8>)>,
     options: Options,
+    shuffle_seed: Option<u64>,
+    baseline: Option<Baseline>,
+    benches: Vec<(String, BenchSamples)>,
 }
 
 impl ConsoleTestState {
@@ -738,6 +978,9 @@ impl ConsoleTestState {
             failures: Vec::new(),
             not_failures: Vec::new(),
             options: opts.options,
+            shuffle_seed: opts.shuffle_seed,
+            baseline: None,
+            benches: Vec::new(),
         })
     }
 
@@ -759,6 +1002,7 @@ impl ConsoleTestState {
                 TrIgnored => "ignored".to_owned(),
                 TrAllowedFail => "failed (allowed)".to_owned(),
                 TrBench(ref bs) => fmt_bench_samples(bs),
+                TrTimedOut => "timed out".to_owned(),
             },
             test.name
         ))
@@ -812,6 +1056,17 @@ pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
             .write_fmt(format_args!(" = {} MB/s", bs.mb_s))
             .unwrap();
     }
+    if let Some(change) = bs.change {
+        output
+            .write_fmt(format_args!(
+                " ({:+.2}% [{:+.2}%, {:+.2}%], {})",
+                change.pct,
+                change.low_pct,
+                change.high_pct,
+                change.describe()
+            ))
+            .unwrap();
+    }
     output
 }
 
@@ -863,12 +1118,16 @@ pub fn list_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Res
             writeln!(output, "")?;
         }
 
-        writeln!(
+        write!(
             output,
             "{}, {}",
             plural(ntest, "test"),
             plural(nbench, "benchmark")
         )?;
+        match opts.shuffle_seed {
+            Some(seed) => writeln!(output, " (shuffle seed: {})", seed)?,
+            None => writeln!(output, "")?,
+        }
     }
 
     Ok(())
@@ -884,12 +1143,21 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
         match (*event).clone() {
             TeFiltered(ref filtered_tests) => {
                 st.total = filtered_tests.len();
-                out.write_run_start(filtered_tests.len())
+                out.write_run_start(filtered_tests.len(), st.shuffle_seed)
             }
             TeFilteredOut(filtered_out) => Ok(st.filtered_out = filtered_out),
             TeWait(ref test) => out.write_test_start(test),
             TeTimeout(ref test) => out.write_timeout(test),
             TeResult(test, result, stdout) => {
+                let result = match result {
+                    TrBench(mut bs) => {
+                        if let Some(ref baseline) = st.baseline {
+                            bs.change = baseline.compare(test.name.as_slice(), &bs);
+                        }
+                        TrBench(bs)
+                    }
+                    result => result,
+                };
                 st.write_log_result(&test, &result)?;
                 out.write_result(&test, &result, &*stdout)?;
                 match result {
@@ -905,6 +1173,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
                             bs.ns_iter_summ.median,
                             bs.ns_iter_summ.max - bs.ns_iter_summ.min,
                         );
+                        st.benches.push((test.name.as_slice().to_owned(), bs));
                         st.measured += 1
                     }
                     TrFailed => {
@@ -917,6 +1186,12 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
                         stdout.extend_from_slice(format!("note: {}", msg).as_bytes());
                         st.failures.push((test, stdout));
                     }
+                    TrTimedOut => {
+                        st.failed += 1;
+                        let mut stdout = stdout;
+                        stdout.extend_from_slice(b"note: test exceeded its time limit");
+                        st.failures.push((test, stdout));
+                    }
                 }
                 Ok(())
             }
@@ -950,8 +1225,12 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
             is_multithreaded,
         )),
         OutputFormat::Json => Box::new(JsonFormatter::new(output)),
+        OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
     };
     let mut st = ConsoleTestState::new(opts)?;
+    if let Some(ref name) = opts.baseline {
+        st.baseline = Some(Baseline::load(name)?);
+    }
     fn len_if_padded(t: &TestDescAndFn) -> usize {
         match t.testfn.padding() {
             PadNone => 0,
@@ -963,7 +1242,19 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
 
     assert!(st.current_test_count() == st.total);
 
-    return out.write_run_finish(&st);
+    let success = out.write_run_finish(&st)?;
+
+    if let Some(ref name) = opts.save_baseline {
+        // Don't replace a baseline with nothing, e.g. when `--bench` was
+        // forgotten or the filter didn't match any benchmark.
+        if st.benches.is_empty() {
+            eprintln!("warning: no benchmarks were run, not saving baseline `{}`", name);
+        } else {
+            Baseline::save(name, &st.benches)?;
+        }
+    }
+
+    Ok(success)
 }
 
 #[test]
@@ -973,6 +1264,7 @@ fn should_sort_failures_before_printing_them() {
         ignore: false,
         should_panic: ShouldPanic::No,
         allow_fail: false,
+        timeout: None,
     };
 
     let test_b = TestDesc {
@@ -980,6 +1272,7 @@ fn should_sort_failures_before_printing_them() {
         ignore: false,
         should_panic: ShouldPanic::No,
         allow_fail: false,
+        timeout: None,
     };
 
     let mut out = PrettyFormatter::new(Raw(Vec::new()), false, 10, false);
@@ -997,6 +1290,9 @@ fn should_sort_failures_before_printing_them() {
         failures: vec![(test_b, Vec::new()), (test_a, Vec::new())],
         options: Options::new(),
         not_failures: Vec::new(),
+        shuffle_seed: None,
+        baseline: None,
+        benches: Vec::new(),
     };
 
     out.write_failures(&st).unwrap();
@@ -1010,6 +1306,47 @@ fn should_sort_failures_before_printing_them() {
     assert!(apos < bpos);
 }
 
+#[test]
+fn junit_formatter_reports_failures() {
+    let test_ok = TestDesc {
+        name: StaticTestName("tests::a"),
+        ignore: false,
+        should_panic: ShouldPanic::No,
+        allow_fail: false,
+        timeout: None,
+    };
+
+    let test_failed = TestDesc {
+        name: StaticTestName("b<c>"),
+        ignore: false,
+        should_panic: ShouldPanic::No,
+        allow_fail: false,
+        timeout: None,
+    };
+
+    let mut out = JunitFormatter::new(Raw(Vec::new()));
+    out.write_run_start(2, None).unwrap();
+    out.write_test_start(&test_ok).unwrap();
+    out.write_result(&test_ok, &TrOk, &[]).unwrap();
+    out.write_test_start(&test_failed).unwrap();
+    out.write_result(&test_failed, &TrFailed, b"oh no").unwrap();
+
+    let mut st = ConsoleTestState::new(&TestOpts::new()).unwrap();
+    st.passed = 1;
+    st.failed = 1;
+    out.write_run_finish(&st).unwrap();
+
+    let s = match out.output_location() {
+        &Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+        &Pretty(_) => unreachable!(),
+    };
+
+    assert!(s.contains(r#"failures="1" skipped="0" tests="2""#));
+    assert!(s.contains(r#"<testcase classname="tests" name="a""#));
+    assert!(s.contains(r#"<testcase classname="crate" name="b&lt;c&gt;""#));
+    assert!(s.contains("<system-out>oh no</system-out>"));
+}
+
 fn use_color(opts: &TestOpts) -> bool {
     match opts.color {
         AutoColor => !opts.nocapture && stdout_isatty(),
@@ -1071,7 +1408,7 @@ pub fn run_tests<F>(opts: &TestOpts, tests: Vec<TestDescAndFn>, mut callback: F)
 where
     F: FnMut(TestEvent) -> io::Result<()>,
 {
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::sync::mpsc::RecvTimeoutError;
 
     let tests_len = tests.len();
@@ -1112,6 +1449,13 @@ where
     let (tx, rx) = channel::<MonitorMsg>();
 
     let mut running_tests: HashMap<TestDesc, Instant> = HashMap::new();
+    // Hard deadlines of the running tests that have a time limit. Spawned
+    // test processes are instead killed by their runner thread, which then
+    // reports the timeout itself.
+    let mut deadlines: HashMap<TestDesc, Instant> = HashMap::new();
+    // Tests that were reported as timed out. Their threads cannot be killed,
+    // so any result they send afterwards has to be dropped.
+    let mut abandoned: HashSet<TestDesc> = HashSet::new();
 
     fn get_timed_out_tests(running_tests: &mut HashMap<TestDesc, Instant>) -> Vec<TestDesc> {
         let now = Instant::now();
@@ -1145,43 +1489,94 @@ where
     if concurrency == 1 {
         while !remaining.is_empty() {
             let test = remaining.pop().unwrap();
-            callback(TeWait(test.desc.clone()))?;
+            let desc = test.desc.clone();
+            callback(TeWait(desc.clone()))?;
+            let deadline = match opts.run_strategy {
+                RunStrategy::InProcess => {
+                    test_timeout(opts, &desc).map(|timeout| Instant::now() + timeout)
+                }
+                RunStrategy::SpawnPrimary => None,
+            };
             run_test(opts, !opts.run_tests, test, tx.clone());
-            let (test, result, stdout) = rx.recv().unwrap();
-            callback(TeResult(test, result, stdout))?;
+            let res = loop {
+                let res = match deadline {
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        let timeout = if deadline >= now {
+                            deadline - now
+                        } else {
+                            Duration::new(0, 0)
+                        };
+                        rx.recv_timeout(timeout)
+                    }
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match res {
+                    Ok(ref msg) if abandoned.contains(&msg.0) => continue,
+                    res => break res,
+                }
+            };
+            match res {
+                Ok((test, result, stdout)) => callback(TeResult(test, result, stdout))?,
+                Err(RecvTimeoutError::Timeout) => {
+                    abandoned.insert(desc.clone());
+                    callback(TeResult(desc, TrTimedOut, Vec::new()))?;
+                }
+                Err(RecvTimeoutError::Disconnected) => unreachable!(),
+            }
         }
     } else {
         while pending > 0 || !remaining.is_empty() {
             while pending < concurrency && !remaining.is_empty() {
                 let test = remaining.pop().unwrap();
-                let timeout = Instant::now() + Duration::from_secs(TEST_WARN_TIMEOUT_S);
+                let now = Instant::now();
+                let timeout = now + Duration::from_secs(TEST_WARN_TIMEOUT_S);
                 running_tests.insert(test.desc.clone(), timeout);
+                if opts.run_strategy == RunStrategy::InProcess {
+                    if let Some(limit) = test_timeout(opts, &test.desc) {
+                        deadlines.insert(test.desc.clone(), now + limit);
+                    }
+                }
                 callback(TeWait(test.desc.clone()))?; //here no pad
                 run_test(opts, !opts.run_tests, test, tx.clone());
                 pending += 1;
             }
 
-            let mut res;
-            loop {
-                if let Some(timeout) = calc_timeout(&running_tests) {
-                    res = rx.recv_timeout(timeout);
-                    for test in get_timed_out_tests(&mut running_tests) {
-                        callback(TeTimeout(test))?;
-                    }
-                    if res != Err(RecvTimeoutError::Timeout) {
-                        break;
+            let mut finished = Vec::new();
+            while finished.is_empty() {
+                let timeout = match (calc_timeout(&running_tests), calc_timeout(&deadlines)) {
+                    (Some(warn), Some(limit)) => Some(cmp::min(warn, limit)),
+                    (warn, limit) => warn.or(limit),
+                };
+                let res = match timeout {
+                    Some(timeout) => rx.recv_timeout(timeout),
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                for test in get_timed_out_tests(&mut running_tests) {
+                    callback(TeTimeout(test))?;
+                }
+                for test in get_timed_out_tests(&mut deadlines) {
+                    abandoned.insert(test.clone());
+                    finished.push((test, TrTimedOut, Vec::new()));
+                }
+                match res {
+                    Ok(msg) => {
+                        if !abandoned.contains(&msg.0) {
+                            finished.push(msg);
+                        }
                     }
-                } else {
-                    res = rx.recv().map_err(|_| RecvTimeoutError::Disconnected);
-                    break;
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => unreachable!(),
                 }
             }
 
-            let (desc, result, stdout) = res.unwrap();
-            running_tests.remove(&desc);
+            for (desc, result, stdout) in finished {
+                running_tests.remove(&desc);
+                deadlines.remove(&desc);
 
-            callback(TeResult(desc, result, stdout))?;
-            pending -= 1;
+                callback(TeResult(desc, result, stdout))?;
+                pending -= 1;
+            }
         }
     }
 
@@ -1197,6 +1592,12 @@ where
     Ok(())
 }
 
+/// The time limit that applies to `desc`: its own `#[test_timeout]` if it has
+/// one, otherwise the one given with `--test-timeout`.
+fn test_timeout(opts: &TestOpts, desc: &TestDesc) -> Option<Duration> {
+    desc.timeout.map(Duration::from_secs).or(opts.test_timeout)
+}
+
 #[allow(deprecated)]
 fn get_concurrency() -> usize {
     return match env::var("RUST_TEST_THREADS") {
@@ -1361,9 +1762,41 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
     // Sort the tests alphabetically
     filtered.sort_by(|t1, t2| t1.desc.name.as_slice().cmp(t2.desc.name.as_slice()));
 
+    // Keep this shard's share of the tests. This is done on the sorted list so
+    // that all shards agree on the split, whatever their shuffle seeds are.
+    if let Some((index, count)) = opts.shard {
+        filtered = filtered
+            .into_iter()
+            .enumerate()
+            .filter(|&(i, _)| i % count == index)
+            .map(|(_, test)| test)
+            .collect();
+    }
+
+    if let Some(seed) = opts.shuffle_seed {
+        shuffle_tests(seed, &mut filtered);
+    }
+
     filtered
 }
 
+/// Shuffles `tests` with a Fisher-Yates shuffle driven by SplitMix64, so that
+/// the same seed always gives the same order on every platform.
+fn shuffle_tests(seed: u64, tests: &mut [TestDescAndFn]) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    for i in (1..tests.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        tests.swap(i, j);
+    }
+}
+
 pub fn convert_benchmarks_to_tests(tests: Vec<TestDescAndFn>) -> Vec<TestDescAndFn> {
     // convert benchmarks to tests, if we're not benchmarking them
     tests
@@ -1460,6 +1893,10 @@ pub fn run_test(
                 (benchfn.clone())(harness)
             });
         }
+        DynTestFn(_) | StaticTestFn(_) if opts.run_strategy == RunStrategy::SpawnPrimary => {
+            let timeout = test_timeout(opts, &desc);
+            run_test_in_subprocess(desc, monitor_ch, opts.nocapture, timeout)
+        }
         DynTestFn(f) => {
             let cb = move || __rust_begin_short_backtrace(f);
             run_test_inner(desc, monitor_ch, opts.nocapture, Box::new(cb))
@@ -1473,6 +1910,165 @@ pub fn run_test(
     }
 }
 
+/// Runs `desc` in a new process of the test binary, killing the process if
+/// it is still running after `timeout`.
+fn run_test_in_subprocess(
+    desc: TestDesc,
+    monitor_ch: Sender<MonitorMsg>,
+    nocapture: bool,
+    timeout: Option<Duration>,
+) {
+    let name = desc.name.clone();
+    let runtest = move || {
+        let (test_result, stdout) = match spawn_test_subprocess(&desc, nocapture, timeout) {
+            Ok(res) => res,
+            Err(e) => (
+                TrFailedMsg(format!("failed to spawn the test process: {}", e)),
+                Vec::new(),
+            ),
+        };
+        monitor_ch
+            .send((desc.clone(), test_result, stdout))
+            .unwrap();
+    };
+
+    let cfg = thread::Builder::new().name(name.as_slice().to_owned());
+    cfg.spawn(runtest).unwrap();
+}
+
+fn spawn_test_subprocess(
+    desc: &TestDesc,
+    nocapture: bool,
+    timeout: Option<Duration>,
+) -> io::Result<(TestResult, Vec<u8>)> {
+    let stdio = || if nocapture { Stdio::inherit() } else { Stdio::piped() };
+    let mut child = Command::new(env::current_exe()?)
+        .arg("--exact")
+        .arg(desc.name.as_slice())
+        .env(SUBPROCESS_ENV, "1")
+        .stdin(Stdio::null())
+        .stdout(stdio())
+        .stderr(stdio())
+        .spawn()?;
+
+    // Drain both pipes while waiting so that the child can't block on a
+    // full pipe.
+    fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    }
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let status = match timeout {
+        Some(timeout) => {
+            let deadline = Instant::now() + timeout;
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    break Some(status);
+                }
+                if Instant::now() >= deadline {
+                    child.kill()?;
+                    child.wait()?;
+                    break None;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+        None => Some(child.wait()?),
+    };
+
+    let mut output = stdout.join().unwrap_or_default();
+    output.extend(stderr.join().unwrap_or_default());
+
+    let test_result = match status {
+        Some(status) => calc_subprocess_result(desc, status, &output),
+        None => TrTimedOut,
+    };
+    Ok((test_result, output))
+}
+
+/// Runs the single test selected by the command line of a process spawned by
+/// `spawn_test_subprocess`, and reports its outcome through the exit code.
+fn run_test_in_spawned_subprocess(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> ! {
+    let mut tests = convert_benchmarks_to_tests(filter_tests(opts, tests));
+    if tests.len() != 1 {
+        eprintln!("error: expected exactly one test to run, found {}", tests.len());
+        process::exit(101);
+    }
+    let TestDescAndFn { desc, testfn } = tests.pop().unwrap();
+
+    let testfn: Box<dyn FnBox() + Send> = match testfn {
+        DynTestFn(f) => Box::new(move || __rust_begin_short_backtrace(f)),
+        StaticTestFn(f) => Box::new(move || __rust_begin_short_backtrace(f)),
+        StaticBenchFn(..) | DynBenchFn(..) => unreachable!(),
+    };
+    let result = catch_unwind(AssertUnwindSafe(testfn));
+
+    // `allow_fail` is applied by the parent process, which also has to apply it
+    // to tests that didn't get to exit normally.
+    let desc = TestDesc { allow_fail: false, ..desc };
+    match calc_result(&desc, result) {
+        TrOk => process::exit(TR_OK),
+        TrFailedMsg(msg) => {
+            eprintln!("note: {}", msg);
+            process::exit(TR_FAILED)
+        }
+        _ => process::exit(TR_FAILED),
+    }
+}
+
+fn calc_subprocess_result(desc: &TestDesc, status: ExitStatus, output: &[u8]) -> TestResult {
+    let result = match status.code() {
+        Some(TR_OK) => TrOk,
+        Some(TR_FAILED) => TrFailed,
+        // With `-C panic=abort` a panic takes the whole process down, which
+        // is the expected outcome of a `#[should_panic]` test.
+        _ if is_abort(&status) => match desc.should_panic {
+            ShouldPanic::No => TrFailedMsg(format!("test process aborted ({})", status)),
+            ShouldPanic::Yes => TrOk,
+            ShouldPanic::YesWithMessage(msg) => {
+                if String::from_utf8_lossy(output).contains(msg) {
+                    TrOk
+                } else {
+                    TrFailedMsg(format!("Panic did not include expected string '{}'", msg))
+                }
+            }
+        },
+        _ => TrFailedMsg(format!("test process exited unexpectedly ({})", status)),
+    };
+
+    match result {
+        TrOk => TrOk,
+        _ if desc.allow_fail => TrAllowedFail,
+        result => result,
+    }
+}
+
+#[cfg(unix)]
+fn is_abort(status: &ExitStatus) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal() == Some(libc::SIGABRT)
+}
+#[cfg(windows)]
+fn is_abort(status: &ExitStatus) -> bool {
+    // `std::process::abort` uses `__fastfail`, which terminates the process
+    // with STATUS_STACK_BUFFER_OVERRUN; the CRT's `abort` exits with 3.
+    match status.code() {
+        Some(code) => code == 0xC0000409_u32 as i32 || code == 3,
+        None => false,
+    }
+}
+#[cfg(not(any(unix, windows)))]
+fn is_abort(_status: &ExitStatus) -> bool {
+    false
+}
+
 /// Fixed frame used to clean the backtrace with `RUST_BACKTRACE=1`.
 #[inline(never)]
 fn __rust_begin_short_backtrace<F: FnOnce()>(f: F) {
@@ -1616,7 +2212,7 @@ where
     n = cmp::max(1, n);
 
     let mut total_run = Duration::new(0, 0);
-    let samples: &mut [f64] = &mut [0.0_f64; 50];
+    let samples: &mut [f64] = &mut [0.0_f64; BENCH_SAMPLES];
     loop {
         let loop_start = Instant::now();
 
@@ -1710,6 +2306,7 @@ pub mod bench {
                 let bs = BenchSamples {
                     ns_iter_summ,
                     mb_s: mb_s as usize,
+                    change: None,
                 };
                 TestResult::TrBench(bs)
             }
@@ -1720,6 +2317,7 @@ pub mod bench {
                 let bs = BenchSamples {
                     ns_iter_summ: stats::Summary::new(samples),
                     mb_s: 0,
+                    change: None,
                 };
                 TestResult::TrBench(bs)
             }
@@ -1747,8 +2345,11 @@ pub mod bench {
 mod tests {
     use test::{filter_tests, parse_opts, run_test, DynTestFn, DynTestName, MetricMap, RunIgnored,
                ShouldPanic, StaticTestName, TestDesc, TestDescAndFn, TestOpts, TrFailed,
-               TrFailedMsg, TrIgnored, TrOk};
+               TrFailedMsg, TrIgnored, TrOk, TrTimedOut};
     use std::sync::mpsc::channel;
+    use std::thread;
+    use std::time::Duration;
+    use {run_tests, RunStrategy, TestEvent};
     use bench;
     use Bencher;
 
@@ -1761,6 +2362,7 @@ mod tests {
                     ignore: true,
                     should_panic: ShouldPanic::No,
                     allow_fail: false,
+                    timeout: None,
                 },
                 testfn: DynTestFn(Box::new(move || {})),
             },
@@ -1770,6 +2372,7 @@ mod tests {
                     ignore: false,
                     should_panic: ShouldPanic::No,
                     allow_fail: false,
+                    timeout: None,
                 },
                 testfn: DynTestFn(Box::new(move || {})),
             },
@@ -1787,6 +2390,7 @@ mod tests {
                 ignore: true,
                 should_panic: ShouldPanic::No,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1805,6 +2409,7 @@ mod tests {
                 ignore: true,
                 should_panic: ShouldPanic::No,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1825,6 +2430,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::Yes,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1845,6 +2451,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::YesWithMessage("error message"),
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1867,6 +2474,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::YesWithMessage(expected),
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1885,6 +2493,7 @@ mod tests {
                 ignore: false,
                 should_panic: ShouldPanic::Yes,
                 allow_fail: false,
+                timeout: None,
             },
             testfn: DynTestFn(Box::new(f)),
         };
@@ -1917,6 +2526,86 @@ mod tests {
         assert_eq!(opts.run_ignored, RunIgnored::Yes);
     }
 
+    #[test]
+    fn parse_test_timeout_flag() {
+        let args = vec![
+            "progname".to_string(),
+            "-Zunstable-options".to_string(),
+            "--test-timeout".to_string(),
+            "30".to_string(),
+        ];
+        let opts = parse_opts(&args).unwrap().unwrap();
+        assert_eq!(opts.test_timeout, Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn parse_process_isolation_flag() {
+        let args = vec![
+            "progname".to_string(),
+            "-Zunstable-options".to_string(),
+            "--process-isolation".to_string(),
+        ];
+        let opts = parse_opts(&args).unwrap().unwrap();
+        assert_eq!(opts.run_strategy, RunStrategy::SpawnPrimary);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn subprocess_exit_status_maps_to_result() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::ExitStatus;
+        use {calc_subprocess_result, TrAllowedFail, TR_FAILED, TR_OK};
+
+        let mut desc = TestDesc {
+            name: StaticTestName("whatever"),
+            ignore: false,
+            should_panic: ShouldPanic::No,
+            allow_fail: false,
+            timeout: None,
+        };
+        let exited = |code: i32| ExitStatus::from_raw(code << 8);
+        let aborted = ExitStatus::from_raw(::libc::SIGABRT);
+
+        assert!(calc_subprocess_result(&desc, exited(TR_OK), b"") == TrOk);
+        assert!(calc_subprocess_result(&desc, exited(TR_FAILED), b"") == TrFailed);
+        assert!(calc_subprocess_result(&desc, exited(0), b"") != TrOk);
+        assert!(calc_subprocess_result(&desc, aborted, b"") != TrOk);
+
+        desc.should_panic = ShouldPanic::YesWithMessage("error message");
+        assert!(calc_subprocess_result(&desc, aborted, b"panicked at 'an error message'") == TrOk);
+        assert!(calc_subprocess_result(&desc, aborted, b"panicked at 'foobar'") != TrOk);
+
+        desc.should_panic = ShouldPanic::No;
+        desc.allow_fail = true;
+        assert!(calc_subprocess_result(&desc, exited(1), b"") == TrAllowedFail);
+    }
+
+    #[test]
+    fn test_timeout_reports_hung_test() {
+        let desc = TestDescAndFn {
+            desc: TestDesc {
+                name: StaticTestName("whatever"),
+                ignore: false,
+                should_panic: ShouldPanic::No,
+                allow_fail: false,
+                timeout: Some(1),
+            },
+            testfn: DynTestFn(Box::new(|| thread::sleep(Duration::from_secs(60)))),
+        };
+        let mut opts = TestOpts::new();
+        opts.run_tests = true;
+        opts.test_threads = Some(1);
+
+        let mut results = Vec::new();
+        run_tests(&opts, vec![desc], |event| {
+            if let TestEvent::TeResult(_, result, _) = event {
+                results.push(result);
+            }
+            Ok(())
+        }).unwrap();
+        assert!(results == vec![TrTimedOut]);
+    }
+
     #[test]
     pub fn filter_for_ignored_option() {
         // When we run ignored tests the test filter should filter out all the
@@ -1962,6 +2651,7 @@ mod tests {
                         ignore: false,
                         should_panic: ShouldPanic::No,
                         allow_fail: false,
+                        timeout: None,
                     },
                     testfn: DynTestFn(Box::new(move || {})),
                 })
@@ -2045,6 +2735,75 @@ mod tests {
         assert_eq!(exact.len(), 1);
     }
 
+    #[test]
+    fn parse_shard_and_shuffle_flags() {
+        let args = vec![
+            "progname".to_string(),
+            "-Zunstable-options".to_string(),
+            "--shard-index".to_string(),
+            "1".to_string(),
+            "--shard-count".to_string(),
+            "3".to_string(),
+            "--shuffle=42".to_string(),
+        ];
+        let opts = parse_opts(&args).unwrap().unwrap();
+        assert_eq!(opts.shard, Some((1, 3)));
+        assert_eq!(opts.shuffle_seed, Some(42));
+
+        let args = vec![
+            "progname".to_string(),
+            "-Zunstable-options".to_string(),
+            "--shard-index".to_string(),
+            "3".to_string(),
+            "--shard-count".to_string(),
+            "3".to_string(),
+        ];
+        assert!(parse_opts(&args).unwrap().is_err());
+    }
+
+    #[test]
+    pub fn shard_and_shuffle_tests() {
+        fn tests() -> Vec<TestDescAndFn> {
+            (0..10)
+                .map(|i| TestDescAndFn {
+                    desc: TestDesc {
+                        name: DynTestName(format!("test{}", i)),
+                        ignore: false,
+                        should_panic: ShouldPanic::No,
+                        allow_fail: false,
+                        timeout: None,
+                    },
+                    testfn: DynTestFn(Box::new(move || {})),
+                })
+                .collect()
+        }
+        fn names(tests: Vec<TestDescAndFn>) -> Vec<String> {
+            tests.into_iter().map(|t| t.desc.name.to_string()).collect()
+        }
+
+        // Every test ends up in exactly one shard, whatever the seed.
+        let mut all = Vec::new();
+        for index in 0..3 {
+            let opts = TestOpts {
+                shard: Some((index, 3)),
+                shuffle_seed: Some(index as u64),
+                ..TestOpts::new()
+            };
+            all.extend(names(filter_tests(&opts, tests())));
+        }
+        all.sort();
+        assert_eq!(all, names(filter_tests(&TestOpts::new(), tests())));
+
+        // The same seed gives the same order.
+        let opts = TestOpts {
+            shuffle_seed: Some(1234),
+            ..TestOpts::new()
+        };
+        let shuffled = names(filter_tests(&opts, tests()));
+        assert_eq!(shuffled, names(filter_tests(&opts, tests())));
+        assert!(shuffled != names(filter_tests(&TestOpts::new(), tests())));
+    }
+
     #[test]
     pub fn sort_tests() {
         let mut opts = TestOpts::new();
@@ -2073,6 +2832,7 @@ mod tests {
                         ignore: false,
                         should_panic: ShouldPanic::No,
                         allow_fail: false,
+                        timeout: None,
                     },
                     testfn: DynTestFn(Box::new(testfn)),
                 };
@@ -2149,6 +2909,7 @@ mod tests {
             ignore: false,
             should_panic: ShouldPanic::No,
             allow_fail: false,
+            timeout: None,
         };
 
         ::bench::benchmark(desc, tx, true, f);
@@ -2168,6 +2929,7 @@ mod tests {
             ignore: false,
             should_panic: ShouldPanic::No,
             allow_fail: false,
+            timeout: None,
         };
 
         ::bench::benchmark(desc, tx, true, f);
@@ -20,6 +20,7 @@
 #include "llvm/IR/AutoUpgrade.h"
 #include "llvm/IR/AssemblyAnnotationWriter.h"
 #include "llvm/Support/CBindingWrapping.h"
+#include "llvm/Support/Compression.h"
 #include "llvm/Support/FileSystem.h"
 #include "llvm/Support/Host.h"
 #include "llvm/Target/TargetMachine.h"
@@ -355,7 +356,8 @@ extern "C" LLVMTargetMachineRef LLVMRustCreateTargetMachine(
     bool TrapUnreachable,
     bool Singlethread,
     bool AsmComments,
-    bool EmitStackSizeSection) {
+    bool EmitStackSizeSection,
+    bool CompressDebugSections) {
 
   auto OptLevel = fromRust(RustOptLevel);
   auto RM = fromRust(RustReloc);
@@ -395,8 +397,22 @@ extern "C" LLVMTargetMachineRef LLVMRustCreateTargetMachine(
 #if LLVM_VERSION_GE(6, 0)
   Options.EmitStackSizeSection = EmitStackSizeSection;
 
+  if (CompressDebugSections) {
+    if (!zlib::isAvailable()) {
+      LLVMRustSetLastError("LLVM was built without zlib, "
+                           "cannot compress debug sections");
+      return nullptr;
+    }
+    // Emit the DWARF sections as `SHF_COMPRESSED` zlib sections.
+    Options.CompressDebugSections = DebugCompressionType::Z;
+  }
+
   Optional<CodeModel::Model> CM;
 #else
+  if (CompressDebugSections) {
+    LLVMRustSetLastError("compressing debug sections requires LLVM 6 or later");
+    return nullptr;
+  }
   CodeModel::Model CM = CodeModel::Model::Default;
 #endif
   if (RustCM != LLVMRustCodeModel::None)
@@ -519,7 +535,7 @@ static TargetMachine::CodeGenFileType fromRust(LLVMRustFileType Type) {
 
 extern "C" LLVMRustResult
 LLVMRustWriteOutputFile(LLVMTargetMachineRef Target, LLVMPassManagerRef PMR,
-                        LLVMModuleRef M, const char *Path,
+                        LLVMModuleRef M, const char *Path, const char *DwoPath,
                         LLVMRustFileType RustFileType) {
   llvm::legacy::PassManager *PM = unwrap<llvm::legacy::PassManager>(PMR);
   auto FileType = fromRust(RustFileType);
@@ -536,8 +552,30 @@ LLVMRustWriteOutputFile(LLVMTargetMachineRef Target, LLVMPassManagerRef PMR,
 
 #if LLVM_VERSION_GE(7, 0)
   buffer_ostream BOS(OS);
+  if (DwoPath) {
+    // Split DWARF: the debug info goes to `DwoPath`, leaving skeleton units
+    // pointing there in the object file.
+    raw_fd_ostream DOS(DwoPath, EC, sys::fs::F_None);
+    if (EC) {
+      LLVMRustSetLastError(EC.message().c_str());
+      return LLVMRustResult::Failure;
+    }
+    buffer_ostream DBOS(DOS);
+    unwrap(Target)->Options.MCOptions.SplitDwarfFile = DwoPath;
+    unwrap(Target)->addPassesToEmitFile(*PM, BOS, &DBOS, FileType, false);
+    PM->run(*unwrap(M));
+    unwrap(Target)->Options.MCOptions.SplitDwarfFile = "";
+    delete PM;
+    return LLVMRustResult::Success;
+  }
   unwrap(Target)->addPassesToEmitFile(*PM, BOS, nullptr, FileType, false);
 #else
+  // Split DWARF needs LLVM 7. `-C split-debuginfo` is rejected on older
+  // versions before we get here.
+  if (DwoPath) {
+    LLVMRustSetLastError("split DWARF requires LLVM 7 or later");
+    return LLVMRustResult::Failure;
+  }
   unwrap(Target)->addPassesToEmitFile(*PM, OS, FileType, false);
 #endif
   PM->run(*unwrap(M));
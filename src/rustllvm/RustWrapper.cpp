@@ -591,7 +591,8 @@ extern "C" LLVMMetadataRef LLVMRustDIBuilderCreateFunction(
       LineNo, unwrapDI<DISubroutineType>(Ty), IsLocalToUnit, IsDefinition,
       ScopeLine, fromRust(Flags), IsOptimized, TParams,
       unwrapDIPtr<DISubprogram>(Decl));
-  unwrap<Function>(Fn)->setSubprogram(Sub);
+  if (Fn)
+    unwrap<Function>(Fn)->setSubprogram(Sub);
   return wrap(Sub);
 }
 
@@ -839,6 +840,16 @@ LLVMRustDIBuilderCreateDebugLocation(LLVMContextRef ContextRef, unsigned Line,
   return wrap(MetadataAsValue::get(Context, debug_loc.getAsMDNode()));
 }
 
+extern "C" LLVMMetadataRef
+LLVMRustDIBuilderCreateInlinedAt(LLVMContextRef ContextRef, unsigned Line,
+                                 unsigned Column, LLVMMetadataRef Scope,
+                                 LLVMMetadataRef InlinedAt) {
+  DebugLoc debug_loc = DebugLoc::get(Line, Column, unwrapDIPtr<MDNode>(Scope),
+                                     unwrapDIPtr<MDNode>(InlinedAt));
+
+  return wrap(debug_loc.getAsMDNode());
+}
+
 extern "C" int64_t LLVMRustDIBuilderCreateOpDeref() {
   return dwarf::DW_OP_deref;
 }
@@ -0,0 +1,89 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+// compile-flags:-Zprint-mono-items=eager -Zpolymorphize
+
+#![deny(dead_code)]
+#![feature(start)]
+
+use std::marker::PhantomData;
+
+fn unused<T>(x: u32) -> u32 {
+    x + 1
+}
+
+fn partially_used<A, B>(a: A) -> A {
+    a
+}
+
+fn phantom<T>(_: PhantomData<T>) -> u32 {
+    2
+}
+
+// The closures inherit `T` from their parent, which mentions it through the
+// type of the closure, so only the closures are collapsed.
+fn non_capturing<T>() -> u32 {
+    let c = |x: u32| x + 1;
+    c(1)
+}
+
+fn capturing<T>(x: u32) -> u32 {
+    let c = move |y: u32| x + y;
+    c(1)
+}
+
+fn capturing_t<T: Copy>(t: T) -> T {
+    let c = move || t;
+    c()
+}
+
+//~ MONO_ITEM fn polymorphization::start[0]
+#[start]
+fn start(_: isize, _: *const *const u8) -> isize {
+    //~ MONO_ITEM fn polymorphization::unused[0]<()>
+    let _ = unused::<u8>(1);
+    let _ = unused::<&str>(2);
+    let _ = unused::<char>(3);
+
+    //~ MONO_ITEM fn polymorphization::partially_used[0]<u8, ()>
+    let _ = partially_used::<u8, i32>(1);
+    let _ = partially_used::<u8, char>(2);
+    //~ MONO_ITEM fn polymorphization::partially_used[0]<u16, ()>
+    let _ = partially_used::<u16, i32>(3);
+
+    // `PhantomData<T>` mentions `T`, so each instance is kept.
+    //~ MONO_ITEM fn polymorphization::phantom[0]<u8>
+    let _ = phantom::<u8>(PhantomData);
+    //~ MONO_ITEM fn polymorphization::phantom[0]<char>
+    let _ = phantom::<char>(PhantomData);
+
+    //~ MONO_ITEM fn polymorphization::non_capturing[0]<u8>
+    let _ = non_capturing::<u8>();
+    //~ MONO_ITEM fn polymorphization::non_capturing[0]<char>
+    let _ = non_capturing::<char>();
+    //~ MONO_ITEM fn polymorphization::non_capturing[0]::{{closure}}[0]<(), i8, extern "rust-call" fn((u32)) -> u32>
+
+    //~ MONO_ITEM fn polymorphization::capturing[0]<u8>
+    let _ = capturing::<u8>(1);
+    //~ MONO_ITEM fn polymorphization::capturing[0]<char>
+    let _ = capturing::<char>(2);
+    //~ MONO_ITEM fn polymorphization::capturing[0]::{{closure}}[0]<(), i8, extern "rust-call" fn((u32)) -> u32, u32>
+
+    // Capturing a `T` makes the closure depend on it.
+    //~ MONO_ITEM fn polymorphization::capturing_t[0]<u8>
+    //~ MONO_ITEM fn polymorphization::capturing_t[0]::{{closure}}[0]<u8, i8, extern "rust-call" fn(()) -> u8, u8>
+    let _ = capturing_t::<u8>(1);
+    //~ MONO_ITEM fn polymorphization::capturing_t[0]<char>
+    //~ MONO_ITEM fn polymorphization::capturing_t[0]::{{closure}}[0]<char, i8, extern "rust-call" fn(()) -> char, char>
+    let _ = capturing_t::<char>('a');
+
+    0
+}
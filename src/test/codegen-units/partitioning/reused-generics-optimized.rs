@@ -0,0 +1,31 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+// compile-flags:-Zprint-mono-items=eager -O -Zreuse-upstream-generics=yes -Zincremental=tmp/partitioning-tests/reused-generics-optimized
+
+// Optimized builds don't share their own instantiations, but can be told to
+// link against the ones upstream crates share.
+
+#![crate_type="rlib"]
+
+// aux-build:reused_generics_aux.rs
+extern crate reused_generics_aux;
+
+//~ MONO_ITEM fn reused_generics_optimized::foo[0]
+pub fn foo() {
+
+    //~ MONO_ITEM fn reused_generics_aux::generic_fn[0]<u16> @@ reused_generics_aux-in-reused_generics_optimized.volatile[External]
+    let _ = reused_generics_aux::generic_fn(0u16, 1u16);
+
+    // This should not generate a monomorphization because it's already
+    // available in `reused_generics_aux`.
+    let _ = reused_generics_aux::generic_fn(0.0f32, 3.0f32);
+}
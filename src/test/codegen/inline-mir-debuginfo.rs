@@ -0,0 +1,34 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// ignore-tidy-linelength
+// ignore-windows
+// compile-flags: -g -C no-prepopulate-passes -Z inline-mir=yes
+
+// Checks that code inlined by the MIR inliner is attributed to the inlined
+// function in the debuginfo, so that backtraces still show its frame.
+
+#![crate_type = "lib"]
+
+#[inline]
+fn callee(x: u32) -> u32 {
+    x ^ 1
+}
+
+// CHECK-LABEL: @caller
+// CHECK: xor i32 {{.*}}, !dbg ![[LOC:[0-9]+]]
+// CHECK-DAG: ![[LOC]] = !DILocation({{.*}}scope: ![[CALLEE:[0-9]+]], inlinedAt: ![[CALL:[0-9]+]])
+// CHECK-DAG: ![[CALLEE]] = distinct !DISubprogram(name: "callee"
+// CHECK-DAG: ![[CALL]] = !DILocation({{.*}}scope: ![[CALLER:[0-9]+]])
+// CHECK-DAG: ![[CALLER]] = distinct !DISubprogram(name: "caller"
+#[no_mangle]
+pub fn caller(x: u32) -> u32 {
+    callee(x)
+}
@@ -0,0 +1,46 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=2
+
+
+#[allow(unused_assignments)]
+fn dead_store() -> u32 {
+    let mut y = 1;
+    y = 5;
+    y
+}
+
+fn main() {
+    // Make sure the function actually gets instantiated.
+    dead_store();
+}
+
+// END RUST SOURCE
+// START rustc.dead_store.DeadStoreElimination.before.mir
+//  bb0: {
+//      nop;
+//      _0 = const 1u32;
+//      _0 = const 5u32;
+//      nop;
+//      nop;
+//      return;
+//  }
+// END rustc.dead_store.DeadStoreElimination.before.mir
+// START rustc.dead_store.DeadStoreElimination.after.mir
+//  bb0: {
+//      nop;
+//      nop;
+//      _0 = const 5u32;
+//      nop;
+//      nop;
+//      return;
+//  }
+// END rustc.dead_store.DeadStoreElimination.after.mir
@@ -0,0 +1,54 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=2
+
+fn nrvo() -> [u8; 1024] {
+    let x = [0; 1024];
+    x
+}
+
+fn borrowed() -> [u8; 1024] {
+    let x = [0; 1024];
+    let r = &x;
+    r[0];
+    x
+}
+
+fn main() {
+    // Make sure the functions actually get instantiated.
+    nrvo();
+    borrowed();
+}
+
+// END RUST SOURCE
+// START rustc.nrvo.DestinationPropagation.before.mir
+//  bb0: {
+//      StorageLive(_1);
+//      _1 = [const 0u8; 1024];
+//      _0 = move _1;
+//      StorageDead(_1);
+//      return;
+//  }
+// END rustc.nrvo.DestinationPropagation.before.mir
+// START rustc.nrvo.DestinationPropagation.after.mir
+//  bb0: {
+//      nop;
+//      _0 = [const 0u8; 1024];
+//      nop;
+//      nop;
+//      return;
+//  }
+// END rustc.nrvo.DestinationPropagation.after.mir
+// START rustc.borrowed.DestinationPropagation.after.mir
+//  ...
+//      _0 = move _1;
+//  ...
+// END rustc.borrowed.DestinationPropagation.after.mir
@@ -0,0 +1,32 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// aux-build:inline_cross_crate_aux.rs
+
+// Checks that `#[inline]` functions from other crates get inlined.
+
+extern crate inline_cross_crate_aux;
+
+fn main() {
+    println!("{}", foo());
+}
+
+fn foo() -> u32 {
+    inline_cross_crate_aux::answer()
+}
+
+// END RUST SOURCE
+// START rustc.foo.Inline.after.mir
+// ...
+// bb0: {
+//     _0 = const 42u32;
+//     return;
+// }
+// END rustc.foo.Inline.after.mir
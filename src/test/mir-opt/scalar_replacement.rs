@@ -0,0 +1,46 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z mir-opt-level=2
+
+
+fn pair(a: u32, b: u32) -> u32 {
+    let p = (a, b);
+    p.0 + p.1
+}
+
+fn main() {
+    // Make sure the function actually gets instantiated.
+    pair(1, 2);
+}
+
+// END RUST SOURCE
+// START rustc.pair.ScalarReplacementOfAggregates.before.mir
+//  bb0: {
+//      ...
+//      (_3.0: u32) = move _4;
+//      (_3.1: u32) = move _5;
+//      ...
+//      _6 = (_3.0: u32);
+//      ...
+//      _7 = (_3.1: u32);
+//      ...
+// END rustc.pair.ScalarReplacementOfAggregates.before.mir
+// START rustc.pair.ScalarReplacementOfAggregates.after.mir
+//  bb0: {
+//      ...
+//      _9 = move _4;
+//      _10 = move _5;
+//      ...
+//      _6 = _9;
+//      ...
+//      _7 = _10;
+//      ...
+// END rustc.pair.ScalarReplacementOfAggregates.after.mir
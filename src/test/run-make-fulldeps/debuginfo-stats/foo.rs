@@ -0,0 +1,28 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "lib"]
+
+pub mod inner {
+    pub struct Pair<T> {
+        pub first: T,
+        pub second: T,
+    }
+
+    pub fn adder(n: u32) -> impl Fn(u32) -> u32 {
+        move |x| x + n
+    }
+}
+
+use inner::Pair;
+
+pub fn pairs(a: Pair<u8>, b: Pair<u32>, c: Pair<String>) -> usize {
+    a.first as usize + b.second as usize + c.first.len()
+}
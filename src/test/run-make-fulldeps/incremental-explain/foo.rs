@@ -0,0 +1,31 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod changing {
+    #[cfg(not(changed))]
+    pub fn value() -> u32 {
+        1
+    }
+
+    #[cfg(changed)]
+    pub fn value() -> u32 {
+        2
+    }
+}
+
+mod unchanged {
+    pub fn value() -> u32 {
+        3
+    }
+}
+
+fn main() {
+    println!("{}", changing::value() + unchanged::value());
+}
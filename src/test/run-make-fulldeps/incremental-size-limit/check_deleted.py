@@ -0,0 +1,28 @@
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+# Checks that `-Z incremental-size-limit` deleted the session directories of
+# exactly the given crates, in the given order, and that they are gone.
+
+import os
+import re
+import sys
+
+deleted = []
+for line in sys.stdin:
+    match = re.match(r"\[incremental\] deleted session directory `(.*)` \(\d+ bytes\) "
+                     r"to stay under the size limit$", line.rstrip("\n"))
+    if match:
+        path = match.group(1)
+        assert not os.path.exists(path), path
+        crate_dir = os.path.basename(os.path.dirname(path))
+        deleted.append(crate_dir.rsplit("-", 1)[0])
+
+assert deleted == sys.argv[1:], deleted
@@ -0,0 +1,47 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::process;
+use std::ptr;
+
+#[test]
+fn a_passes() {}
+
+#[test]
+fn aborts() {
+    process::abort();
+}
+
+#[test]
+fn exits() {
+    process::exit(0);
+}
+
+#[test]
+fn panics() {
+    panic!("boom");
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn panics_as_expected() {
+    panic!("boom");
+}
+
+#[test]
+#[cfg(unix)]
+fn segfaults() {
+    unsafe {
+        ptr::write_volatile(ptr::null_mut::<u8>(), 1);
+    }
+}
+
+#[test]
+fn z_passes() {}
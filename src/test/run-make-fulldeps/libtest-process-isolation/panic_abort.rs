@@ -0,0 +1,31 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// Built with `-C panic=abort`, where every panic takes the process down.
+
+#[test]
+fn passes() {}
+
+#[test]
+fn panics() {
+    panic!("boom");
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn panics_as_expected() {
+    panic!("boom");
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn panics_with_the_wrong_message() {
+    panic!("bang");
+}
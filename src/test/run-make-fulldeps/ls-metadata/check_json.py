@@ -0,0 +1,33 @@
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+# Checks the output of `-Z ls -Z ls-json` for `lib.rs`.
+
+import json
+import sys
+
+dump = json.load(sys.stdin)
+
+assert dump["format_version"] == 1
+root = dump["crate_root"]
+assert root["name"] == "lib"
+assert root["edition"] == "2018"
+assert int(root["hash"], 16) != 0
+
+deps = [dep["name"] for dep in dump["dependencies"]]
+assert "std" in deps and "core" in deps, deps
+
+items = [(item["kind"], item["path"]) for item in dump["exported_items"]]
+for item in [("function", "lib::top"), ("module", "lib::inner"),
+             ("function", "lib::inner::helper"), ("struct", "lib::Thing")]:
+    assert item in items, (item, items)
+
+tables = dict((table["table"], table["bytes"]) for table in dump["table_sizes"])
+assert tables["index"] > 0 and tables["def path table"] > 0, tables
@@ -0,0 +1,20 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![crate_type = "lib"]
+
+#[inline(never)]
+fn generic<T: Clone>(x: &T) -> (T, T) {
+    (x.clone(), x.clone())
+}
+
+pub fn instances() -> usize {
+    generic(&1u8).0 as usize + generic(&2u32).1 as usize + generic(&String::new()).0.len()
+}
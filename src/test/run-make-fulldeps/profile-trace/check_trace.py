@@ -0,0 +1,50 @@
+# Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+# file at the top-level directory of this distribution and at
+# http://rust-lang.org/COPYRIGHT.
+#
+# Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+# http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+# <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+# option. This file may not be copied, modified, or distributed
+# except according to those terms.
+
+# Checks that a `-Z profile-trace` file is a well-formed Chrome trace: every
+# thread is named, begin and end events nest properly and don't go back in
+# time, and the queries of `foo.rs` show up with their keys.
+
+import json
+import sys
+
+with open(sys.argv[1]) as f:
+    trace = json.load(f)
+
+named_threads = set()
+stacks = {}
+last_ts = {}
+keys = {}
+
+for event in trace["traceEvents"]:
+    tid = event["tid"]
+    if event["ph"] == "M":
+        assert event["name"] == "thread_name"
+        named_threads.add(tid)
+        continue
+
+    assert tid in named_threads, event
+    ts = float(event["ts"])
+    assert ts >= last_ts.get(tid, 0), event
+    last_ts[tid] = ts
+
+    stack = stacks.setdefault(tid, [])
+    if event["ph"] == "B":
+        stack.append(event["name"])
+        keys.setdefault(event["name"], []).append(event["args"]["key"])
+    else:
+        assert event["ph"] == "E", event
+        assert stack.pop() == event["name"], event
+
+for tid, stack in stacks.items():
+    assert not stack, (tid, stack)
+
+assert "typeck_tables_of" in keys, sorted(keys)
+assert any("square" in key for key in keys["typeck_tables_of"]), keys["typeck_tables_of"]
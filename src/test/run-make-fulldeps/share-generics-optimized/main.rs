@@ -0,0 +1,17 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+extern crate upstream;
+
+fn main() {
+    assert_eq!(upstream::use_generic_fn_f32(), (0.0, 1.0));
+    // `upstream` already instantiated this one.
+    assert_eq!(upstream::generic_fn(2.0f32, 3.0f32), (2.0, 3.0));
+}
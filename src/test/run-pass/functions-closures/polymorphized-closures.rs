@@ -0,0 +1,57 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// run-pass
+// compile-flags:-Zpolymorphize
+
+// Closures in generic functions that don't use the parameters of their
+// parent are codegened once, while each caller still refers to them with
+// its own closure type. Check that the calls, also through `Fn` trait
+// objects and function pointers, still reach the shared copy.
+
+fn non_capturing<T>() -> u32 {
+    let c = |x: u32| x + 1;
+    c(1)
+}
+
+fn capturing<T>(x: u32) -> u32 {
+    let c = move |y: u32| x + y;
+    c(1)
+}
+
+fn boxed<T>(x: u32) -> Box<dyn Fn(u32) -> u32> {
+    Box::new(move |y| x * y)
+}
+
+fn coerced<T>() -> fn(u32) -> u32 {
+    |x| x * 3
+}
+
+fn capturing_t<T: Clone>(t: T) -> T {
+    let c = move || t.clone();
+    c()
+}
+
+fn main() {
+    assert_eq!(non_capturing::<u8>(), 2);
+    assert_eq!(non_capturing::<String>(), 2);
+
+    assert_eq!(capturing::<u8>(1), 2);
+    assert_eq!(capturing::<Vec<u64>>(2), 3);
+
+    assert_eq!(boxed::<u8>(2)(3), 6);
+    assert_eq!(boxed::<char>(4)(3), 12);
+
+    assert_eq!(coerced::<u8>()(1), 3);
+    assert_eq!(coerced::<[u8; 16]>()(2), 6);
+
+    assert_eq!(capturing_t(7u8), 7);
+    assert_eq!(capturing_t(String::from("t")), "t");
+}
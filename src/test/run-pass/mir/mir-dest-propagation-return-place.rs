@@ -0,0 +1,53 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// run-pass
+// compile-flags:-Zmir-opt-level=2
+
+// Once `y` has been merged into the return place, `_0 = x` is a candidate,
+// but `x` is written in a later block while the return place still holds
+// the value to be returned. Merging `x` into the return place as well would
+// return 7.
+
+#[inline(never)]
+fn consume(x: u32) -> u32 {
+    x
+}
+
+#[inline(never)]
+fn overwritten_after_copy(flag: bool) -> u32 {
+    let mut x = 5;
+    let y = x;
+    if flag {
+        x = 7;
+        consume(x);
+    }
+    y
+}
+
+#[inline(never)]
+fn overwritten_in_loop(n: u32) -> u32 {
+    let mut x = 1;
+    let y = x;
+    let mut i = 0;
+    while i < n {
+        x = consume(x + 1);
+        i += 1;
+    }
+    consume(x);
+    y
+}
+
+fn main() {
+    assert_eq!(overwritten_after_copy(false), 5);
+    assert_eq!(overwritten_after_copy(true), 5);
+    assert_eq!(overwritten_in_loop(0), 1);
+    assert_eq!(overwritten_in_loop(3), 1);
+}
@@ -0,0 +1,54 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// run-pass
+// compile-flags:-Zinline-mir=yes -Zinline-mir-threshold=1000 -Zinline-mir-hint-threshold=1000
+
+// Checks that the MIR inliner neither keeps inlining recursive functions
+// forever nor miscompiles them, including recursion through std.
+
+#[inline]
+fn even<T: Copy>(n: u32, t: T) -> bool {
+    if n == 0 { true } else { odd(n - 1, t) }
+}
+
+#[inline]
+fn odd<T: Copy>(n: u32, t: T) -> bool {
+    if n == 0 { false } else { even(n - 1, t) }
+}
+
+#[inline]
+fn fact(n: u64) -> u64 {
+    if n == 0 { 1 } else { n * fact(n - 1) }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Tree {
+    Leaf(u32),
+    Node(Vec<Tree>),
+}
+
+#[inline]
+fn sum(tree: &Tree) -> u32 {
+    match *tree {
+        Tree::Leaf(n) => n,
+        Tree::Node(ref children) => children.iter().map(sum).sum(),
+    }
+}
+
+fn main() {
+    assert!(even(10, ()));
+    assert!(odd(7, 'x'));
+    assert_eq!(fact(10), 3628800);
+
+    let tree = Tree::Node(vec![Tree::Leaf(1), Tree::Node(vec![Tree::Leaf(2), Tree::Leaf(3)])]);
+    assert_eq!(sum(&tree), 6);
+    assert_eq!(tree.clone(), tree);
+}
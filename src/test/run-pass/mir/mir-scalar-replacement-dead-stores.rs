@@ -0,0 +1,72 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// run-pass
+// compile-flags:-Zmir-opt-level=2 -Zvalidate-mir
+
+// Exercises scalar replacement of aggregates and dead store elimination:
+// aggregates split into their fields, stores to the return place in blocks
+// that don't return themselves, stores that are read through a reference,
+// and a generator, whose return place is replaced before liveness is
+// computed.
+
+#![feature(generators, generator_trait)]
+
+use std::ops::{Generator, GeneratorState};
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+#[inline(never)]
+fn swap(pair: (u32, u32)) -> (u32, u32) {
+    let mut p = Point { x: pair.0, y: pair.1 };
+    let t = p.x;
+    p.x = p.y;
+    p.y = t;
+    (p.x, p.y)
+}
+
+#[inline(never)]
+fn early_return(flag: bool) -> u32 {
+    let mut r = 1;
+    if flag {
+        r = 2;
+    }
+    r
+}
+
+#[inline(never)]
+fn through_ref() -> u32 {
+    let mut x = 1;
+    let p = &mut x as *mut u32;
+    x = 7;
+    unsafe { *p }
+}
+
+fn main() {
+    assert_eq!(swap((1, 2)), (2, 1));
+    assert_eq!(early_return(false), 1);
+    assert_eq!(early_return(true), 2);
+    assert_eq!(through_ref(), 7);
+
+    let mut gen = || {
+        let pair = (3u32, 4u32);
+        yield pair.0;
+        yield pair.1;
+        pair.0 + pair.1
+    };
+    unsafe {
+        assert_eq!(gen.resume(), GeneratorState::Yielded(3));
+        assert_eq!(gen.resume(), GeneratorState::Yielded(4));
+        assert_eq!(gen.resume(), GeneratorState::Complete(7));
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// run-pass
+// compile-flags:-Zvalidate-mir -Zmir-opt-level=2
+
+// Checks that none of the MIR passes break the invariants checked by
+// `-Z validate-mir` on a mix of constructs: matches with guards, drops on
+// unwind paths, closures, generators, `impl Trait` and inlined calls.
+
+#![feature(generators, generator_trait)]
+
+use std::fmt::Debug;
+use std::ops::{Generator, GeneratorState};
+
+const LIMIT: u32 = 3 * 7;
+
+struct Noisy(Vec<u32>);
+
+impl Drop for Noisy {
+    fn drop(&mut self) {
+        self.0.clear();
+    }
+}
+
+#[inline]
+fn classify(x: Option<u32>) -> &'static str {
+    match x {
+        Some(n) if n > LIMIT => "big",
+        Some(0) => "zero",
+        Some(_) => "small",
+        None => "none",
+    }
+}
+
+fn make_adder(n: u32) -> impl Fn(u32) -> u32 {
+    move |x| x + n
+}
+
+fn show<T: Debug>(t: T) -> String {
+    format!("{:?}", t)
+}
+
+fn main() {
+    let mut noisy = Noisy(vec![1, 2, 3]);
+    noisy.0.push(4);
+    noisy = Noisy(noisy.0.iter().map(|x| x * 2).collect());
+    assert_eq!(noisy.0, [2, 4, 6, 8]);
+
+    assert_eq!(classify(Some(22)), "big");
+    assert_eq!(classify(Some(0)), "zero");
+    assert_eq!(classify(None), "none");
+
+    let add = make_adder(5);
+    assert_eq!(add(1), 6);
+    assert_eq!(show((1u8, [2i64; 3])), "(1, [2, 2, 2])");
+
+    let mut gen = || {
+        let s = String::from("gen");
+        yield s.len();
+        yield s.len() * 2;
+        s
+    };
+    unsafe {
+        assert_eq!(gen.resume(), GeneratorState::Yielded(3));
+        assert_eq!(gen.resume(), GeneratorState::Yielded(6));
+        assert_eq!(gen.resume(), GeneratorState::Complete(String::from("gen")));
+    }
+}
@@ -0,0 +1,20 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+// compile-flags: -Z unstable-options --apply-suggestions=diff,fixpoint
+// compile-pass
+
+#![warn(unused_parens)]
+
+fn main() {
+    let _a = (1 / (2 + 3));
+    // Only one pair of parentheses is removed per pass.
+    let _b = ((1));
+}
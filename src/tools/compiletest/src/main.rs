@@ -667,6 +667,10 @@ pub fn make_test(config: &Config, testpaths: &TestPaths) -> Vec<test::TestDescAn
                     ignore,
                     should_panic,
                     allow_fail: false,
+                    // compiletest is built against the libtest of the stage0
+                    // compiler, which doesn't have per-test timeouts yet.
+                    #[cfg(not(stage0))]
+                    timeout: None,
                 },
                 testfn: make_test_closure(config, early_props.ignore, testpaths, revision),
             }